@@ -0,0 +1,122 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::error::{AppError, AppResult, ErrorKind};
+
+/// Account used when a job names none, and the owner of tokens stored
+/// before accounts existed.
+pub const DEFAULT_ACCOUNT: &str = "default";
+
+/// A named YouTube sign-in and the channel it posts to.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Account {
+    pub name: String,
+    #[serde(rename = "channelId")]
+    pub channel_id: Option<String>,
+    #[serde(rename = "channelTitle")]
+    pub channel_title: Option<String>,
+}
+
+fn accounts_path(app: &AppHandle<Wry>) -> AppResult<PathBuf> {
+    let mut dir = match std::env::var("YTAPP_TEST_DIR") {
+        Ok(p) => PathBuf::from(p),
+        Err(_) => app.path().app_config_dir()?,
+    };
+    fs::create_dir_all(&dir)?;
+    dir.push("accounts.json");
+    Ok(dir)
+}
+
+pub fn list(app: &AppHandle<Wry>) -> AppResult<Vec<Account>> {
+    match fs::read_to_string(accounts_path(app)?) {
+        Ok(data) => Ok(serde_json::from_str(&data)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn write(app: &AppHandle<Wry>, accounts: &[Account]) -> AppResult<()> {
+    fs::write(accounts_path(app)?, serde_json::to_string_pretty(accounts)?)?;
+    Ok(())
+}
+
+/// Add an account or update the one with the same name.
+pub fn save(app: &AppHandle<Wry>, account: Account) -> AppResult<()> {
+    let mut accounts = list(app)?;
+    match accounts.iter_mut().find(|a| a.name == account.name) {
+        Some(existing) => *existing = account,
+        None => accounts.push(account),
+    }
+    write(app, &accounts)
+}
+
+/// Forget an account. Returns whether it was registered.
+pub fn remove(app: &AppHandle<Wry>, name: &str) -> AppResult<bool> {
+    let mut accounts = list(app)?;
+    let before = accounts.len();
+    accounts.retain(|a| a.name != name);
+    if accounts.len() == before {
+        return Ok(false);
+    }
+    write(app, &accounts)?;
+    Ok(true)
+}
+
+pub fn validate_name(name: &str) -> AppResult<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c));
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::new(
+            ErrorKind::InvalidInput,
+            format!("account name {:?} may only contain letters, digits, '-', '_' and '.'", name),
+        ))
+    }
+}
+
+/// The account a job runs as. Named accounts must have been added first, so
+/// a typo never starts a sign-in for an unknown channel from the queue.
+pub fn resolve(app: &AppHandle<Wry>, name: Option<&str>) -> AppResult<String> {
+    match name {
+        None | Some(DEFAULT_ACCOUNT) => Ok(DEFAULT_ACCOUNT.to_string()),
+        Some(name) if list(app)?.iter().any(|a| a.name == name) => Ok(name.to_string()),
+        Some(name) => Err(AppError::new(ErrorKind::NotFound, format!("unknown account {}", name))
+            .with_hint("Add the account before uploading with it.")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tauri::test::{mock_context, noop_assets};
+    use tauri::Builder;
+
+    #[test]
+    fn names_are_validated() {
+        assert!(validate_name("brand-channel_2").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("a/b").is_err());
+    }
+
+    #[test]
+    fn save_resolve_and_remove() {
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("YTAPP_TEST_DIR", dir.path());
+        let app = Builder::default().build(mock_context(noop_assets())).unwrap();
+        let app = app.handle();
+        assert_eq!(resolve(app, None).unwrap(), DEFAULT_ACCOUNT);
+        assert_eq!(resolve(app, Some("music")).unwrap_err().kind, ErrorKind::NotFound);
+        save(app, Account { name: "music".into(), channel_id: Some("UC1".into()), channel_title: None }).unwrap();
+        save(app, Account { name: "music".into(), channel_id: Some("UC2".into()), channel_title: None }).unwrap();
+        assert_eq!(list(app).unwrap().len(), 1);
+        assert_eq!(list(app).unwrap()[0].channel_id.as_deref(), Some("UC2"));
+        assert_eq!(resolve(app, Some("music")).unwrap(), "music");
+        assert!(remove(app, "music").unwrap());
+        assert!(!remove(app, "music").unwrap());
+    }
+}
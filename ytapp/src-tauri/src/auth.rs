@@ -0,0 +1,248 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use chrono::{DateTime, Utc};
+use google_youtube3::common::{to_body, Body};
+use google_youtube3::hyper::{header, Method, Request, StatusCode};
+use google_youtube3::hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use google_youtube3::hyper_util::client::legacy::{connect::HttpConnector, Client};
+use google_youtube3::hyper_util::rt::TokioExecutor;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Wry};
+use yup_oauth2::authenticator_delegate::{DeviceAuthResponse, DeviceFlowDelegate};
+use yup_oauth2::storage::TokenInfo;
+use yup_oauth2::ApplicationSecret;
+
+use crate::error::{AppError, AppResult, ErrorKind};
+use crate::logger::log;
+use crate::token_store::AccountTokens;
+
+/// Scope requested on sign-in; it covers every call the app makes.
+pub const YOUTUBE_SCOPE: &str = "https://www.googleapis.com/auth/youtube";
+
+const REVOKE_URI: &str = "https://oauth2.googleapis.com/revoke";
+const TOKENINFO_URI: &str = "https://oauth2.googleapis.com/tokeninfo";
+
+pub type HttpClient = Client<HttpsConnector<HttpConnector>, Body>;
+
+pub fn https_client() -> AppResult<HttpClient> {
+    Ok(Client::builder(TokioExecutor::new()).build(
+        HttpsConnectorBuilder::new()
+            .with_native_roots()?
+            .https_or_http()
+            .enable_http1()
+            .build(),
+    ))
+}
+
+/// How a sign-in asks the user to authorize the app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthFlow {
+    /// Open a browser and receive the code on a local redirect.
+    Browser,
+    /// Show a code to enter at a URL on any other device, for headless machines.
+    Device,
+}
+
+impl AuthFlow {
+    /// `device` when given, else `YOUTUBE_AUTH_FLOW=device`, else the browser.
+    pub fn choose(device: Option<bool>) -> Self {
+        let device = device.unwrap_or_else(|| {
+            std::env::var("YOUTUBE_AUTH_FLOW").map(|f| f.eq_ignore_ascii_case("device")).unwrap_or(false)
+        });
+        if device {
+            AuthFlow::Device
+        } else {
+            AuthFlow::Browser
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DeviceCode {
+    #[serde(rename = "userCode")]
+    pub user_code: String,
+    #[serde(rename = "verificationUrl")]
+    pub verification_url: String,
+}
+
+/// Shows the device flow's code in the log and sends it to the UI as a
+/// `youtube_device_code` event.
+pub struct DeviceCodePrompt {
+    app: AppHandle<Wry>,
+}
+
+impl DeviceCodePrompt {
+    pub fn new(app: AppHandle<Wry>) -> Self {
+        DeviceCodePrompt { app }
+    }
+}
+
+impl DeviceFlowDelegate for DeviceCodePrompt {
+    fn present_user_code<'a>(&'a self, resp: &'a DeviceAuthResponse) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+        Box::pin(async move {
+            let code = DeviceCode { user_code: resp.user_code.clone(), verification_url: resp.verification_uri.clone() };
+            log(&self.app, "info", &format!("to sign in, open {} and enter the code {}", code.verification_url, code.user_code));
+            let _ = self.app.emit("youtube_device_code", code);
+        })
+    }
+}
+
+/// What is stored for one set of scopes. Never includes the tokens.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct TokenStatus {
+    pub scopes: Vec<String>,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// The access token expired; it is refreshed on next use if possible.
+    pub expired: bool,
+    #[serde(rename = "hasRefreshToken")]
+    pub has_refresh_token: bool,
+    /// Whether Google accepted the tokens, when checked online.
+    pub valid: Option<bool>,
+}
+
+impl TokenStatus {
+    /// Whether the tokens can still authorize a request, as far as known.
+    pub fn usable(&self) -> bool {
+        self.valid.unwrap_or(self.has_refresh_token || !self.expired)
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct AccountStatus {
+    pub account: String,
+    pub tokens: Vec<TokenStatus>,
+}
+
+impl AccountStatus {
+    pub fn signed_in(&self) -> bool {
+        self.tokens.iter().any(TokenStatus::usable)
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SignInStatus {
+    #[serde(rename = "signedIn")]
+    pub signed_in: bool,
+    pub accounts: Vec<AccountStatus>,
+}
+
+pub fn token_status(scopes: &str, token: &TokenInfo, now: DateTime<Utc>) -> TokenStatus {
+    let expires_at = token.expires_at.and_then(|t| DateTime::from_timestamp(t.unix_timestamp(), 0));
+    TokenStatus {
+        scopes: scopes.split_whitespace().map(str::to_string).collect(),
+        expires_at,
+        expired: token.access_token.is_none() || expires_at.map(|t| t <= now).unwrap_or(false),
+        has_refresh_token: token.refresh_token.is_some(),
+        valid: None,
+    }
+}
+
+pub fn account_status(account: &str, tokens: &AccountTokens, now: DateTime<Utc>) -> AccountStatus {
+    let mut tokens: Vec<_> = tokens.iter().map(|(scopes, t)| token_status(scopes, t, now)).collect();
+    tokens.sort_by(|a, b| a.scopes.cmp(&b.scopes));
+    AccountStatus { account: account.to_string(), tokens }
+}
+
+/// Percent-encode `pairs` as an `application/x-www-form-urlencoded` body.
+fn form(pairs: &[(&str, &str)]) -> String {
+    let encode = |s: &str| {
+        s.bytes()
+            .map(|b| match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+                _ => format!("%{:02X}", b),
+            })
+            .collect::<String>()
+    };
+    pairs.iter().map(|(k, v)| format!("{}={}", k, encode(v))).collect::<Vec<_>>().join("&")
+}
+
+async fn post_form(client: &HttpClient, uri: &str, body: String) -> AppResult<StatusCode> {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(uri)
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(to_body(Some(body)))
+        .map_err(|e| e.to_string())?;
+    let response = client
+        .request(request)
+        .await
+        .map_err(|e| AppError::new(ErrorKind::Network, format!("request to {} failed", uri)).caused_by(&e))?;
+    Ok(response.status())
+}
+
+/// Ask Google whether the tokens still work. A refresh token is checked by
+/// refreshing with it, which fails once it was revoked.
+pub async fn validate(client: &HttpClient, secret: &ApplicationSecret, token: &TokenInfo) -> AppResult<bool> {
+    let status = match (&token.refresh_token, &token.access_token) {
+        (Some(refresh), _) => {
+            let body = form(&[
+                ("client_id", secret.client_id.as_str()),
+                ("client_secret", secret.client_secret.as_str()),
+                ("refresh_token", refresh.as_str()),
+                ("grant_type", "refresh_token"),
+            ]);
+            post_form(client, &secret.token_uri, body).await?
+        }
+        (None, Some(access)) => post_form(client, TOKENINFO_URI, form(&[("access_token", access.as_str())])).await?,
+        (None, None) => return Ok(false),
+    };
+    match status {
+        s if s.is_success() => Ok(true),
+        StatusCode::BAD_REQUEST => Ok(false),
+        s => Err(AppError::new(ErrorKind::Auth, format!("token validation returned {}", s))
+            .with_hint("Check the OAuth client secret.")),
+    }
+}
+
+/// Revoke the grant behind `token` at Google. Tokens Google no longer knows
+/// count as revoked.
+pub async fn revoke(client: &HttpClient, token: &TokenInfo) -> AppResult<()> {
+    // Revoking the refresh token also revokes the access tokens issued with it.
+    let Some(token) = token.refresh_token.as_ref().or(token.access_token.as_ref()) else {
+        return Ok(());
+    };
+    match post_form(client, REVOKE_URI, form(&[("token", token.as_str())])).await? {
+        s if s.is_success() || s == StatusCode::BAD_REQUEST => Ok(()),
+        s => Err(AppError::new(ErrorKind::Auth, format!("token revocation returned {}", s))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn token(access: Option<&str>, refresh: Option<&str>, expires: Option<i64>) -> TokenInfo {
+        TokenInfo {
+            access_token: access.map(Into::into),
+            refresh_token: refresh.map(Into::into),
+            expires_at: expires.map(|t| time::OffsetDateTime::from_unix_timestamp(t).unwrap()),
+            id_token: None,
+        }
+    }
+
+    #[test]
+    fn status_reports_expiry_and_refresh_tokens() {
+        let now = Utc.with_ymd_and_hms(2024, 3, 5, 12, 0, 0).unwrap();
+        let mut tokens = AccountTokens::new();
+        tokens.insert("a b".into(), token(Some("x"), None, Some(now.timestamp() - 60)));
+        tokens.insert("c".into(), token(Some("y"), Some("r"), Some(now.timestamp() - 60)));
+        let status = account_status("main", &tokens, now);
+        assert_eq!(status.tokens[0].scopes, ["a", "b"]);
+        assert!(status.tokens[0].expired && !status.tokens[0].usable());
+        assert!(status.tokens[1].usable());
+        assert!(status.signed_in());
+
+        let mut revoked = status.tokens[1].clone();
+        revoked.valid = Some(false);
+        assert!(!revoked.usable());
+        assert!(!token_status("s", &token(Some("x"), None, None), now).expired);
+    }
+
+    #[test]
+    fn forms_are_percent_encoded() {
+        assert_eq!(form(&[("token", "1//0g a+b"), ("x", "y")]), "token=1%2F%2F0g%20a%2Bb&x=y");
+    }
+}
@@ -0,0 +1,121 @@
+use crate::schema::EncodingOptions;
+
+pub const DEFAULT_FPS: u32 = 25;
+pub const DEFAULT_PRESET: &str = "medium";
+pub const DEFAULT_CRF: u32 = 23;
+pub const DEFAULT_AUDIO_BITRATE: &str = "192k";
+pub const DEFAULT_SAMPLE_RATE: u32 = 48000;
+
+/// Pick per-field values from `primary`, falling back to `fallback`.
+pub fn merge(primary: Option<&EncodingOptions>, fallback: Option<&EncodingOptions>) -> EncodingOptions {
+    let p = primary.cloned().unwrap_or_default();
+    let f = fallback.cloned().unwrap_or_default();
+    EncodingOptions {
+        preset: p.preset.or(f.preset),
+        crf: p.crf.or(f.crf),
+        video_bitrate: p.video_bitrate.or(f.video_bitrate),
+        keyframe_interval: p.keyframe_interval.or(f.keyframe_interval),
+        tune: p.tune.or(f.tune),
+        audio_bitrate: p.audio_bitrate.or(f.audio_bitrate),
+        sample_rate: p.sample_rate.or(f.sample_rate),
+    }
+}
+
+/// ffmpeg arguments for the libx264 video stream.
+///
+/// `bitrate` overrides the configured `videoBitrate`, which in turn takes
+/// precedence over CRF. Every segment of a job must use the same `fps` so the
+/// concat demuxer can join them with stream copy.
+pub fn video_args(opts: &EncodingOptions, fps: u32, bitrate: Option<&str>) -> Vec<String> {
+    let mut args = vec![
+        "-c:v".to_string(),
+        "libx264".to_string(),
+        "-pix_fmt".to_string(),
+        "yuv420p".to_string(),
+        "-preset".to_string(),
+        opts.preset.clone().unwrap_or_else(|| DEFAULT_PRESET.to_string()),
+    ];
+    if let Some(ref tune) = opts.tune {
+        args.extend(["-tune".to_string(), tune.clone()]);
+    }
+    match bitrate.or(opts.video_bitrate.as_deref()) {
+        Some(b) => args.extend([
+            "-b:v".to_string(),
+            b.to_string(),
+            "-maxrate".to_string(),
+            b.to_string(),
+            "-bufsize".to_string(),
+            double_bitrate(b),
+        ]),
+        None => args.extend(["-crf".to_string(), opts.crf.unwrap_or(DEFAULT_CRF).to_string()]),
+    }
+    let gop = opts.keyframe_interval.unwrap_or(fps * 2);
+    args.extend([
+        "-r".to_string(),
+        fps.to_string(),
+        "-g".to_string(),
+        gop.to_string(),
+        "-keyint_min".to_string(),
+        gop.to_string(),
+        "-sc_threshold".to_string(),
+        "0".to_string(),
+    ]);
+    args
+}
+
+/// ffmpeg arguments for the AAC audio stream.
+pub fn audio_args(opts: &EncodingOptions) -> Vec<String> {
+    vec![
+        "-c:a".to_string(),
+        "aac".to_string(),
+        "-b:a".to_string(),
+        opts.audio_bitrate.clone().unwrap_or_else(|| DEFAULT_AUDIO_BITRATE.to_string()),
+        "-ar".to_string(),
+        opts.sample_rate.unwrap_or(DEFAULT_SAMPLE_RATE).to_string(),
+        "-ac".to_string(),
+        "2".to_string(),
+    ]
+}
+
+/// Double a bitrate string like `8M` or `2500k` for the VBV buffer size.
+fn double_bitrate(b: &str) -> String {
+    let (num, unit) = b.split_at(b.trim_end_matches(|c: char| c.is_ascii_alphabetic()).len());
+    match num.parse::<f64>() {
+        Ok(v) => format!("{}{}", v * 2.0, unit),
+        Err(_) => b.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crf_used_without_bitrate() {
+        let args = video_args(&EncodingOptions::default(), 30, None);
+        let joined = args.join(" ");
+        assert!(joined.contains("-crf 23"));
+        assert!(joined.contains("-g 60"));
+        assert!(!joined.contains("-b:v"));
+    }
+
+    #[test]
+    fn bitrate_overrides_crf() {
+        let opts = EncodingOptions { crf: Some(18), video_bitrate: Some("8M".into()), ..Default::default() };
+        let joined = video_args(&opts, 25, None).join(" ");
+        assert!(joined.contains("-b:v 8M -maxrate 8M -bufsize 16M"));
+        assert!(!joined.contains("-crf"));
+        let joined = video_args(&opts, 25, Some("2500k")).join(" ");
+        assert!(joined.contains("-b:v 2500k"));
+        assert!(joined.contains("-bufsize 5000k"));
+    }
+
+    #[test]
+    fn merge_prefers_primary() {
+        let job = EncodingOptions { preset: Some("slow".into()), ..Default::default() };
+        let settings = EncodingOptions { preset: Some("fast".into()), crf: Some(20), ..Default::default() };
+        let merged = merge(Some(&job), Some(&settings));
+        assert_eq!(merged.preset.as_deref(), Some("slow"));
+        assert_eq!(merged.crf, Some(20));
+    }
+}
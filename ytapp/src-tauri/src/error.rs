@@ -0,0 +1,303 @@
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::ffmpeg_error::{FfmpegCause, FfmpegFailure};
+
+/// Broad category of an error, stable for the frontend to match on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    NotSignedIn,
+    Auth,
+    TokenStore,
+    FfmpegMissing,
+    /// The installed ffmpeg lacks an encoder, filter or demuxer a job needs.
+    Unsupported,
+    Ffmpeg,
+    InvalidInput,
+    NotFound,
+    DiskFull,
+    Quota,
+    /// The channel reached YouTube's upload limit. Only uploads wait for it.
+    UploadLimit,
+    Network,
+    Upload,
+    /// YouTube refused the request itself, e.g. an invalid title or a
+    /// missing permission. Sending it again fails the same way.
+    Rejected,
+    Config,
+    Canceled,
+    Io,
+    Internal,
+}
+
+/// Error returned by every command and stored on failed queue jobs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppError {
+    pub kind: ErrorKind,
+    pub message: String,
+    /// Messages of the underlying errors, outermost first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<String>,
+    /// What the user can do about it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+    /// Captured stderr when ffmpeg itself failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ffmpeg: Option<FfmpegFailure>,
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        AppError { kind, message: message.into(), sources: Vec::new(), hint: None, ffmpeg: None }
+    }
+
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    /// Record `err` and its `source()` chain below this error.
+    pub fn caused_by(mut self, err: &(dyn std::error::Error + 'static)) -> Self {
+        let mut current = Some(err);
+        while let Some(e) = current {
+            self.sources.push(e.to_string());
+            current = e.source();
+        }
+        self
+    }
+
+    pub fn canceled(what: &str) -> Self {
+        AppError::new(ErrorKind::Canceled, format!("{} canceled", what))
+    }
+
+    /// A tool that could not be started, distinguishing a missing binary
+    /// from other spawn failures.
+    pub fn spawn(program: &str, e: std::io::Error) -> Self {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            AppError::new(ErrorKind::FfmpegMissing, format!("{} not found", program))
+                .with_hint("Install ffmpeg and make sure it is on PATH.")
+        } else {
+            AppError::new(ErrorKind::Io, format!("failed to start {}", program)).caused_by(&e)
+        }
+    }
+
+    /// Whether running the same job again can succeed. Plain messages become
+    /// `Internal` errors, which are not retried: a transient failure must say
+    /// so with its kind.
+    pub fn is_retryable(&self) -> bool {
+        match self.kind {
+            ErrorKind::Network | ErrorKind::Upload | ErrorKind::Io => true,
+            ErrorKind::Ffmpeg => self.ffmpeg.as_ref().map(|f| f.cause == FfmpegCause::Unknown).unwrap_or(true),
+            _ => false,
+        }
+    }
+
+    /// Whether an upload should wait for the quota day to end instead of
+    /// failing.
+    pub fn holds_upload(&self) -> bool {
+        matches!(self.kind, ErrorKind::Quota | ErrorKind::UploadLimit)
+    }
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::new(ErrorKind::Internal, message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::new(ErrorKind::Internal, message)
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        let kind = if e.kind() == std::io::ErrorKind::NotFound {
+            ErrorKind::NotFound
+        } else if is_disk_full(&e) {
+            ErrorKind::DiskFull
+        } else {
+            ErrorKind::Io
+        };
+        AppError::new(kind, e.to_string())
+    }
+}
+
+#[cfg(unix)]
+fn is_disk_full(e: &std::io::Error) -> bool {
+    e.raw_os_error() == Some(28) // ENOSPC
+}
+
+#[cfg(windows)]
+fn is_disk_full(e: &std::io::Error) -> bool {
+    matches!(e.raw_os_error(), Some(39) | Some(112)) // ERROR_HANDLE_DISK_FULL, ERROR_DISK_FULL
+}
+
+#[cfg(not(any(unix, windows)))]
+fn is_disk_full(_e: &std::io::Error) -> bool {
+    false
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        AppError::new(ErrorKind::InvalidInput, e.to_string())
+    }
+}
+
+impl From<tauri::Error> for AppError {
+    fn from(e: tauri::Error) -> Self {
+        AppError::new(ErrorKind::Internal, e.to_string())
+    }
+}
+
+impl From<notify::Error> for AppError {
+    fn from(e: notify::Error) -> Self {
+        AppError::new(ErrorKind::Io, e.to_string())
+    }
+}
+
+impl From<FfmpegFailure> for AppError {
+    fn from(f: FfmpegFailure) -> Self {
+        let hint = match f.cause {
+            FfmpegCause::MissingCodec => Some("Install an ffmpeg build with libx264 and AAC support."),
+            FfmpegCause::UnknownFilter => Some("Update ffmpeg; the installed build lacks a filter this job needs."),
+            FfmpegCause::InvalidFont => Some("Choose an installed font or a valid font file."),
+            FfmpegCause::FileNotFound => Some("Check that every input file still exists."),
+            FfmpegCause::LibassMissing => Some("Install an ffmpeg build with libass to burn in captions."),
+            FfmpegCause::Unknown => None,
+        };
+        AppError {
+            kind: ErrorKind::Ffmpeg,
+            message: f.to_string(),
+            sources: Vec::new(),
+            hint: hint.map(String::from),
+            ffmpeg: Some(f),
+        }
+    }
+}
+
+/// Classify a YouTube API error from its message.
+pub fn api_error_kind(message: &str) -> ErrorKind {
+    let m = message.to_lowercase();
+    if m.contains("quotaexceeded") || m.contains("dailylimitexceeded") {
+        ErrorKind::Quota
+    } else if m.contains("uploadlimitexceeded") {
+        ErrorKind::UploadLimit
+    } else if m.contains("401") || m.contains("unauthorized") || m.contains("invalid_grant") || m.contains("autherror") {
+        ErrorKind::NotSignedIn
+    } else if m.contains("connection") || m.contains("timed out") || m.contains("dns") || m.contains("http error") {
+        ErrorKind::Network
+    } else if m.contains("ratelimitexceeded") {
+        ErrorKind::Upload
+    } else if m.starts_with("bad request") || matches!(http_status(&m), Some(s) if (400..500).contains(&s) && s != 429) {
+        ErrorKind::Rejected
+    } else {
+        ErrorKind::Upload
+    }
+}
+
+/// HTTP status named in an API error message: the `code` of a JSON error
+/// body, the `status` of a failed response or a `returned <status>` line.
+fn http_status(message: &str) -> Option<u16> {
+    ["\"code\":", "status:", "returned "].iter().find_map(|key| {
+        let rest = message[message.find(key)? + key.len()..].trim_start();
+        rest.get(..3)?.parse().ok()
+    })
+}
+
+/// Classify a failed YouTube API call.
+pub fn api_error(e: impl std::fmt::Display) -> AppError {
+    let message = e.to_string();
+    AppError::new(api_error_kind(&message), format!("YouTube API error: {}", message))
+}
+
+/// Read an error stored either as an `AppError` or, in queues saved by older
+/// versions, as a plain message.
+pub fn deserialize_legacy<'de, D: Deserializer<'de>>(d: D) -> Result<Option<AppError>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Stored {
+        Structured(AppError),
+        Message(String),
+    }
+    Ok(match Option::<Stored>::deserialize(d)? {
+        Some(Stored::Structured(e)) => Some(e),
+        Some(Stored::Message(m)) => Some(AppError::from(m)),
+        None => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn io_errors_are_classified() {
+        let missing = std::io::Error::new(std::io::ErrorKind::NotFound, "gone");
+        assert_eq!(AppError::from(missing).kind, ErrorKind::NotFound);
+        #[cfg(unix)]
+        assert_eq!(AppError::from(std::io::Error::from_raw_os_error(28)).kind, ErrorKind::DiskFull);
+    }
+
+    #[test]
+    fn retry_classification() {
+        assert!(!AppError::from("boom").is_retryable());
+        assert!(AppError::new(ErrorKind::Io, "busy").is_retryable());
+        assert!(AppError::new(ErrorKind::Network, "reset").is_retryable());
+        assert!(!AppError::new(ErrorKind::NotSignedIn, "no token").is_retryable());
+        assert!(!AppError::new(ErrorKind::Quota, "quota").is_retryable());
+        assert!(!api_error("Bad Request: {\"error\": {\"code\": 400, \"errors\": [{\"reason\": \"invalidTitle\"}]}}").is_retryable());
+        assert!(!api_error("Http status indicates failure: Response { status: 403, reason: \"forbidden\" }").is_retryable());
+        assert!(!api_error("captions.insert returned 403 Forbidden: {}").is_retryable());
+        assert!(api_error("Http status indicates failure: Response { status: 503 }").is_retryable());
+        assert!(api_error("{\"error\": {\"code\": 403, \"errors\": [{\"reason\": \"rateLimitExceeded\"}]}}").is_retryable());
+        assert!(api_error("videos.insert returned 429 Too Many Requests: {}").is_retryable());
+        assert!(api_error("connection reset by peer").is_retryable());
+        let codec = crate::ffmpeg_error::classify(["Unknown encoder 'libx264'"].into_iter(), Some(1));
+        assert!(!AppError::from(codec).is_retryable());
+        let other = crate::ffmpeg_error::classify(["Conversion failed!"].into_iter(), Some(1));
+        assert!(AppError::from(other).is_retryable());
+    }
+
+    #[test]
+    fn api_errors_by_message() {
+        assert_eq!(api_error_kind("Bad Request: {\"reason\": \"quotaExceeded\"}"), ErrorKind::Quota);
+        assert_eq!(api_error_kind("401 Unauthorized"), ErrorKind::NotSignedIn);
+        assert_eq!(api_error_kind("something else"), ErrorKind::Upload);
+    }
+
+    #[test]
+    fn upload_limit_is_not_the_api_quota() {
+        let e = api_error("Bad Request: {\"reason\": \"uploadLimitExceeded\"}");
+        assert_eq!(e.kind, ErrorKind::UploadLimit);
+        assert!(e.holds_upload() && !e.is_retryable());
+        assert!(AppError::new(ErrorKind::Quota, "quota").holds_upload());
+        assert!(!AppError::new(ErrorKind::Network, "reset").holds_upload());
+    }
+
+    #[test]
+    fn reads_legacy_string_errors() {
+        #[derive(Deserialize)]
+        struct Item {
+            #[serde(default, deserialize_with = "deserialize_legacy")]
+            error: Option<AppError>,
+        }
+        let old: Item = serde_json::from_str(r#"{"error": "ffmpeg failed"}"#).unwrap();
+        assert_eq!(old.error.unwrap().kind, ErrorKind::Internal);
+        let new: Item = serde_json::from_str(r#"{"error": {"kind": "quota", "message": "q"}}"#).unwrap();
+        assert_eq!(new.error.unwrap().kind, ErrorKind::Quota);
+        let none: Item = serde_json::from_str(r#"{"error": null}"#).unwrap();
+        assert!(none.error.is_none());
+    }
+}
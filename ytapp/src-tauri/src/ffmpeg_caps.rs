@@ -0,0 +1,320 @@
+use std::collections::BTreeSet;
+use std::process::Command;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use crate::error::{AppError, AppResult, ErrorKind};
+
+/// Oldest ffmpeg release the pipeline is known to work with.
+pub const MIN_VERSION: (u32, u32) = (4, 4);
+
+/// Probe result of the ffmpeg on PATH, kept for the session.
+static CACHE: Lazy<Mutex<Option<Capabilities>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComponentKind {
+    Encoder,
+    Filter,
+    Demuxer,
+}
+
+impl ComponentKind {
+    fn label(self) -> &'static str {
+        match self {
+            ComponentKind::Encoder => "encoder",
+            ComponentKind::Filter => "filter",
+            ComponentKind::Demuxer => "demuxer",
+        }
+    }
+}
+
+/// A part of the render pipeline that depends on optional ffmpeg components.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    /// H.264/AAC encoding every job needs.
+    Render,
+    /// Burned-in captions via libass.
+    Captions,
+    /// Intros and outros joined with the concat demuxer.
+    Bookends,
+    ImageWatermark,
+    /// drawtext, which needs libfreetype.
+    TextWatermark,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 5] = [
+        Feature::Render,
+        Feature::Captions,
+        Feature::Bookends,
+        Feature::ImageWatermark,
+        Feature::TextWatermark,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Feature::Render => "rendering",
+            Feature::Captions => "captions",
+            Feature::Bookends => "intros and outros",
+            Feature::ImageWatermark => "image watermarks",
+            Feature::TextWatermark => "text watermarks",
+        }
+    }
+
+    fn requirements(self) -> &'static [(ComponentKind, &'static str)] {
+        use ComponentKind::*;
+        match self {
+            Feature::Render => &[(Encoder, "libx264"), (Encoder, "aac")],
+            Feature::Captions => &[(Filter, "subtitles")],
+            Feature::Bookends => &[(Demuxer, "concat"), (Filter, "anullsrc")],
+            Feature::ImageWatermark => &[(Filter, "movie"), (Filter, "colorchannelmixer"), (Filter, "overlay")],
+            Feature::TextWatermark => &[(Filter, "drawtext")],
+        }
+    }
+}
+
+/// Availability of one feature.
+#[derive(Serialize, Clone, Debug)]
+pub struct FeatureStatus {
+    pub feature: Feature,
+    pub available: bool,
+    /// Missing components such as `filter subtitles`.
+    pub missing: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CapabilityReport {
+    pub version: Option<String>,
+    /// Whether the version is at least `minVersion`; unknown for builds
+    /// without a release number, such as git snapshots.
+    #[serde(rename = "versionSupported")]
+    pub version_supported: Option<bool>,
+    #[serde(rename = "minVersion")]
+    pub min_version: String,
+    pub features: Vec<FeatureStatus>,
+}
+
+/// Parsed output of `ffmpeg -version`, `-encoders`, `-filters` and `-demuxers`.
+#[derive(Debug, Clone, Default)]
+pub struct Capabilities {
+    pub version: Option<String>,
+    encoders: BTreeSet<String>,
+    filters: BTreeSet<String>,
+    demuxers: BTreeSet<String>,
+}
+
+impl Capabilities {
+    pub fn has(&self, kind: ComponentKind, name: &str) -> bool {
+        match kind {
+            ComponentKind::Encoder => self.encoders.contains(name),
+            ComponentKind::Filter => self.filters.contains(name),
+            ComponentKind::Demuxer => self.demuxers.contains(name),
+        }
+    }
+
+    pub fn missing(&self, feature: Feature) -> Vec<String> {
+        feature
+            .requirements()
+            .iter()
+            .filter(|(kind, name)| !self.has(*kind, name))
+            .map(|(kind, name)| format!("{} {}", kind.label(), name))
+            .collect()
+    }
+
+    /// Reject a job whose features this build cannot render.
+    pub fn require(&self, features: &[Feature]) -> AppResult<()> {
+        for feature in features {
+            let missing = self.missing(*feature);
+            if !missing.is_empty() {
+                let hint = match feature {
+                    Feature::Captions => "Install an ffmpeg build with libass to burn in captions.",
+                    Feature::TextWatermark => "Install an ffmpeg build with libfreetype for text watermarks.",
+                    _ => "Install a full ffmpeg build, such as the one from ffmpeg.org.",
+                };
+                return Err(AppError::new(
+                    ErrorKind::Unsupported,
+                    format!("ffmpeg cannot render {}: missing {}", feature.label(), missing.join(", ")),
+                )
+                .with_hint(hint));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn version_supported(&self) -> Option<bool> {
+        self.version.as_deref().and_then(version_number).map(|v| v >= MIN_VERSION)
+    }
+
+    pub fn report(&self) -> CapabilityReport {
+        CapabilityReport {
+            version: self.version.clone(),
+            version_supported: self.version_supported(),
+            min_version: format!("{}.{}", MIN_VERSION.0, MIN_VERSION.1),
+            features: Feature::ALL
+                .iter()
+                .map(|f| {
+                    let missing = self.missing(*f);
+                    FeatureStatus { feature: *f, available: missing.is_empty(), missing }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Capabilities of the ffmpeg on PATH, probed once and then cached.
+/// `refresh` probes again, e.g. after the user installed another build.
+pub fn capabilities(refresh: bool) -> AppResult<Capabilities> {
+    let mut cache = CACHE.lock().unwrap();
+    if refresh || cache.is_none() {
+        *cache = Some(probe()?);
+    }
+    Ok(cache.clone().unwrap_or_default())
+}
+
+fn probe() -> AppResult<Capabilities> {
+    Ok(Capabilities {
+        version: parse_version(&ffmpeg_stdout("-version")?),
+        encoders: parse_listing(&ffmpeg_stdout("-encoders")?),
+        filters: parse_listing(&ffmpeg_stdout("-filters")?),
+        demuxers: parse_listing(&ffmpeg_stdout("-demuxers")?),
+    })
+}
+
+fn ffmpeg_stdout(arg: &str) -> AppResult<String> {
+    let output = Command::new("ffmpeg")
+        .args(["-hide_banner", arg])
+        .output()
+        .map_err(|e| AppError::spawn("ffmpeg", e))?;
+    if !output.status.success() {
+        return Err(AppError::new(ErrorKind::Ffmpeg, format!("ffmpeg {} failed", arg)));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Version from the first line, e.g. `6.1.1-3ubuntu5` or `N-112345-gabc`.
+fn parse_version(text: &str) -> Option<String> {
+    text.lines()
+        .next()?
+        .strip_prefix("ffmpeg version ")?
+        .split_whitespace()
+        .next()
+        .map(String::from)
+}
+
+/// Major and minor release of a version string. Git snapshots (`N-…`) and
+/// date-stamped builds have no usable release number.
+fn version_number(version: &str) -> Option<(u32, u32)> {
+    let v = version.strip_prefix('n').unwrap_or(version);
+    let mut parts = v.split(|c: char| !c.is_ascii_digit());
+    let major: u32 = parts.next()?.parse().ok()?;
+    let minor = parts.next().and_then(|m| m.parse().ok()).unwrap_or(0);
+    (major < 100).then_some((major, minor))
+}
+
+fn is_separator(line: &str) -> bool {
+    let t = line.trim();
+    !t.is_empty() && t.chars().all(|c| c == '-')
+}
+
+/// Names from an `-encoders`, `-filters` or `-demuxers` listing. Entries are
+/// `<flags> <name> …` lines after a legend. Encoder and demuxer legends end
+/// with a dashed line; filters have none, but every filter entry has an
+/// `A->V` style third column.
+fn parse_listing(text: &str) -> BTreeSet<String> {
+    let separated = text.lines().any(is_separator);
+    let mut in_body = !separated;
+    let mut names = BTreeSet::new();
+    for line in text.lines() {
+        if !in_body {
+            in_body = is_separator(line);
+            continue;
+        }
+        let cols: Vec<&str> = line.split_whitespace().collect();
+        if cols.len() < 2 || cols[1] == "=" {
+            continue;
+        }
+        if !separated && !cols.get(2).is_some_and(|c| c.contains("->")) {
+            continue;
+        }
+        // Demuxers list aliases together, e.g. `mov,mp4,m4a,3gp`.
+        names.extend(cols[1].split(',').map(String::from));
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENCODERS: &str = "Encoders:
+ V..... = Video
+ A..... = Audio
+ ------
+ V....D libx264              libx264 H.264 / AVC / MPEG-4 AVC (codec h264)
+ A....D aac                  AAC (Advanced Audio Coding)
+";
+
+    const FILTERS: &str = "Filters:
+  T.. = Timeline support
+  | = Source or sink filter
+ ... anullsrc          |->A       Null audio source, return empty audio frames.
+ TSC overlay           VV->V      Overlay a video source on top of the input.
+ ... movie             |->N       Read audio and/or video stream from a movie source.
+ T.C colorchannelmixer V->V       Adjust colors by mixing color channels.
+";
+
+    const DEMUXERS: &str = "File formats:
+ D. = Demuxing supported
+ .E = Muxing supported
+ --
+ D  concat          Virtual concatenation script
+ D  mov,mp4,m4a,3gp QuickTime / MOV
+";
+
+    fn caps() -> Capabilities {
+        Capabilities {
+            version: parse_version("ffmpeg version 6.1.1-3ubuntu5 Copyright (c) 2000-2023"),
+            encoders: parse_listing(ENCODERS),
+            filters: parse_listing(FILTERS),
+            demuxers: parse_listing(DEMUXERS),
+        }
+    }
+
+    #[test]
+    fn parses_listings() {
+        let c = caps();
+        assert!(c.has(ComponentKind::Encoder, "libx264"));
+        assert!(!c.has(ComponentKind::Encoder, "V....."));
+        assert!(c.has(ComponentKind::Filter, "overlay"));
+        assert!(!c.has(ComponentKind::Filter, "="));
+        assert!(c.has(ComponentKind::Demuxer, "concat"));
+        assert!(c.has(ComponentKind::Demuxer, "mp4"));
+    }
+
+    #[test]
+    fn reports_missing_features() {
+        let c = caps();
+        assert!(c.require(&[Feature::Render, Feature::Bookends, Feature::ImageWatermark]).is_ok());
+        let err = c.require(&[Feature::Render, Feature::Captions]).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Unsupported);
+        assert!(err.message.contains("filter subtitles"));
+        let report = c.report();
+        let text = report.features.iter().find(|f| f.feature == Feature::TextWatermark).unwrap();
+        assert!(!text.available);
+        assert_eq!(report.version.as_deref(), Some("6.1.1-3ubuntu5"));
+        assert_eq!(report.version_supported, Some(true));
+    }
+
+    #[test]
+    fn version_numbers() {
+        assert_eq!(version_number("6.1.1-3ubuntu5"), Some((6, 1)));
+        assert_eq!(version_number("n4.3.2"), Some((4, 3)));
+        assert_eq!(version_number("7.0"), Some((7, 0)));
+        assert_eq!(version_number("N-112345-gabcdef"), None);
+        assert_eq!(version_number("2023-03-05-git-912ac82a3c"), None);
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use serde::{Deserialize, Serialize};
+
+/// Number of stderr lines kept while ffmpeg runs.
+const RING_LINES: usize = 200;
+/// Number of meaningful lines attached to a failure.
+const REPORT_LINES: usize = 5;
+
+/// Categorized reason an ffmpeg run failed.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FfmpegCause {
+    MissingCodec,
+    UnknownFilter,
+    InvalidFont,
+    FileNotFound,
+    LibassMissing,
+    Unknown,
+}
+
+impl FfmpegCause {
+    fn label(self) -> &'static str {
+        match self {
+            FfmpegCause::MissingCodec => "missing codec",
+            FfmpegCause::UnknownFilter => "unknown filter",
+            FfmpegCause::InvalidFont => "invalid font",
+            FfmpegCause::FileNotFound => "file not found",
+            FfmpegCause::LibassMissing => "libass missing",
+            FfmpegCause::Unknown => "error",
+        }
+    }
+}
+
+/// Failure of one ffmpeg run, with the tail of its stderr.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FfmpegFailure {
+    pub cause: FfmpegCause,
+    /// The line that identified the cause, or the last meaningful line.
+    pub summary: String,
+    /// Last meaningful stderr lines, oldest first.
+    pub lines: Vec<String>,
+    #[serde(rename = "exitCode")]
+    pub exit_code: Option<i32>,
+}
+
+impl std::fmt::Display for FfmpegFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ffmpeg failed ({}): {}", self.cause.label(), self.summary)
+    }
+}
+
+/// Bounded buffer of the latest stderr lines.
+#[derive(Default)]
+pub struct StderrRing {
+    lines: VecDeque<String>,
+}
+
+impl StderrRing {
+    pub fn push(&mut self, line: &str) {
+        if self.lines.len() == RING_LINES {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line.to_string());
+    }
+
+    /// Build the failure for a non-zero exit.
+    pub fn failure(&self, exit_code: Option<i32>) -> FfmpegFailure {
+        classify(self.lines.iter().map(|l| l.as_str()), exit_code)
+    }
+}
+
+/// Read ffmpeg's stderr on a background thread into a ring buffer.
+pub fn capture<R: Read + Send + 'static>(stderr: R) -> (Arc<Mutex<StderrRing>>, JoinHandle<()>) {
+    let ring = Arc::new(Mutex::new(StderrRing::default()));
+    let sink = ring.clone();
+    let handle = std::thread::spawn(move || {
+        // ffmpeg ends status updates with \r, so split on both terminators.
+        for chunk in BufReader::new(stderr).split(b'\n').map_while(Result::ok) {
+            let text = String::from_utf8_lossy(&chunk);
+            for line in text.split('\r').filter(|l| !l.trim().is_empty()) {
+                sink.lock().unwrap().push(line);
+            }
+        }
+    });
+    (ring, handle)
+}
+
+/// Banner, stream listings and progress lines carry no error information.
+fn is_meaningful(line: &str) -> bool {
+    const NOISE: [&str; 10] = [
+        "ffmpeg version",
+        "Input #",
+        "Output #",
+        "Stream mapping:",
+        "Press [q]",
+        "frame=",
+        "size=",
+        "video:",
+        "[out#",
+        "Guessed Channel Layout",
+    ];
+    !line.starts_with(char::is_whitespace) && !NOISE.iter().any(|n| line.starts_with(n))
+}
+
+fn cause_of(line: &str) -> Option<FfmpegCause> {
+    let l = line.to_lowercase();
+    if l.contains("libass") || l.contains("no such filter: 'subtitles'") || l.contains("no such filter: 'ass'") {
+        Some(FfmpegCause::LibassMissing)
+    } else if l.contains("no such filter") || l.contains("filter not found") {
+        Some(FfmpegCause::UnknownFilter)
+    } else if l.contains("unknown encoder") || l.contains("unknown decoder") || (l.contains("codec") && l.contains("not found")) {
+        Some(FfmpegCause::MissingCodec)
+    } else if l.contains("font") && (l.contains("cannot") || l.contains("could not") || l.contains("error") || l.contains("invalid")) {
+        Some(FfmpegCause::InvalidFont)
+    } else if l.contains("no such file or directory") || l.contains("error opening input") {
+        Some(FfmpegCause::FileNotFound)
+    } else {
+        None
+    }
+}
+
+/// Pick a cause and the lines worth showing from captured stderr.
+pub fn classify<'a>(lines: impl Iterator<Item = &'a str>, exit_code: Option<i32>) -> FfmpegFailure {
+    let meaningful: Vec<&str> = lines.filter(|l| is_meaningful(l)).collect();
+    let hit = meaningful.iter().rev().find_map(|l| cause_of(l).map(|c| (c, *l)));
+    let (cause, summary) = match hit {
+        Some((c, l)) => (c, l.to_string()),
+        None => (
+            FfmpegCause::Unknown,
+            meaningful
+                .last()
+                .map(|l| l.to_string())
+                .unwrap_or_else(|| format!("exit status {:?}", exit_code)),
+        ),
+    };
+    let start = meaningful.len().saturating_sub(REPORT_LINES);
+    FfmpegFailure {
+        cause,
+        summary: summary.trim().to_string(),
+        lines: meaningful[start..].iter().map(|l| l.to_string()).collect(),
+        exit_code,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(stderr: &str) -> FfmpegFailure {
+        classify(stderr.lines(), Some(1))
+    }
+
+    #[test]
+    fn classifies_known_patterns() {
+        assert_eq!(run("Unknown encoder 'libx264'").cause, FfmpegCause::MissingCodec);
+        assert_eq!(run("[AVFilterGraph @ 0x1] No such filter: 'subtitles'").cause, FfmpegCause::LibassMissing);
+        assert_eq!(run("[AVFilterGraph @ 0x1] No such filter: 'zoompan2'").cause, FfmpegCause::UnknownFilter);
+        assert_eq!(run("[Parsed_drawtext_0 @ 0x1] Cannot find a valid font for the family Foo").cause, FfmpegCause::InvalidFont);
+        assert_eq!(run("bg.png: No such file or directory").cause, FfmpegCause::FileNotFound);
+    }
+
+    #[test]
+    fn keeps_last_meaningful_lines() {
+        let stderr = "ffmpeg version 6.0\n  built with gcc\nInput #0, mp3, from 'a.mp3':\n  Duration: 00:01:00\nframe=  10 fps=0.0\nConversion failed!";
+        let f = run(stderr);
+        assert_eq!(f.cause, FfmpegCause::Unknown);
+        assert_eq!(f.summary, "Conversion failed!");
+        assert_eq!(f.lines, vec!["Conversion failed!".to_string()]);
+        assert_eq!(f.to_string(), "ffmpeg failed (error): Conversion failed!");
+    }
+
+    #[test]
+    fn ring_is_bounded() {
+        let mut ring = StderrRing::default();
+        for i in 0..RING_LINES + 10 {
+            ring.push(&format!("line {}", i));
+        }
+        assert_eq!(ring.lines.len(), RING_LINES);
+        assert_eq!(ring.lines.front().unwrap(), "line 10");
+    }
+}
@@ -7,6 +7,9 @@ use serde::{Serialize, Deserialize};
 use tauri::{AppHandle, Wry, Manager, Emitter};
 
 use crate::schema::GenerateParams;
+use crate::preflight::SizeEstimate;
+use crate::error::{self, AppError, AppResult};
+use crate::resumable::UploadSession;
 use crate::logger;
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -19,10 +22,38 @@ pub enum JobStatus {
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct QueueItem {
+    /// Stable id of the job. Its index shifts as jobs are removed or moved.
+    #[serde(default)]
+    pub id: u64,
     pub job: Job,
     pub status: JobStatus,
     pub retries: u32,
-    pub error: Option<String>,
+    #[serde(default, deserialize_with = "error::deserialize_legacy")]
+    pub error: Option<AppError>,
+    /// Expected disk usage, computed when the job is queued.
+    #[serde(default)]
+    pub estimate: Option<SizeEstimate>,
+    /// Unfinished resumable upload, continued when the job is retried.
+    #[serde(default, rename = "uploadSession", skip_serializing_if = "Option::is_none")]
+    pub upload_session: Option<UploadSession>,
+}
+
+impl QueueItem {
+    pub fn pending(job: Job) -> Self {
+        QueueItem { id: 0, job, status: JobStatus::Pending, retries: 0, error: None, estimate: None, upload_session: None }
+    }
+}
+
+/// Give jobs without an id, or with one already taken, a fresh id.
+fn assign_ids(q: &mut [QueueItem]) {
+    let mut next = q.iter().map(|item| item.id).max().unwrap_or(0) + 1;
+    let mut seen = std::collections::HashSet::new();
+    for item in q.iter_mut() {
+        if item.id == 0 || !seen.insert(item.id) {
+            item.id = next;
+            next += 1;
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -56,35 +87,46 @@ fn emit_changed(app: &AppHandle<Wry>) {
     logger::log(app, "info", "queue_changed");
 }
 
-fn queue_path(app: &AppHandle<Wry>) -> Result<PathBuf, String> {
+fn queue_path(app: &AppHandle<Wry>) -> AppResult<PathBuf> {
     if let Ok(p) = std::env::var("YTAPP_TEST_DIR") {
         let mut dir = PathBuf::from(p);
-        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        fs::create_dir_all(&dir)?;
         dir.push("queue.json");
         return Ok(dir);
     }
-    let mut dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
-    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let mut dir = app.path().app_config_dir()?;
+    fs::create_dir_all(&dir)?;
     dir.push("queue.json");
     Ok(dir)
 }
 
-pub fn enqueue(app: &AppHandle<Wry>, job: Job) -> Result<(), String> {
+pub fn enqueue(app: &AppHandle<Wry>, job: Job, estimate: Option<SizeEstimate>) -> AppResult<()> {
     let mut q = QUEUE.lock().unwrap();
-    q.push(QueueItem { job, status: JobStatus::Pending, retries: 0, error: None });
+    let id = q.iter().map(|item| item.id).max().unwrap_or(0) + 1;
+    q.push(QueueItem { id, estimate, ..QueueItem::pending(job) });
     NOTIFY.notify_one();
     save_queue(app)?;
     Ok(())
 }
 
-pub fn dequeue(app: &AppHandle<Wry>, retry_failed: bool, max_retries: u32) -> Result<Option<(usize, QueueItem)>, String> {
+/// Take the next runnable job. Jobs `can_start` rejects, such as uploads
+/// held until the API quota resets, stay queued.
+pub fn dequeue(
+    app: &AppHandle<Wry>,
+    retry_failed: bool,
+    max_retries: u32,
+    can_start: impl Fn(&Job) -> bool,
+) -> AppResult<Option<(usize, QueueItem)>> {
     if is_paused() {
         return Ok(None);
     }
     let mut q = QUEUE.lock().unwrap();
     for (i, item) in q.iter_mut().enumerate() {
         if item.status == JobStatus::Pending ||
-           (retry_failed && item.status == JobStatus::Failed && item.retries < max_retries) {
+           (retry_failed && item.status == JobStatus::Failed && item.retries < max_retries && is_retryable(item)) {
+            if !can_start(&item.job) {
+                continue;
+            }
             item.status = JobStatus::Running;
             save_queue(app)?;
             return Ok(Some((i, item.clone())));
@@ -93,7 +135,12 @@ pub fn dequeue(app: &AppHandle<Wry>, retry_failed: bool, max_retries: u32) -> Re
     Ok(None)
 }
 
-pub fn mark_complete(app: &AppHandle<Wry>, index: usize) -> Result<(), String> {
+/// Failed jobs are only retried when their error can be transient.
+fn is_retryable(item: &QueueItem) -> bool {
+    item.error.as_ref().map(|e| e.is_retryable()).unwrap_or(true)
+}
+
+pub fn mark_complete(app: &AppHandle<Wry>, index: usize) -> AppResult<()> {
     let mut q = QUEUE.lock().unwrap();
     if index < q.len() {
         q.remove(index);
@@ -102,19 +149,44 @@ pub fn mark_complete(app: &AppHandle<Wry>, index: usize) -> Result<(), String> {
     Ok(())
 }
 
-pub fn mark_failed(app: &AppHandle<Wry>, index: usize, error: String) -> Result<(), String> {
+/// Record a failed run. Uploads Google rejected for quota or the channel's
+/// upload limit are held instead: they go back to pending without using a
+/// retry, and `dequeue` starts them once `can_start` sees the quota reset.
+pub fn mark_failed(app: &AppHandle<Wry>, index: usize, error: AppError) -> AppResult<()> {
     let mut q = QUEUE.lock().unwrap();
     if let Some(item) = q.get_mut(index) {
-        item.status = JobStatus::Failed;
-        item.retries += 1;
+        if error.holds_upload() {
+            item.status = JobStatus::Pending;
+        } else {
+            item.status = JobStatus::Failed;
+            item.retries += 1;
+        }
         item.error = Some(error);
     }
     save_queue(app)?;
     Ok(())
 }
 
+/// Resumable upload session stored on the job with id `id`.
+pub fn upload_session(id: u64) -> Option<UploadSession> {
+    let q = QUEUE.lock().unwrap();
+    q.iter().find(|item| item.id == id).and_then(|item| item.upload_session.clone())
+}
+
+/// Store or clear the resumable upload session of the job with id `id`.
+pub fn set_upload_session(app: &AppHandle<Wry>, id: u64, session: Option<UploadSession>) -> AppResult<()> {
+    {
+        let mut q = QUEUE.lock().unwrap();
+        match q.iter_mut().find(|item| item.id == id) {
+            Some(item) => item.upload_session = session,
+            None => return Ok(()),
+        }
+    }
+    save_queue(app)
+}
+
 /// Remove a job from the queue by index.
-pub fn remove_job(app: &AppHandle<Wry>, index: usize) -> Result<(), String> {
+pub fn remove_job(app: &AppHandle<Wry>, index: usize) -> AppResult<()> {
     let mut q = QUEUE.lock().unwrap();
     if index < q.len() {
         q.remove(index);
@@ -124,7 +196,7 @@ pub fn remove_job(app: &AppHandle<Wry>, index: usize) -> Result<(), String> {
 }
 
 /// Move a job from one position to another.
-pub fn move_job(app: &AppHandle<Wry>, from: usize, to: usize) -> Result<(), String> {
+pub fn move_job(app: &AppHandle<Wry>, from: usize, to: usize) -> AppResult<()> {
     let mut q = QUEUE.lock().unwrap();
     let len = q.len();
     if from < len && to < len && from != to {
@@ -140,16 +212,16 @@ pub fn peek_all() -> Vec<QueueItem> {
     q.clone()
 }
 
-pub fn save_queue(app: &AppHandle<Wry>) -> Result<(), String> {
+pub fn save_queue(app: &AppHandle<Wry>) -> AppResult<()> {
     let path = queue_path(app)?;
     let q = QUEUE.lock().unwrap();
-    let data = serde_json::to_string(&*q).map_err(|e| e.to_string())?;
-    fs::write(path, data).map_err(|e| e.to_string())?;
+    let data = serde_json::to_string(&*q)?;
+    fs::write(path, data)?;
     emit_changed(app);
     Ok(())
 }
 
-pub fn load_queue(app: &AppHandle<Wry>) -> Result<(), String> {
+pub fn load_queue(app: &AppHandle<Wry>) -> AppResult<()> {
     let path = queue_path(app)?;
     let data = match fs::read_to_string(&path) {
         Ok(d) => d,
@@ -158,22 +230,23 @@ pub fn load_queue(app: &AppHandle<Wry>) -> Result<(), String> {
             q.clear();
             return Ok(());
         }
-        Err(e) => return Err(e.to_string()),
+        Err(e) => return Err(e.into()),
     };
-    let q_data: Vec<QueueItem> = match serde_json::from_str(&data) {
+    let mut q_data: Vec<QueueItem> = match serde_json::from_str(&data) {
         Ok(v) => v,
         Err(_) => {
-            let legacy: Vec<Job> = serde_json::from_str(&data).map_err(|e| e.to_string())?;
-            legacy.into_iter().map(|job| QueueItem { job, status: JobStatus::Pending, retries: 0, error: None }).collect()
+            let legacy: Vec<Job> = serde_json::from_str(&data)?;
+            legacy.into_iter().map(QueueItem::pending).collect()
         }
     };
+    assign_ids(&mut q_data);
     let mut q = QUEUE.lock().unwrap();
     *q = q_data;
     Ok(())
 }
 
 /// Remove all queued jobs and persist the empty queue.
-pub fn clear_queue(app: &AppHandle<Wry>) -> Result<(), String> {
+pub fn clear_queue(app: &AppHandle<Wry>) -> AppResult<()> {
     let mut q = QUEUE.lock().unwrap();
     q.clear();
     save_queue(app)?;
@@ -181,7 +254,7 @@ pub fn clear_queue(app: &AppHandle<Wry>) -> Result<(), String> {
 }
 
 /// Remove finished jobs from the queue.
-pub fn clear_completed(app: &AppHandle<Wry>) -> Result<(), String> {
+pub fn clear_completed(app: &AppHandle<Wry>) -> AppResult<()> {
     let mut q = QUEUE.lock().unwrap();
     q.retain(|item| item.status == JobStatus::Pending || item.status == JobStatus::Running);
     save_queue(app)?;
@@ -189,7 +262,7 @@ pub fn clear_completed(app: &AppHandle<Wry>) -> Result<(), String> {
 }
 
 /// Remove only failed jobs from the queue.
-pub fn clear_failed(app: &AppHandle<Wry>) -> Result<(), String> {
+pub fn clear_failed(app: &AppHandle<Wry>) -> AppResult<()> {
     let mut q = QUEUE.lock().unwrap();
     q.retain(|item| item.status != JobStatus::Failed);
     save_queue(app)?;
@@ -197,20 +270,20 @@ pub fn clear_failed(app: &AppHandle<Wry>) -> Result<(), String> {
 }
 
 /// Write the current queue state to the provided path.
-pub fn export_queue(app: &AppHandle<Wry>, dest: &str) -> Result<(), String> {
+pub fn export_queue(app: &AppHandle<Wry>, dest: &str) -> AppResult<()> {
     let src = queue_path(app)?;
-    fs::copy(src, dest).map_err(|e| e.to_string())?;
+    fs::copy(src, dest)?;
     Ok(())
 }
 
 /// Replace or append to the queue with jobs from the given file.
-pub fn import_queue(app: &AppHandle<Wry>, path: &str, append: bool) -> Result<(), String> {
-    let data = fs::read_to_string(path).map_err(|e| e.to_string())?;
+pub fn import_queue(app: &AppHandle<Wry>, path: &str, append: bool) -> AppResult<()> {
+    let data = fs::read_to_string(path)?;
     let mut items: Vec<QueueItem> = match serde_json::from_str(&data) {
         Ok(v) => v,
         Err(_) => {
-            let legacy: Vec<Job> = serde_json::from_str(&data).map_err(|e| e.to_string())?;
-            legacy.into_iter().map(|job| QueueItem { job, status: JobStatus::Pending, retries: 0, error: None }).collect()
+            let legacy: Vec<Job> = serde_json::from_str(&data)?;
+            legacy.into_iter().map(QueueItem::pending).collect()
         }
     };
     let mut q = QUEUE.lock().unwrap();
@@ -219,6 +292,7 @@ pub fn import_queue(app: &AppHandle<Wry>, path: &str, append: bool) -> Result<()
     } else {
         *q = items;
     }
+    assign_ids(&mut q);
     save_queue(app)?;
     Ok(())
 }
@@ -226,6 +300,7 @@ pub fn import_queue(app: &AppHandle<Wry>, path: &str, append: bool) -> Result<()
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::ErrorKind;
     use tauri::test::{mock_context, noop_assets};
     use tauri::Builder;
 
@@ -236,8 +311,8 @@ mod tests {
         let app = Builder::default()
             .build(mock_context(noop_assets()))
             .unwrap();
-        let params = GenerateParams { file: "a.mp3".into(), output: None, captions: None, caption_options: None, background: None, intro: None, outro: None, watermark: None, watermark_position: None, watermark_opacity: None, watermark_scale: None, width: None, height: None, fps: None, title: None, description: None, tags: None, publish_at: None, thumbnail: None, privacy: None, playlist_id: None };
-        enqueue(&app.handle(), Job::Generate { params: params.clone(), dest: "a.mp4".into() }).unwrap();
+        let params = GenerateParams { file: "a.mp3".into(), ..Default::default() };
+        enqueue(&app.handle(), Job::Generate { params: params.clone(), dest: "a.mp4".into() }, None).unwrap();
         load_queue(&app.handle()).unwrap();
         assert_eq!(peek_all().len(), 1);
         mark_failed(&app.handle(), 0, "err".into()).unwrap();
@@ -247,6 +322,84 @@ mod tests {
         assert_eq!(item.status, JobStatus::Failed);
     }
 
+    #[test]
+    fn quota_failures_are_held() {
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("YTAPP_TEST_DIR", dir.path());
+        let app = Builder::default()
+            .build(mock_context(noop_assets()))
+            .unwrap();
+        let params = GenerateParams { file: "a.mp3".into(), ..Default::default() };
+        enqueue(&app.handle(), Job::GenerateUpload { params, dest: "a.mp4".into(), thumbnail: None }, None).unwrap();
+        mark_failed(&app.handle(), 0, AppError::new(ErrorKind::Quota, "quotaExceeded")).unwrap();
+        let item = peek_all()[0].clone();
+        assert_eq!(item.status, JobStatus::Pending);
+        assert_eq!(item.retries, 0);
+        assert!(dequeue(&app.handle(), false, 3, |_| false).unwrap().is_none());
+        assert!(dequeue(&app.handle(), false, 3, |_| true).unwrap().is_some());
+    }
+
+    #[test]
+    fn upload_session_survives_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("YTAPP_TEST_DIR", dir.path());
+        let app = Builder::default()
+            .build(mock_context(noop_assets()))
+            .unwrap();
+        let video = dir.path().join("a.mp4");
+        fs::write(&video, b"video").unwrap();
+        let params = GenerateParams { file: "a.mp3".into(), ..Default::default() };
+        enqueue(&app.handle(), Job::GenerateUpload { params, dest: "a.mp4".into(), thumbnail: None }, None).unwrap();
+        let mut session = UploadSession::new("https://upload.example/s", video.to_str().unwrap()).unwrap();
+        session.offset = 3;
+        let id = peek_all()[0].id;
+        set_upload_session(&app.handle(), id, Some(session.clone())).unwrap();
+        load_queue(&app.handle()).unwrap();
+        assert_eq!(upload_session(id), Some(session));
+        set_upload_session(&app.handle(), id, None).unwrap();
+        load_queue(&app.handle()).unwrap();
+        assert_eq!(upload_session(id), None);
+    }
+
+    #[test]
+    fn upload_sessions_stay_with_their_job_when_moved() {
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("YTAPP_TEST_DIR", dir.path());
+        let app = Builder::default()
+            .build(mock_context(noop_assets()))
+            .unwrap();
+        let video = dir.path().join("a.mp4");
+        fs::write(&video, b"video").unwrap();
+        let params = GenerateParams { file: "a.mp3".into(), ..Default::default() };
+        for dest in ["a.mp4", "b.mp4"] {
+            enqueue(&app.handle(), Job::GenerateUpload { params: params.clone(), dest: dest.into(), thumbnail: None }, None).unwrap();
+        }
+        let (a, b) = (peek_all()[0].id, peek_all()[1].id);
+        assert_ne!(a, b);
+        let session = UploadSession::new("https://upload.example/s", video.to_str().unwrap()).unwrap();
+        set_upload_session(&app.handle(), a, Some(session.clone())).unwrap();
+        mark_failed(&app.handle(), 0, AppError::new(ErrorKind::Network, "connection reset")).unwrap();
+
+        move_job(&app.handle(), 1, 0).unwrap();
+        load_queue(&app.handle()).unwrap();
+        let (index, item) = dequeue(&app.handle(), true, 3, |_| true).unwrap().unwrap();
+        assert_eq!((index, item.id), (0, b));
+        assert_eq!(upload_session(item.id), None);
+        mark_complete(&app.handle(), index).unwrap();
+        let (_, item) = dequeue(&app.handle(), true, 3, |_| true).unwrap().unwrap();
+        assert_eq!(item.id, a);
+        assert_eq!(upload_session(item.id), Some(session));
+    }
+
+    #[test]
+    fn loaded_jobs_get_unique_ids() {
+        let params = GenerateParams { file: "a.mp3".into(), ..Default::default() };
+        let job = |id| QueueItem { id, ..QueueItem::pending(Job::Generate { params: params.clone(), dest: "a.mp4".into() }) };
+        let mut q = vec![job(0), job(4), job(4), job(0)];
+        assign_ids(&mut q);
+        assert_eq!(q.iter().map(|item| item.id).collect::<Vec<_>>(), [5, 4, 6, 7]);
+    }
+
     #[test]
     fn clear_failed_only_removes_failed_jobs() {
         let dir = tempfile::tempdir().unwrap();
@@ -254,9 +407,9 @@ mod tests {
         let app = Builder::default()
             .build(mock_context(noop_assets()))
             .unwrap();
-        let params = GenerateParams { file: "a.mp3".into(), output: None, captions: None, caption_options: None, background: None, intro: None, outro: None, watermark: None, watermark_position: None, watermark_opacity: None, watermark_scale: None, width: None, height: None, fps: None, title: None, description: None, tags: None, publish_at: None, thumbnail: None, privacy: None, playlist_id: None };
-        enqueue(&app.handle(), Job::Generate { params: params.clone(), dest: "a.mp4".into() }).unwrap();
-        enqueue(&app.handle(), Job::Generate { params: params.clone(), dest: "b.mp4".into() }).unwrap();
+        let params = GenerateParams { file: "a.mp3".into(), ..Default::default() };
+        enqueue(&app.handle(), Job::Generate { params: params.clone(), dest: "a.mp4".into() }, None).unwrap();
+        enqueue(&app.handle(), Job::Generate { params: params.clone(), dest: "b.mp4".into() }, None).unwrap();
         mark_failed(&app.handle(), 0, "err".into()).unwrap();
         clear_failed(&app.handle()).unwrap();
         let q = peek_all();
@@ -274,8 +427,8 @@ mod tests {
         let app = Builder::default()
             .build(mock_context(noop_assets()))
             .unwrap();
-        let params = GenerateParams { file: "a.mp3".into(), output: None, captions: None, caption_options: None, background: None, intro: None, outro: None, watermark: None, watermark_position: None, watermark_opacity: None, watermark_scale: None, width: None, height: None, fps: None, title: None, description: None, tags: None, publish_at: None, thumbnail: None, privacy: None, playlist_id: None };
-        enqueue(&app.handle(), Job::Generate { params: params.clone(), dest: "a.mp4".into() }).unwrap();
+        let params = GenerateParams { file: "a.mp3".into(), ..Default::default() };
+        enqueue(&app.handle(), Job::Generate { params: params.clone(), dest: "a.mp4".into() }, None).unwrap();
         let export_path = dir.path().join("q.json");
         export_queue(&app.handle(), export_path.to_str().unwrap()).unwrap();
         clear_queue(&app.handle()).unwrap();
@@ -295,9 +448,9 @@ mod tests {
         let app = Builder::default()
             .build(mock_context(noop_assets()))
             .unwrap();
-        let params = GenerateParams { file: "a.mp3".into(), output: None, captions: None, caption_options: None, background: None, intro: None, outro: None, watermark: None, watermark_position: None, watermark_opacity: None, watermark_scale: None, width: None, height: None, fps: None, title: None, description: None, tags: None, publish_at: None, thumbnail: None, privacy: None, playlist_id: None };
-        enqueue(&app.handle(), Job::Generate { params: params.clone(), dest: "a.mp4".into() }).unwrap();
-        let append_job = QueueItem { job: Job::Generate { params: params.clone(), dest: "b.mp4".into() }, status: JobStatus::Pending, retries: 0, error: None };
+        let params = GenerateParams { file: "a.mp3".into(), ..Default::default() };
+        enqueue(&app.handle(), Job::Generate { params: params.clone(), dest: "a.mp4".into() }, None).unwrap();
+        let append_job = QueueItem::pending(Job::Generate { params: params.clone(), dest: "b.mp4".into() });
         let import_path = dir.path().join("append.json");
         fs::write(&import_path, serde_json::to_string(&vec![append_job]).unwrap()).unwrap();
         import_queue(&app.handle(), import_path.to_str().unwrap(), true).unwrap();
@@ -5,6 +5,8 @@ use tauri::{AppHandle, Wry, Manager};
 use chrono::Utc;
 use serde::{Serialize, Deserialize};
 
+use crate::error::AppResult;
+
 #[derive(Serialize, Deserialize)]
 struct LogEntry<'a> {
     level: &'a str,
@@ -12,15 +14,15 @@ struct LogEntry<'a> {
     timestamp: String,
 }
 
-fn log_path(app: &AppHandle<Wry>) -> Result<PathBuf, String> {
+fn log_path(app: &AppHandle<Wry>) -> AppResult<PathBuf> {
     if let Ok(p) = std::env::var("YTAPP_TEST_DIR") {
         let mut dir = PathBuf::from(p);
-        create_dir_all(&dir).map_err(|e| e.to_string())?;
+        create_dir_all(&dir)?;
         dir.push("ytapp.log");
         return Ok(dir);
     }
-    let mut dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
-    create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let mut dir = app.path().app_config_dir()?;
+    create_dir_all(&dir)?;
     dir.push("ytapp.log");
     Ok(dir)
 }
@@ -45,12 +47,9 @@ pub fn read_logs(
     max_lines: usize,
     level: Option<String>,
     search: Option<String>,
-) -> Result<String, String> {
-    let path = match log_path(app) {
-        Ok(p) => p,
-        Err(e) => return Err(e),
-    };
-    let data = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+) -> AppResult<String> {
+    let path = log_path(app)?;
+    let data = std::fs::read_to_string(path)?;
     let mut lines = Vec::new();
     for line in data.lines() {
         if let Ok(entry) = serde_json::from_str::<LogEntry>(line) {
@@ -71,9 +70,10 @@ pub fn read_logs(
     Ok(lines[start..].join("\n"))
 }
 
-pub fn clear_logs(app: &AppHandle<Wry>) -> Result<(), String> {
+pub fn clear_logs(app: &AppHandle<Wry>) -> AppResult<()> {
     let path = log_path(app)?;
-    File::create(path).map(|_| ()).map_err(|e| e.to_string())
+    File::create(path)?;
+    Ok(())
 }
 
 #[cfg(test)]
@@ -1,6 +1,6 @@
 use std::{
-    fs::{self, File},
-    io::{Write, Read, Seek, SeekFrom},
+    fs,
+    io::{Read, Seek, SeekFrom},
     path::{Path, PathBuf},
     process::Command,
 };
@@ -8,31 +8,52 @@ use tauri::{command, Manager, WebviewWindow, AppHandle, Wry, Emitter};
 use tauri_plugin_process;
 use tauri_plugin_shell;
 use serde::{Deserialize, Serialize};
-use mime_guess;
 mod schema;
-use schema::{CaptionOptions, GenerateParams, Profile};
+use schema::{CaptionOptions, CaptionTrack, EncodingOptions, GenerateParams, Profile, VideoMetadata};
 use std::collections::HashMap;
 // path resolution via AppHandle.path() in Tauri v2
 use whisper_cli::{Model, Size, Whisper};
 mod model_check;
 use model_check::ensure_whisper_model;
 use google_youtube3::{api::Video, YouTube};
-use yup_oauth2::{InstalledFlowAuthenticator, InstalledFlowReturnMethod};
+use yup_oauth2::{ApplicationSecret, DeviceFlowAuthenticator, InstalledFlowAuthenticator, InstalledFlowReturnMethod};
 use yup_oauth2::authenticator::Authenticator;
-use google_youtube3::hyper_util::{
-    client::legacy::{Client, connect::HttpConnector},
-    rt::TokioExecutor,
-};
-use google_youtube3::hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use google_youtube3::hyper_util::client::legacy::connect::HttpConnector;
+use google_youtube3::hyper_rustls::HttpsConnector;
 use chrono::prelude::*;
 use walkdir::WalkDir;
 mod language;
 mod token_store;
-use token_store::EncryptedTokenStorage;
+use token_store::{EncryptedTokenStorage, TokenBundle, TokenKey};
 mod job_queue;
 use job_queue::{Job, QueueItem, enqueue, dequeue, peek_all, load_queue, clear_queue as clear_in_memory, notifier, mark_complete, mark_failed};
 mod logger;
 use logger::{log, read_logs, clear_logs};
+mod encoding;
+mod metadata;
+mod workspace;
+use workspace::Workspace;
+mod preflight;
+use preflight::SizeEstimate;
+mod render_cache;
+use render_cache::{CacheInfo, CacheKey, RenderCache};
+mod plan;
+use plan::{Recorder, RenderPlan};
+mod ffmpeg_error;
+mod ffmpeg_caps;
+use ffmpeg_caps::{CapabilityReport, Feature};
+mod error;
+use error::{api_error, AppError, AppResult, ErrorKind};
+mod post_upload;
+use post_upload::{Hub, PostStep, PostTask, UploadResult};
+mod resumable;
+use resumable::SessionDelegate;
+mod quota;
+use quota::{ApiMethod, QuotaStatus};
+mod accounts;
+mod auth;
+use accounts::{Account, DEFAULT_ACCOUNT};
+use auth::{AuthFlow, SignInStatus};
 // Dialogs previously used tauri::api; in Tauri v2 prefer plugin APIs or log
 use notify::{RecommendedWatcher, RecursiveMode, Watcher, Config, EventKind, Event, Error as NotifyError};
 use once_cell::sync::Lazy;
@@ -59,7 +80,11 @@ struct QueueProgress {
 struct QueueNotify {
     index: usize,
     success: bool,
+    /// Uploaded, but some post-upload steps failed.
+    partial: bool,
     error: Option<String>,
+    /// What the render produced, when the job rendered.
+    render: Option<GenerateResult>,
 }
 
 static WATCHER: Lazy<Mutex<Option<RecommendedWatcher>>> = Lazy::new(|| Mutex::new(None));
@@ -80,6 +105,12 @@ struct UploadOptions {
     privacy: Option<String>,
     #[serde(rename = "playlistId")]
     playlist_id: Option<String>,
+    #[serde(rename = "captionTracks")]
+    caption_tracks: Option<Vec<CaptionTrack>>,
+    #[serde(flatten)]
+    video: VideoMetadata,
+    /// Account to upload with. Defaults to the default account.
+    account: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -113,6 +144,17 @@ struct AppSettings {
     default_fps: Option<u32>,
     default_privacy: Option<String>,
     default_playlist_id: Option<String>,
+    encoding: Option<EncodingOptions>,
+    /// Directory for per-job intermediate files. Defaults to the system temp dir.
+    scratch_dir: Option<String>,
+    /// Render cache location. Defaults to the app cache dir.
+    cache_dir: Option<String>,
+    /// Render cache size cap; 0 disables caching.
+    cache_max_bytes: Option<u64>,
+    /// Log every ffmpeg/ffprobe command line of a render before it runs.
+    log_plans: Option<bool>,
+    /// Daily YouTube API quota of the OAuth project. Defaults to 10,000 units.
+    quota_daily_limit: Option<u64>,
     profiles: HashMap<String, Profile>,
 }
 
@@ -147,15 +189,46 @@ impl Default for AppSettings {
             default_fps: Some(25),
             default_privacy: Some("public".into()),
             default_playlist_id: None,
+            encoding: None,
+            scratch_dir: None,
+            cache_dir: None,
+            cache_max_bytes: None,
+            log_plans: None,
+            quota_daily_limit: None,
             profiles: HashMap::new(),
         }
     }
 }
 
-fn settings_path(app: &AppHandle<Wry>) -> Result<PathBuf, String> {
+fn scratch_root(settings: &AppSettings) -> PathBuf {
+    settings
+        .scratch_dir
+        .as_ref()
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(workspace::default_root)
+}
+
+fn render_cache(app: &AppHandle<Wry>, settings: &AppSettings) -> Option<RenderCache> {
+    let max = settings.cache_max_bytes.unwrap_or(render_cache::DEFAULT_MAX_BYTES);
+    if max == 0 {
+        return None;
+    }
+    let dir = match settings.cache_dir.as_ref().filter(|d| !d.is_empty()) {
+        Some(d) => PathBuf::from(d),
+        None => app
+            .path()
+            .app_cache_dir()
+            .map(|d| d.join("renders"))
+            .unwrap_or_else(|_| std::env::temp_dir().join("ytapp_cache")),
+    };
+    Some(RenderCache::new(dir, max))
+}
+
+fn settings_path(app: &AppHandle<Wry>) -> AppResult<PathBuf> {
     // Resolve app config directory via Tauri v2 path API
-    let mut dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
-    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let mut dir = app.path().app_config_dir()?;
+    fs::create_dir_all(&dir)?;
     dir.push("settings.json");
     Ok(dir)
 }
@@ -184,32 +257,86 @@ fn is_image(p: &str) -> bool {
     )
 }
 
-fn audio_duration(file: &str) -> Result<f64, String> {
-    let output = Command::new("ffprobe")
-        .args([
-            "-v",
-            "error",
-            "-show_entries",
-            "format=duration",
-            "-of",
-            "default=noprint_wrappers=1:nokey=1",
-            file,
-        ])
-        .output()
-        .map_err(|e| format!("failed to run ffprobe: {}", e))?;
+fn is_video(p: &str) -> bool {
+    matches!(
+        Path::new(p)
+            .extension()
+            .and_then(|s| s.to_str())
+            .map(|s| s.to_lowercase()),
+        Some(ref ext) if ["mp4", "mov", "mkv", "webm", "avi", "m4v"].contains(&ext.as_str())
+    )
+}
+
+fn duration_probe(file: &str) -> Command {
+    let mut cmd = Command::new("ffprobe");
+    cmd.args([
+        "-v",
+        "error",
+        "-show_entries",
+        "format=duration",
+        "-of",
+        "default=noprint_wrappers=1:nokey=1",
+        file,
+    ]);
+    cmd
+}
+
+fn audio_duration(file: &str) -> AppResult<f64> {
+    let output = duration_probe(file).output().map_err(|e| AppError::spawn("ffprobe", e))?;
     if !output.status.success() {
-        return Err("ffprobe failed".into());
+        return Err(AppError::new(ErrorKind::InvalidInput, format!("ffprobe could not read {}", file)));
     }
     let s = String::from_utf8_lossy(&output.stdout);
-    s.trim().parse::<f64>().map_err(|e| e.to_string())
+    s.trim()
+        .parse::<f64>()
+        .map_err(|e| AppError::new(ErrorKind::InvalidInput, format!("no duration for {}: {}", file, e)))
 }
 
-fn temp_file(name: &str) -> PathBuf {
-    let ts = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_nanos();
-    std::env::temp_dir().join(format!("{}_{}.mp4", name, ts))
+fn codec_probe(file: &str) -> Command {
+    let mut cmd = Command::new("ffprobe");
+    cmd.args([
+        "-v",
+        "error",
+        "-select_streams",
+        "a:0",
+        "-show_entries",
+        "stream=codec_name",
+        "-of",
+        "default=noprint_wrappers=1:nokey=1",
+        file,
+    ]);
+    cmd
+}
+
+/// Whether `file` has an audio stream.
+fn has_audio(file: &str) -> AppResult<bool> {
+    let output = codec_probe(file).output().map_err(|e| AppError::spawn("ffprobe", e))?;
+    if !output.status.success() {
+        return Err(AppError::new(ErrorKind::InvalidInput, format!("ffprobe could not read {}", file)));
+    }
+    Ok(!String::from_utf8_lossy(&output.stdout).trim().is_empty())
+}
+
+/// Input args for a silent stereo track of `duration` seconds.
+fn silence_input(duration: f64, enc: &EncodingOptions) -> Vec<String> {
+    vec![
+        "-f".into(),
+        "lavfi".into(),
+        "-t".into(),
+        duration.to_string(),
+        "-i".into(),
+        format!("anullsrc=r={}:cl=stereo", enc.sample_rate.unwrap_or(encoding::DEFAULT_SAMPLE_RATE)),
+    ]
+}
+
+/// Codec name of the first audio stream, e.g. `aac` or `mp3`.
+fn audio_codec(file: &str) -> Option<String> {
+    let output = codec_probe(file).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let codec = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if codec.is_empty() { None } else { Some(codec) }
 }
 
 /// Escape a path for use in ffmpeg filter arguments.
@@ -224,27 +351,38 @@ fn escape_filter_path(path: &str) -> String {
     format!("'{}'", escaped)
 }
 
-fn run_ffmpeg(mut cmd: Command) -> Result<(), String> {
-    let child = cmd.spawn().map_err(|e| e.to_string())?;
+fn run_ffmpeg(mut cmd: Command) -> AppResult<()> {
+    cmd.stderr(std::process::Stdio::piped());
+    let mut child = cmd.spawn().map_err(|e| AppError::spawn("ffmpeg", e))?;
+    let stderr = child.stderr.take().ok_or("failed to capture stderr")?;
+    let (ring, reader) = ffmpeg_error::capture(stderr);
     let child = Arc::new(Mutex::new(child));
     {
         let mut active = ACTIVE_FFMPEG.lock().unwrap();
         *active = Some(child.clone());
     }
-    let status = child.lock().unwrap().wait().map_err(|e| e.to_string())?;
+    let status = child.lock().unwrap().wait()?;
     {
         let mut active = ACTIVE_FFMPEG.lock().unwrap();
         *active = None;
     }
-    if status.success() { Ok(()) } else { Err("ffmpeg failed".into()) }
+    let _ = reader.join();
+    if status.success() {
+        Ok(())
+    } else {
+        Err(ring.lock().unwrap().failure(status.code()).into())
+    }
 }
 
-fn run_with_progress(mut cmd: Command, duration: f64, window: &WebviewWindow<Wry>, index: Option<usize>) -> Result<(), String> {
+fn run_with_progress(mut cmd: Command, duration: f64, window: &WebviewWindow<Wry>, index: Option<usize>) -> AppResult<()> {
     use std::io::{BufRead, BufReader};
     cmd.args(["-progress", "pipe:1", "-nostats"]);
     cmd.stdout(std::process::Stdio::piped());
-    let mut child_process = cmd.spawn().map_err(|e| format!("failed to start ffmpeg: {}", e))?;
+    cmd.stderr(std::process::Stdio::piped());
+    let mut child_process = cmd.spawn().map_err(|e| AppError::spawn("ffmpeg", e))?;
     let stdout = child_process.stdout.take().ok_or("failed to capture stdout")?;
+    let stderr = child_process.stderr.take().ok_or("failed to capture stderr")?;
+    let (ring, stderr_reader) = ffmpeg_error::capture(stderr);
     let child = Arc::new(Mutex::new(child_process));
     {
         let mut active = ACTIVE_FFMPEG.lock().unwrap();
@@ -265,12 +403,17 @@ fn run_with_progress(mut cmd: Command, duration: f64, window: &WebviewWindow<Wry
             }
         }
     }
-    let status = child.lock().unwrap().wait().map_err(|e| format!("ffmpeg error: {}", e))?;
+    let status = child.lock().unwrap().wait()?;
     {
         let mut active = ACTIVE_FFMPEG.lock().unwrap();
         *active = None;
     }
-    if status.success() { Ok(()) } else { Err(format!("ffmpeg exited with status {:?}", status.code())) }
+    let _ = stderr_reader.join();
+    if status.success() {
+        Ok(())
+    } else {
+        Err(ring.lock().unwrap().failure(status.code()).into())
+    }
 }
 
 struct ProgressReader<R: Read + Seek> {
@@ -308,7 +451,10 @@ impl<R: Read + Seek> Read for ProgressReader<R> {
 
 impl<R: Read + Seek> Seek for ProgressReader<R> {
     fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
-        self.inner.seek(pos)
+        // A resumed upload starts reading at the committed offset.
+        let pos = self.inner.seek(pos)?;
+        self.sent = pos;
+        Ok(pos)
     }
 }
 
@@ -323,67 +469,280 @@ fn parse_publish_at(s: &str) -> Option<DateTime<Utc>> {
     None
 }
 
-fn convert_media(path: &str, duration: Option<f64>, width: u32, height: u32, fps: Option<u32>) -> Result<PathBuf, String> {
-    let out = temp_file("segment");
+/// Encoder settings, scratch space and render cache shared by the ffmpeg
+/// steps of one job.
+struct RenderContext {
+    enc: EncodingOptions,
+    still: Option<StillRender>,
+    workspace: Workspace,
+    cache: Option<RenderCache>,
+    /// Records the job's commands for `generate_plan` or the `logPlans` setting.
+    plan: Option<Recorder>,
+}
+
+impl RenderContext {
+    /// Add `cmd` to the plan. Returns true when it must not be executed.
+    fn record(&self, cmd: &Command) -> bool {
+        match self.plan {
+            Some(ref rec) => {
+                rec.command(cmd);
+                rec.is_dry_run()
+            }
+            None => false,
+        }
+    }
+
+    fn run(&self, cmd: Command) -> AppResult<()> {
+        if self.record(&cmd) {
+            return Ok(());
+        }
+        run_ffmpeg(cmd)
+    }
+
+    fn temp_file(&self, name: &str, ext: &str) -> PathBuf {
+        let path = self.workspace.file(name, ext);
+        if let Some(ref rec) = self.plan {
+            rec.temp_file(&path);
+        }
+        path
+    }
+
+    fn write_file(&self, path: &Path, contents: &str) -> AppResult<()> {
+        if let Some(ref rec) = self.plan {
+            rec.file(path, contents);
+            if rec.is_dry_run() {
+                return Ok(());
+            }
+        }
+        Ok(fs::write(path, contents)?)
+    }
+
+    fn move_file(&self, from: &Path, to: &Path) -> AppResult<()> {
+        if let Some(ref rec) = self.plan {
+            rec.moved(from, to);
+            if rec.is_dry_run() {
+                return Ok(());
+            }
+        }
+        workspace::move_file(from, to).map_err(|e| AppError::new(ErrorKind::Io, e))
+    }
+
+    /// Duration of an intermediate file. A dry run cannot probe files that
+    /// were never written, so it uses `planned` instead.
+    fn probe_duration(&self, path: &str, planned: impl FnOnce() -> f64) -> AppResult<f64> {
+        if self.record(&duration_probe(path)) {
+            return Ok(planned());
+        }
+        audio_duration(path)
+    }
+}
+
+/// Convert an intro or outro to a segment matching `target`. Segments are
+/// cached by content, so the same intro is converted once per resolution.
+fn convert_media(path: &str, duration: Option<f64>, target: &RenditionTarget, ctx: &RenderContext) -> AppResult<PathBuf> {
+    let out = ctx.temp_file("segment", "mp4");
+    let key = ctx.cache.as_ref().and_then(|_| {
+        CacheKey::new("segment")
+            .value(&(duration, target.width, target.height, target.fps, &target.bitrate, &ctx.enc))
+            .and_then(|k| k.file(path))
+            .map(|k| k.finish())
+            .ok()
+    });
+    if let (Some(cache), Some(key)) = (&ctx.cache, &key) {
+        if cache.restore(key, "mp4", &out).unwrap_or(false) {
+            return Ok(out);
+        }
+    }
+    encode_media(path, duration, target, ctx, &out)?;
+    if let (Some(cache), Some(key)) = (&ctx.cache, &key) {
+        let _ = cache.store(key, "mp4", &out);
+    }
+    Ok(out)
+}
+
+fn encode_media(path: &str, duration: Option<f64>, target: &RenditionTarget, ctx: &RenderContext, out: &Path) -> AppResult<()> {
+    let enc = &ctx.enc;
     let mut cmd = Command::new("ffmpeg");
     cmd.arg("-y");
     if is_image(path) {
         let dur = duration.unwrap_or(5.0);
         cmd.args(["-loop", "1", "-t", &dur.to_string(), "-i", path]);
+        // Give still images a silent track so every segment has the same
+        // streams and the concat demuxer can join them without re-encoding.
+        cmd.args(silence_input(dur, enc));
+        cmd.args(["-map", "0:v", "-map", "1:a"]);
     } else {
         cmd.args(["-i", path]);
     }
-    if let Some(f) = fps {
-        cmd.args(["-r", &f.to_string()]);
-    }
-    cmd.args([
-        "-vf",
-        &format!("scale={}x{}", width, height),
-        "-pix_fmt",
-        "yuv420p",
-        "-c:v",
-        "libx264",
-        "-c:a",
-        "aac",
-        out.to_str().unwrap(),
-    ]);
-    run_ffmpeg(cmd)?;
-    Ok(out)
+    cmd.args(["-vf", &format!("scale={}x{}", target.width, target.height)]);
+    cmd.args(encoding::video_args(enc, target.fps, target.bitrate.as_deref()));
+    cmd.args(encoding::audio_args(enc));
+    cmd.arg(out.to_str().unwrap());
+    ctx.run(cmd)
 }
 
-fn build_main_section(window: Option<&WebviewWindow<Wry>>, params: &GenerateParams, duration: f64, width: u32, height: u32, fps: Option<u32>, index: Option<usize>) -> Result<PathBuf, String> {
-    let out = temp_file("main");
-    let mut cmd = Command::new("ffmpeg");
-    cmd.arg("-y");
+/// Size, rate and caption style for one output of the main section.
+#[derive(Clone)]
+struct RenditionTarget {
+    output: String,
+    width: u32,
+    height: u32,
+    fps: u32,
+    bitrate: Option<String>,
+    caption_options: Option<CaptionOptions>,
+}
 
+#[derive(Serialize, Clone)]
+struct GenerateResult {
+    /// Path of the primary output.
+    output: String,
+    /// Every file produced by the job, primary output first.
+    outputs: Vec<String>,
+    /// Whether the still-image fast path was used for the main section.
+    #[serde(rename = "fastPath")]
+    fast_path: bool,
+    /// Whether the outputs were restored from the render cache.
+    cached: bool,
+    /// Wall-clock seconds spent rendering.
+    #[serde(rename = "renderSeconds")]
+    render_seconds: f64,
+    /// Seconds of media rendered per wall-clock second.
+    speed: f64,
+    /// Chapters embedded in the outputs.
+    chapters: Vec<schema::Chapter>,
+    /// Description for YouTube with chapter timestamp lines appended.
+    description: Option<String>,
+}
 
-    match params.background.as_deref() {
-        Some(bg) if is_image(bg) => {
-            cmd.args(["-loop", "1", "-t", &duration.to_string(), "-i", bg]);
-        }
-        Some(bg) => {
-            cmd.args(["-stream_loop", "-1", "-t", &duration.to_string(), "-i", bg]);
-        }
-        None => {
-            cmd.args([
-                "-f",
-                "lavfi",
-                "-i",
-                &format!("color=c=black:s={}x{}:r=25", width, height),
-                "-t",
-                &duration.to_string(),
-            ]);
-        }
-    }
+/// Derive the output path for a rendition without an explicit `output`.
+fn rendition_output(primary: &str, name: Option<&str>, index: usize) -> String {
+    let path = Path::new(primary);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("output");
+    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("mp4");
+    let suffix = name.map(|n| n.to_string()).unwrap_or_else(|| (index + 1).to_string());
+    path.with_file_name(format!("{}_{}.{}", stem, suffix, ext))
+        .to_string_lossy()
+        .to_string()
+}
 
-    cmd.args(["-i", &params.file, "-shortest", "-pix_fmt", "yuv420p", "-c:v", "libx264", "-c:a", "aac"]);
-    if let Some(f) = fps {
-        cmd.args(["-r", &f.to_string()]);
-    }
-    let mut filter_chain = format!("scale={}x{}", width, height);
+/// Collect the primary output and any declared renditions of a job.
+fn rendition_targets(params: &GenerateParams, output: &str) -> Vec<RenditionTarget> {
+    let width = params.width.unwrap_or(1280);
+    let height = params.height.unwrap_or(720);
+    let mut targets = vec![RenditionTarget {
+        output: output.to_string(),
+        width,
+        height,
+        fps: params.fps.unwrap_or(encoding::DEFAULT_FPS),
+        bitrate: None,
+        caption_options: params.caption_options.clone(),
+    }];
+    for (i, r) in params.renditions.iter().flatten().enumerate() {
+        targets.push(RenditionTarget {
+            output: r
+                .output
+                .clone()
+                .unwrap_or_else(|| rendition_output(output, r.name.as_deref(), i)),
+            width: r.width.unwrap_or(width),
+            height: r.height.unwrap_or(height),
+            fps: r.fps.or(params.fps).unwrap_or(encoding::DEFAULT_FPS),
+            bitrate: r.bitrate.clone(),
+            caption_options: r.caption_options.clone().or_else(|| params.caption_options.clone()),
+        });
+    }
+    targets
+}
+
+/// Frame rate used for the main section when the picture never changes.
+const STILL_FPS: u32 = 1;
+/// Seconds between keyframes on the still-image path.
+const STILL_GOP_SECONDS: u32 = 10;
+
+/// Encoder shortcuts for a main section whose picture never changes.
+#[derive(Clone, Copy)]
+struct StillRender {
+    /// Encode at `STILL_FPS` instead of the target rate.
+    low_fps: bool,
+    /// Copy the source audio stream instead of re-encoding it.
+    copy_audio: bool,
+}
+
+/// True when the main section is a single static picture: an image (or
+/// plain colour) background, no burned captions and no animated watermark.
+fn is_static_render(params: &GenerateParams) -> bool {
+    let static_bg = match params.background.as_deref() {
+        Some(bg) => is_image(bg) && !bg.to_lowercase().ends_with(".gif"),
+        None => true,
+    };
+    let static_wm = params
+        .watermark
+        .as_deref()
+        .map(|wm| is_image(wm) && !wm.to_lowercase().ends_with(".gif"))
+        .unwrap_or(true);
+    let timed_wm = params.watermark_start.is_some() || params.watermark_end.is_some();
+    static_bg && static_wm && !timed_wm && params.captions.is_none() && !is_video(&params.file)
+}
+
+/// Decide which still-image shortcuts apply. Lowering the frame rate or
+/// copying audio would break stream-copy concat with intro/outro segments,
+/// so both are only used when the main section is the whole video.
+fn still_render(params: &GenerateParams, plan: Option<&Recorder>) -> Option<StillRender> {
+    if !is_static_render(params) {
+        return None;
+    }
+    let standalone = params.intro.is_none() && params.outro.is_none();
+    if let (true, Some(rec)) = (standalone, plan) {
+        rec.command(&codec_probe(&params.file));
+    }
+    Some(StillRender {
+        low_fps: standalone,
+        copy_audio: standalone && audio_codec(&params.file).as_deref() == Some("aac"),
+    })
+}
+
+/// Chapters of a job, recording the ffprobe call when they come from the
+/// source audio.
+fn resolve_chapters(params: &GenerateParams, plan: Option<&Recorder>) -> AppResult<Vec<schema::Chapter>> {
+    if let (Some(rec), "audio") = (plan, metadata::chapter_source(params)) {
+        rec.command(&metadata::chapters_probe(&params.file));
+    }
+    Ok(metadata::resolve_chapters(params)?)
+}
+
+/// Portion of the source audio rendered by `build_main_section`.
+#[derive(Clone, Copy)]
+struct TimeRange {
+    start: f64,
+    duration: f64,
+}
+
+impl TimeRange {
+    fn full(duration: f64) -> Self {
+        TimeRange { start: 0.0, duration }
+    }
+}
+
+/// Build the filter graph fragment turning `input` into the labelled `output`
+/// stream for a single rendition: scale, burned captions and watermark.
+/// `start` is the offset of the excerpt so captions keep their timing.
+fn rendition_filter(params: &GenerateParams, target: &RenditionTarget, input: &str, output: &str, start: f64) -> AppResult<String> {
+    let mut filter_chain = if is_video(&params.file) {
+        // Keep the aspect ratio of recorded footage and letterbox it.
+        format!(
+            "[{}]scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2,setsar=1",
+            input,
+            w = target.width,
+            h = target.height
+        )
+    } else {
+        format!("[{}]scale={}x{}", input, target.width, target.height)
+    };
 
     if let Some(ref caption_file) = params.captions {
-        let opts = params.caption_options.clone().unwrap_or_default();
+        if start > 0.0 {
+            filter_chain = format!("{},setpts=PTS+{:.3}/TB", filter_chain, start);
+        }
+        let opts = target.caption_options.clone().unwrap_or_default();
         let font = opts.font.unwrap_or_else(|| "Arial".to_string());
         let size = opts.size.unwrap_or(24);
         let style = opts.style.unwrap_or_else(|| "".to_string());
@@ -409,7 +768,7 @@ fn build_main_section(window: Option<&WebviewWindow<Wry>>, params: &GeneratePara
         if let Some(ref path) = opts.font_path {
             let dir = Path::new(path).parent().and_then(|p| p.to_str()).unwrap_or("");
             filter_chain = format!(
-                "{} ,subtitles={}:fontsdir={}:force_style='{}'",
+                "{},subtitles={}:fontsdir={}:force_style='{}'",
                 filter_chain,
                 escape_filter_path(caption_file),
                 dir,
@@ -417,129 +776,811 @@ fn build_main_section(window: Option<&WebviewWindow<Wry>>, params: &GeneratePara
             );
         } else {
             filter_chain = format!(
-                "{} ,subtitles={}:force_style='{}'",
+                "{},subtitles={}:force_style='{}'",
                 filter_chain,
                 escape_filter_path(caption_file),
                 style_parts.join(",")
             );
         }
+        if start > 0.0 {
+            filter_chain = format!("{},setpts=PTS-STARTPTS", filter_chain);
+        }
+    }
+
+    let enable = watermark_enable(params, start);
+    if let Some(ref text) = params.watermark_text {
+        filter_chain = format!("{},{}", filter_chain, drawtext_filter(params, text, enable.as_deref())?);
     }
 
     if let Some(ref wm) = params.watermark {
         if Path::new(wm).exists() {
-            let pos = match params
-                .watermark_position
-                .as_deref()
-                .unwrap_or("top-right")
-            {
-                "top-left" => "10:10",
-                "top-right" => "W-w-10:10",
-                "bottom-left" => "10:H-h-10",
-                _ => "W-w-10:H-h-10",
-            };
+            let (x, y) = watermark_xy(params, "W-w", "H-h")?;
             let scale = params.watermark_scale.unwrap_or(0.2);
             let opacity = params.watermark_opacity.unwrap_or(1.0);
-            filter_chain = format!(
-                "{},movie={},scale=iw*{:.3}:-1,format=rgba,colorchannelmixer=aa={:.3}[wm];[in][wm]overlay={}",
+            // GIF and video watermarks loop for the whole main section.
+            let source = if is_image(wm) && !wm.to_lowercase().ends_with(".gif") {
+                format!("movie={}", escape_filter_path(wm))
+            } else {
+                format!("movie={}:loop=0,setpts=N/FRAME_RATE/TB", escape_filter_path(wm))
+            };
+            let enable = enable.map(|e| format!(":enable='{}'", e)).unwrap_or_default();
+            return Ok(format!(
+                "{}[{out}_base];{},scale=iw*{:.3}:-1,format=rgba,colorchannelmixer=aa={:.3}[{out}_wm];[{out}_base][{out}_wm]overlay={}:{}:shortest=0{}[{out}]",
                 filter_chain,
-                escape_filter_path(wm),
+                source,
                 scale,
                 opacity,
-                pos
-            );
+                x,
+                y,
+                enable,
+                out = output
+            ));
         }
     }
 
-    cmd.args(["-vf", &filter_chain]);
+    Ok(format!("{}[{}]", filter_chain, output))
+}
 
-    cmd.arg(out.to_str().unwrap());
+/// Resolve one watermark offset into an ffmpeg expression. `free` is the
+/// space left for the watermark, e.g. `W-w` for overlay or `w-text_w` for drawtext.
+fn offset_expr(value: &str, free: &str) -> AppResult<String> {
+    let v = value.trim();
+    let invalid = || {
+        AppError::new(ErrorKind::InvalidInput, format!("invalid watermark offset {:?}", value))
+            .with_hint("Use pixels such as 20, -20 to measure from the far edge, or a percentage such as 50%.")
+    };
+    if let Some(pct) = v.strip_suffix('%') {
+        let p = pct.trim().parse::<f64>().ok().filter(|p| p.is_finite()).ok_or_else(invalid)? / 100.0;
+        Ok(format!("({})*{:.4}", free, p))
+    } else if let Some(px) = v.strip_prefix('-') {
+        Ok(format!("{}-{}", free, px.trim().parse::<u32>().map_err(|_| invalid())?))
+    } else {
+        Ok(v.parse::<u32>().map_err(|_| invalid())?.to_string())
+    }
+}
+
+/// x/y expressions for the watermark from the corner preset, the margin and
+/// any explicit `watermarkX`/`watermarkY` overrides.
+fn watermark_xy(params: &GenerateParams, free_x: &str, free_y: &str) -> AppResult<(String, String)> {
+    let m = params.watermark_margin.unwrap_or(10);
+    let pos = params.watermark_position.as_deref().unwrap_or("top-right");
+    let x = params.watermark_x.as_deref().map(|v| offset_expr(v, free_x)).transpose()?.unwrap_or_else(|| {
+        if pos.ends_with("left") {
+            m.to_string()
+        } else if pos == "center" {
+            format!("({})/2", free_x)
+        } else {
+            format!("{}-{}", free_x, m)
+        }
+    });
+    let y = params.watermark_y.as_deref().map(|v| offset_expr(v, free_y)).transpose()?.unwrap_or_else(|| {
+        if pos.starts_with("top") {
+            m.to_string()
+        } else if pos == "center" {
+            format!("({})/2", free_y)
+        } else {
+            format!("{}-{}", free_y, m)
+        }
+    });
+    Ok((x, y))
+}
+
+/// Timeline expression limiting the watermark to its display window,
+/// shifted by `start` when rendering an excerpt.
+fn watermark_enable(params: &GenerateParams, start: f64) -> Option<String> {
+    match (params.watermark_start, params.watermark_end) {
+        (None, None) => None,
+        (from, to) => {
+            let from = (from.unwrap_or(0.0) - start).max(0.0);
+            match to {
+                Some(to) => Some(format!("between(t,{:.3},{:.3})", from, to - start)),
+                None => Some(format!("gte(t,{:.3})", from)),
+            }
+        }
+    }
+}
+
+/// Escape text for a drawtext option inside a filter graph: once for the
+/// option parser and once more for the graph parser.
+fn escape_drawtext(text: &str) -> String {
+    let mut option = String::new();
+    for c in text.chars() {
+        if matches!(c, '\\' | '\'' | ':') {
+            option.push('\\');
+        }
+        option.push(c);
+    }
+    let mut graph = String::new();
+    for c in option.chars() {
+        if matches!(c, '\\' | '\'' | '[' | ']' | ',' | ';') {
+            graph.push('\\');
+        }
+        graph.push(c);
+    }
+    graph
+}
+
+fn drawtext_filter(params: &GenerateParams, text: &schema::TextWatermark, enable: Option<&str>) -> AppResult<String> {
+    let (x, y) = watermark_xy(params, "w-text_w", "h-text_h")?;
+    let opacity = params.watermark_opacity.unwrap_or(1.0);
+    let mut parts = vec![
+        format!("text={}", escape_drawtext(&text.text)),
+        "expansion=none".to_string(),
+    ];
+    match (&text.font_path, &text.font) {
+        (Some(path), _) => parts.push(format!("fontfile={}", escape_filter_path(path))),
+        (None, Some(font)) => parts.push(format!("font={}", escape_drawtext(font))),
+        (None, None) => {}
+    }
+    parts.push(format!("fontsize={}", text.size.unwrap_or(32)));
+    parts.push(format!(
+        "fontcolor={}@{:.3}",
+        text.color.as_deref().unwrap_or("white").replace('#', "0x"),
+        opacity
+    ));
+    if let Some(ref b) = text.box_color {
+        parts.push("box=1".into());
+        parts.push(format!("boxcolor={}", b.replace('#', "0x")));
+        parts.push("boxborderw=8".into());
+    }
+    if let Some(ref sc) = text.shadow_color {
+        let off = text.shadow_offset.unwrap_or(2);
+        parts.push(format!("shadowcolor={}", sc.replace('#', "0x")));
+        parts.push(format!("shadowx={}", off));
+        parts.push(format!("shadowy={}", off));
+    }
+    parts.push(format!("x={}", x));
+    parts.push(format!("y={}", y));
+    if let Some(e) = enable {
+        parts.push(format!("enable='{}'", e));
+    }
+    Ok(format!("drawtext={}", parts.join(":")))
+}
+
+/// Add the looped background input followed by the source audio input.
+fn add_background_input(cmd: &mut Command, params: &GenerateParams, range: TimeRange, first: &RenditionTarget, low_fps: bool) {
+    let duration = range.duration;
+    match params.background.as_deref() {
+        Some(bg) if is_image(bg) && low_fps => {
+            cmd.args(["-loop", "1", "-framerate", &STILL_FPS.to_string(), "-t", &duration.to_string(), "-i", bg]);
+        }
+        Some(bg) if is_image(bg) => {
+            cmd.args(["-loop", "1", "-t", &duration.to_string(), "-i", bg]);
+        }
+        Some(bg) => {
+            cmd.args(["-stream_loop", "-1", "-t", &duration.to_string(), "-i", bg]);
+        }
+        None => {
+            cmd.args([
+                "-f",
+                "lavfi",
+                "-i",
+                &format!(
+                    "color=c=black:s={}x{}:r={}",
+                    first.width,
+                    first.height,
+                    if low_fps { STILL_FPS } else { 25 }
+                ),
+                "-t",
+                &duration.to_string(),
+            ]);
+        }
+    }
+    if range.start > 0.0 {
+        cmd.args(["-ss", &range.start.to_string(), "-t", &duration.to_string()]);
+    }
+    cmd.args(["-i", &params.file]);
+}
+
+/// Render the main section once per target from a single decode of the
+/// background and audio, splitting the video stream in the filter graph.
+fn build_main_section(window: Option<&WebviewWindow<Wry>>, params: &GenerateParams, range: TimeRange, targets: &[RenditionTarget], ctx: &RenderContext, index: Option<usize>) -> AppResult<Vec<PathBuf>> {
+    let first = targets.first().ok_or("no output targets")?;
+    let duration = range.duration;
+    let mut cmd = Command::new("ffmpeg");
+    cmd.arg("-y");
+
+    let still = ctx.still;
+    let low_fps = still.map(|s| s.low_fps).unwrap_or(false);
+    let mut enc = ctx.enc.clone();
+    if still.is_some() {
+        enc.tune = Some("stillimage".into());
+    }
+
+    // Video inputs bring their own picture, so the background is skipped
+    // and both streams come from the first input. A video without sound
+    // gets a silent track so it still concatenates with intro and outro.
+    let video_input = is_video(&params.file);
+    let silent_video = video_input && {
+        if let Some(rec) = &ctx.plan {
+            rec.command(&codec_probe(&params.file));
+        }
+        !has_audio(&params.file)?
+    };
+    let audio_map = if video_input && !silent_video { "0:a" } else { "1:a" };
+    if video_input {
+        if range.start > 0.0 {
+            cmd.args(["-ss", &range.start.to_string()]);
+        }
+        cmd.args(["-t", &duration.to_string(), "-i", &params.file]);
+        if silent_video {
+            cmd.args(silence_input(duration, &enc));
+        }
+    } else {
+        add_background_input(&mut cmd, params, range, first, low_fps);
+    }
+
+    let mut graph = Vec::new();
+    let inputs: Vec<String> = if targets.len() == 1 {
+        vec!["0:v".to_string()]
+    } else {
+        let labels: Vec<String> = (0..targets.len()).map(|i| format!("s{}", i)).collect();
+        graph.push(format!(
+            "[0:v]split={}{}",
+            targets.len(),
+            labels.iter().map(|l| format!("[{}]", l)).collect::<String>()
+        ));
+        labels
+    };
+    for (i, target) in targets.iter().enumerate() {
+        graph.push(rendition_filter(params, target, &inputs[i], &format!("v{}", i), range.start)?);
+    }
+    cmd.args(["-filter_complex", &graph.join(";")]);
+
+    let mut outs = Vec::new();
+    for (i, target) in targets.iter().enumerate() {
+        let out = ctx.temp_file(&format!("main{}", i), "mp4");
+        cmd.args(["-map", &format!("[v{}]", i), "-map", audio_map, "-shortest"]);
+        let fps = if low_fps { STILL_FPS } else { target.fps };
+        let mut target_enc = enc.clone();
+        if still.is_some() && target_enc.keyframe_interval.is_none() {
+            target_enc.keyframe_interval = Some(fps * STILL_GOP_SECONDS);
+        }
+        cmd.args(encoding::video_args(&target_enc, fps, target.bitrate.as_deref()));
+        if still.map(|s| s.copy_audio).unwrap_or(false) {
+            cmd.args(["-c:a", "copy"]);
+        } else {
+            cmd.args(encoding::audio_args(&target_enc));
+        }
+        cmd.arg(out.to_str().unwrap());
+        outs.push(out);
+    }
+
+    if ctx.record(&cmd) {
+        return Ok(outs);
+    }
     if let Some(w) = window {
         run_with_progress(cmd, duration, w, index)?;
     } else {
         run_ffmpeg(cmd)?;
     }
-    Ok(out)
+    Ok(outs)
 }
 
-#[command]
-fn generate_video(window: WebviewWindow<Wry>, params: GenerateParams, queue_index: Option<usize>) -> Result<String, String> {
-    log(&window.app_handle(), "info", "generate_video start");
+/// Write title, description, episode tags and chapters into an MP4 in place.
+fn embed_metadata(output: &str, ffmetadata: &str, ctx: &RenderContext) -> AppResult<()> {
+    let meta_path = ctx.temp_file("meta", "txt");
+    ctx.write_file(&meta_path, ffmetadata)?;
+    let tagged = ctx.temp_file("tagged", "mp4");
+    let mut cmd = Command::new("ffmpeg");
+    cmd.args([
+        "-y",
+        "-i",
+        output,
+        "-i",
+        meta_path.to_str().unwrap(),
+        "-map",
+        "0",
+        "-map_metadata",
+        "1",
+        "-map_chapters",
+        "1",
+        "-c",
+        "copy",
+        tagged.to_str().unwrap(),
+    ]);
+    ctx.run(cmd)?;
+    ctx.move_file(&tagged, Path::new(output))
+}
+
+/// Join the rendered segments of one target into its final output file.
+fn concat_segments(segments: &[PathBuf], output_path: &str, ctx: &RenderContext) -> AppResult<()> {
+    if segments.len() == 1 {
+        return ctx.move_file(&segments[0], Path::new(output_path));
+    }
+    let list_path = ctx.temp_file("list", "txt");
+    let list: String = segments
+        .iter()
+        .map(|seg| format!("file '{}'\n", seg.to_string_lossy()))
+        .collect();
+    ctx.write_file(&list_path, &list)?;
+    let mut cmd = Command::new("ffmpeg");
+    cmd.args([
+        "-y",
+        "-f",
+        "concat",
+        "-safe",
+        "0",
+        "-i",
+        list_path.to_str().unwrap(),
+        "-c",
+        "copy",
+        output_path,
+    ]);
+    ctx.run(cmd)
+}
+
+/// Expected length of an intro or outro segment: images are shown for five
+/// seconds, videos play in full.
+fn bookend_duration(path: &str) -> f64 {
+    if is_image(path) {
+        5.0
+    } else {
+        audio_duration(path).unwrap_or(5.0)
+    }
+}
+
+/// Mix the contents of an input file into `key` under `label` and drop its
+/// path from the params, so moved or renamed inputs still hit the cache.
+/// Values that are not files, like a background colour, stay in the params.
+fn hash_input(key: CacheKey, label: &str, slot: &mut Option<String>) -> AppResult<CacheKey> {
+    match slot.take() {
+        Some(path) if Path::new(&path).is_file() => Ok(key.value(label)?.file(&path)?),
+        other => {
+            *slot = other;
+            Ok(key)
+        }
+    }
+}
+
+/// Cache keys for the finished outputs of a job, one per target, built from
+/// the input contents plus every param that changes the rendered file.
+fn output_cache_keys(params: &GenerateParams, targets: &[RenditionTarget], enc: &EncodingOptions) -> AppResult<Vec<String>> {
+    let mut p = params.clone();
+    let mut file = Some(std::mem::take(&mut p.file));
+    let mut key = hash_input(CacheKey::new("output"), "file", &mut file)?;
+    p.file = file.unwrap_or_default();
+    key = hash_input(key, "background", &mut p.background)?;
+    key = hash_input(key, "captions", &mut p.captions)?;
+    key = hash_input(key, "watermark", &mut p.watermark)?;
+    key = hash_input(key, "intro", &mut p.intro)?;
+    key = hash_input(key, "outro", &mut p.outro)?;
+    // Fonts count by content too, so a font updated in place renders anew.
+    if let Some(o) = p.caption_options.as_mut() {
+        key = hash_input(key, "captionFont", &mut o.font_path)?;
+    }
+    if let Some(t) = p.watermark_text.as_mut() {
+        key = hash_input(key, "textFont", &mut t.font_path)?;
+    }
+    for (i, o) in p.renditions.iter_mut().flatten().filter_map(|r| r.caption_options.as_mut()).enumerate() {
+        key = hash_input(key.value(&i)?, "renditionFont", &mut o.font_path)?;
+    }
+    // Destinations and upload settings do not change the rendered file.
+    p.output = None;
+    p.thumbnail = None;
+    p.tags = None;
+    p.publish_at = None;
+    p.privacy = None;
+    p.playlist_id = None;
+    p.caption_tracks = None;
+    p.video = VideoMetadata::default();
+    p.account = None;
+    for r in p.renditions.iter_mut().flatten() {
+        r.output = None;
+    }
+    let key = key.value(&p)?.value(enc)?;
+    (0..targets.len()).map(|i| Ok(key.clone().value(&i)?.finish())).collect()
+}
+
+/// Estimate scratch and output usage of a job. Segments of every target stay
+/// in the workspace until the job ends, and a tagged copy of each output is
+/// assumed since chapters may only be known after probing the source.
+fn estimate_size(
+    params: &GenerateParams,
+    targets: &[RenditionTarget],
+    enc: &EncodingOptions,
+    still: Option<StillRender>,
+    duration: f64,
+) -> SizeEstimate {
+    let bookends: f64 = [params.intro.as_deref(), params.outro.as_deref()]
+        .into_iter()
+        .flatten()
+        .map(bookend_duration)
+        .sum();
+    let audio = preflight::audio_bitrate(enc);
+    let mut estimate = SizeEstimate::default();
+    for target in targets {
+        let main_fps = if still.map(|s| s.low_fps).unwrap_or(false) { STILL_FPS } else { target.fps };
+        let bitrate = target.bitrate.as_deref();
+        let main = preflight::stream_bytes(
+            preflight::video_bitrate(enc, target.width, target.height, main_fps, bitrate),
+            audio,
+            duration,
+        );
+        let extra = preflight::stream_bytes(
+            preflight::video_bitrate(enc, target.width, target.height, target.fps, bitrate),
+            audio,
+            bookends,
+        );
+        estimate.output_bytes += main + extra;
+        estimate.intermediate_bytes += 2 * (main + extra);
+    }
+    estimate
+}
+
+/// Size estimate shown next to a queued job. Probing fails for files that
+/// are still being written, in which case the job is queued without one.
+fn job_estimate(app: &AppHandle<Wry>, job: &Job) -> Option<SizeEstimate> {
+    let (params, dest) = match job {
+        Job::Generate { params, dest } | Job::GenerateUpload { params, dest, .. } => (params, dest),
+    };
+    let settings = load_settings(app.clone()).unwrap_or_default();
+    let duration = audio_duration(&params.file).ok()?;
+    let enc = encoding::merge(params.encoding.as_ref(), settings.encoding.as_ref());
+    Some(estimate_size(params, &rendition_targets(params, dest), &enc, still_render(params, None), duration))
+}
+
+/// ffmpeg features a job depends on, checked against the installed build
+/// before anything is rendered.
+fn required_features(params: &GenerateParams) -> Vec<Feature> {
+    let mut features = vec![Feature::Render];
+    if params.captions.is_some() {
+        features.push(Feature::Captions);
+    }
+    if params.intro.is_some() || params.outro.is_some() {
+        features.push(Feature::Bookends);
+    }
+    if params.watermark.as_deref().map(|w| Path::new(w).exists()).unwrap_or(false) {
+        features.push(Feature::ImageWatermark);
+    }
+    if params.watermark_text.is_some() {
+        features.push(Feature::TextWatermark);
+    }
+    features
+}
+
+fn render_video(window: &WebviewWindow<Wry>, params: &GenerateParams, queue_index: Option<usize>) -> AppResult<GenerateResult> {
+    let app = window.app_handle();
+    let settings = load_settings(app.clone()).unwrap_or_default();
+    let recorder = settings.log_plans.unwrap_or(false).then(|| Recorder::logging(app.clone()));
+    render_job(app, Some(window), params, &settings, queue_index, recorder).map(|(result, _)| result)
+}
+
+/// Shared body of real renders and dry runs. With a dry-run recorder the
+/// ffmpeg steps are only recorded; input files are still probed so the plan
+/// reflects the resolved durations, chapters and fast paths.
+fn render_job(
+    app: &AppHandle<Wry>,
+    window: Option<&WebviewWindow<Wry>>,
+    params: &GenerateParams,
+    settings: &AppSettings,
+    queue_index: Option<usize>,
+    recorder: Option<Recorder>,
+) -> AppResult<(GenerateResult, Option<RenderPlan>)> {
+    let dry_run = recorder.as_ref().map(|r| r.is_dry_run()).unwrap_or(false);
+    log(app, "info", if dry_run { "generate_plan start" } else { "generate_video start" });
     let output_path = params
         .output
         .clone()
         .unwrap_or_else(|| "output.mp4".to_string());
 
-    if let Some(parent) = Path::new(&output_path).parent() {
-        if !parent.as_os_str().is_empty() {
-            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    let targets = rendition_targets(params, &output_path);
+    if !dry_run {
+        for target in &targets {
+            if let Some(parent) = Path::new(&target.output).parent() {
+                if !parent.as_os_str().is_empty() {
+                    fs::create_dir_all(parent)?;
+                }
+            }
         }
     }
 
-    let width = params.width.unwrap_or(1280);
-    let height = params.height.unwrap_or(720);
-
+    let started = std::time::Instant::now();
+    if let Some(ref rec) = recorder {
+        rec.command(&duration_probe(&params.file));
+    }
     let duration = audio_duration(&params.file)?;
-    let _ = window.emit("generate_progress", 0f64);
-    let main = build_main_section(Some(&window), &params, duration, width, height, params.fps, queue_index)?;
+    let enc = encoding::merge(params.encoding.as_ref(), settings.encoding.as_ref());
+    let still = still_render(params, recorder.as_ref());
+    let scratch = scratch_root(settings);
+    // A plan lists the full pipeline, so it never reads from the cache.
+    let cache = if dry_run { None } else { render_cache(app, settings) };
+    let output_keys = cache.as_ref().and_then(|_| output_cache_keys(params, &targets, &enc).ok());
+    if let (Some(cache), Some(keys)) = (&cache, &output_keys) {
+        let restored = targets
+            .iter()
+            .zip(keys)
+            .all(|(t, key)| cache.restore(key, "mp4", Path::new(&t.output)).unwrap_or(false));
+        if restored {
+            log(app, "info", "generate_video reused cached render");
+            if let Some(w) = window {
+                let _ = w.emit("generate_progress", 100f64);
+            }
+            let chapters = resolve_chapters(params, recorder.as_ref())?;
+            let offset = params.intro.as_deref().map(bookend_duration).unwrap_or(0.0);
+            let description = metadata::description_with_chapters(params.description.as_deref(), &chapters, offset);
+            let outputs: Vec<String> = targets.into_iter().map(|t| t.output).collect();
+            let plan = recorder.map(|r| r.finish(outputs.clone()));
+            return Ok((
+                GenerateResult {
+                    output: output_path,
+                    outputs,
+                    fast_path: still.is_some(),
+                    cached: true,
+                    render_seconds: started.elapsed().as_secs_f64(),
+                    speed: 0.0,
+                    chapters,
+                    description,
+                },
+                plan,
+            ));
+        }
+    }
+    if !dry_run {
+        ffmpeg_caps::capabilities(false)?.require(&required_features(params))?;
+    }
+    let estimate = estimate_size(params, &targets, &enc, still, duration);
+    log(
+        app,
+        "info",
+        &format!(
+            "estimated size: {} output, {} scratch",
+            preflight::format_bytes(estimate.output_bytes),
+            preflight::format_bytes(estimate.intermediate_bytes)
+        ),
+    );
+    if !dry_run {
+        let mut needs = vec![
+            (scratch.as_path(), estimate.intermediate_bytes),
+            (Path::new(&output_path), estimate.output_bytes),
+        ];
+        // Segments and outputs are copied into the cache as they finish.
+        if let Some(cache) = &cache {
+            needs.push((cache.dir(), cache.store_bytes(estimate.intermediate_bytes + estimate.output_bytes)));
+        }
+        preflight::check_space(&needs)
+        .map_err(|e| AppError::new(ErrorKind::DiskFull, e).with_hint("Free up space or choose another scratch or output folder."))?;
+    }
+    // Dropping the context removes the scratch directory, whether the job
+    // finishes, fails or is canceled.
+    let ctx = RenderContext {
+        enc,
+        still,
+        workspace: if dry_run { Workspace::planned(&scratch) } else { Workspace::new(&scratch).map_err(|e| AppError::new(ErrorKind::Io, e))? },
+        cache,
+        plan: recorder,
+    };
+    if let Some(w) = window {
+        let _ = w.emit("generate_progress", 0f64);
+    }
+    let chapters = resolve_chapters(params, ctx.plan.as_ref())?;
+    let mains = build_main_section(window, params, TimeRange::full(duration), &targets, &ctx, queue_index)?;
+
+    let tagged = !chapters.is_empty()
+        || params.title.is_some()
+        || params.description.is_some()
+        || params.artist.is_some()
+        || params.episode.is_some();
+    let mut outputs = Vec::new();
+    let mut chapter_offset = 0.0;
+    for (i, (target, main)) in targets.iter().zip(mains).enumerate() {
+        let mut segments = Vec::new();
+        if let Some(ref intro) = params.intro {
+            let seg = convert_media(intro, Some(5.0), target, &ctx)?;
+            chapter_offset = ctx
+                .probe_duration(seg.to_str().unwrap(), || bookend_duration(intro))
+                .unwrap_or(5.0);
+            segments.push(seg);
+        }
+        segments.push(main);
+        if let Some(ref outro) = params.outro {
+            segments.push(convert_media(outro, Some(5.0), target, &ctx)?);
+        }
+        concat_segments(&segments, &target.output, &ctx)?;
+        if tagged {
+            let total = ctx.probe_duration(&target.output, || {
+                duration + chapter_offset + params.outro.as_deref().map(bookend_duration).unwrap_or(0.0)
+            })?;
+            embed_metadata(&target.output, &metadata::ffmetadata(params, &chapters, chapter_offset, total), &ctx)?;
+        }
+        if let (Some(cache), Some(keys)) = (&ctx.cache, &output_keys) {
+            if let Err(e) = cache.store(&keys[i], "mp4", Path::new(&target.output)) {
+                log(app, "error", &format!("render cache: {}", e));
+            }
+        }
+        outputs.push(target.output.clone());
+    }
+    let description = metadata::description_with_chapters(params.description.as_deref(), &chapters, chapter_offset);
+    let plan = ctx.plan.map(|r| r.finish(outputs.clone()));
+    if dry_run {
+        log(app, "info", "generate_plan done");
+        return Ok((
+            GenerateResult {
+                output: output_path,
+                outputs,
+                fast_path: still.is_some(),
+                cached: false,
+                render_seconds: 0.0,
+                speed: 0.0,
+                chapters,
+                description,
+            },
+            plan,
+        ));
+    }
 
-    let mut segments = Vec::new();
-    if let Some(ref intro) = params.intro {
-        segments.push(convert_media(intro, Some(5.0), width, height, params.fps)?);
+    let render_seconds = started.elapsed().as_secs_f64();
+    let speed = if render_seconds > 0.0 { duration / render_seconds } else { 0.0 };
+    if let Some(w) = window {
+        let _ = w.emit("generate_progress", 100f64);
+    }
+    log(
+        app,
+        "info",
+        &format!(
+            "generate_video done in {:.1}s ({:.1}x realtime{})",
+            render_seconds,
+            speed,
+            if still.is_some() { ", still-image path" } else { "" }
+        ),
+    );
+
+    Ok((
+        GenerateResult {
+            output: output_path,
+            outputs,
+            fast_path: still.is_some(),
+            cached: false,
+            render_seconds,
+            speed,
+            chapters,
+            description,
+        },
+        plan,
+    ))
+}
+
+/// Render a job and report every produced file, including renditions.
+#[command]
+fn generate_video(window: WebviewWindow<Wry>, params: GenerateParams, queue_index: Option<usize>) -> AppResult<GenerateResult> {
+    render_video(&window, &params, queue_index)
+}
+
+const PREVIEW_WIDTH: u32 = 640;
+const PREVIEW_SECONDS: f64 = 10.0;
+
+/// Previews older than this are deleted when the next one is requested.
+const PREVIEW_MAX_AGE: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+
+/// Directory previews are cached in, pruned of old ones.
+fn preview_dir() -> AppResult<PathBuf> {
+    let dir = std::env::temp_dir().join("ytapp_preview");
+    fs::create_dir_all(&dir)?;
+    prune_previews(&dir, PREVIEW_MAX_AGE);
+    Ok(dir)
+}
+
+/// Delete previews in `dir` last written at least `max_age` ago.
+fn prune_previews(dir: &Path, max_age: std::time::Duration) {
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        let age = entry.metadata().and_then(|m| m.modified()).ok().and_then(|t| t.elapsed().ok());
+        if age.is_some_and(|age| age >= max_age) {
+            let _ = fs::remove_file(entry.path());
+        }
     }
-    segments.push(main);
-    if let Some(ref outro) = params.outro {
-        segments.push(convert_media(outro, Some(5.0), width, height, params.fps)?);
+}
+
+/// Cache location for a preview in `dir`, keyed by the params, the excerpt
+/// and the contents of every input file and font, so edited captions and
+/// fonts are picked up.
+fn preview_path(dir: &Path, params: &GenerateParams, range: TimeRange, ext: &str) -> AppResult<PathBuf> {
+    let mut key = CacheKey::new("preview").value(params)?.value(&(range.start, range.duration))?;
+    let inputs = [
+        Some(&params.file),
+        params.background.as_ref(),
+        params.captions.as_ref(),
+        params.watermark.as_ref(),
+        params.caption_options.as_ref().and_then(|o| o.font_path.as_ref()),
+        params.watermark_text.as_ref().and_then(|t| t.font_path.as_ref()),
+    ];
+    for input in inputs.into_iter().flatten().filter(|p| Path::new(p).is_file()) {
+        key = key.file(input)?;
     }
+    Ok(dir.join(format!("{}.{}", key.finish(), ext)))
+}
 
-    if segments.len() == 1 {
-        fs::rename(&segments[0], &output_path).map_err(|e| e.to_string())?;
+/// Render a short, low resolution excerpt of the main section so caption
+/// style and watermark placement can be checked before a full render.
+/// Returns the path of an MP4, or of a PNG still when `still` is true.
+#[command]
+fn generate_preview(window: WebviewWindow<Wry>, params: GenerateParams, start: Option<f64>, length: Option<f64>, still: Option<bool>) -> AppResult<String> {
+    let still = still.unwrap_or(false);
+    let total = audio_duration(&params.file)?;
+    let start = start.unwrap_or(0.0).clamp(0.0, total.max(0.0));
+    let length = if still { 1.0 } else { length.unwrap_or(PREVIEW_SECONDS) };
+    let range = TimeRange { start, duration: length.min(total - start).max(0.1) };
+
+    let out = preview_path(&preview_dir()?, &params, range, if still { "png" } else { "mp4" })?;
+    if out.exists() {
+        return Ok(out.to_string_lossy().to_string());
+    }
+    log(&window.app_handle(), "info", "generate_preview start");
+    let mut features = required_features(&params);
+    features.retain(|f| *f != Feature::Bookends);
+    ffmpeg_caps::capabilities(false)?.require(&features)?;
+
+    let mut target = rendition_targets(&params, "preview.mp4").remove(0);
+    if target.width > PREVIEW_WIDTH {
+        let height = (target.height as f64 * PREVIEW_WIDTH as f64 / target.width as f64) as u32;
+        target.width = PREVIEW_WIDTH;
+        target.height = height + height % 2;
+    }
+    let settings = load_settings(window.app_handle().clone()).unwrap_or_default();
+    let ctx = RenderContext {
+        enc: EncodingOptions {
+            preset: Some("ultrafast".into()),
+            crf: Some(28),
+            ..Default::default()
+        },
+        still: None,
+        workspace: Workspace::new(&scratch_root(&settings)).map_err(|e| AppError::new(ErrorKind::Io, e))?,
+        cache: None,
+        plan: None,
+    };
+    let clip = build_main_section(None, &params, range, &[target], &ctx, None)?.remove(0);
+
+    if still {
+        let mut cmd = Command::new("ffmpeg");
+        cmd.args(["-y", "-i", clip.to_str().unwrap(), "-frames:v", "1", out.to_str().unwrap()]);
+        run_ffmpeg(cmd)?;
     } else {
-        let list_path = temp_file("list");
-        let mut list = File::create(&list_path).map_err(|e| e.to_string())?;
-        for seg in &segments {
-            writeln!(list, "file '{}'", seg.to_string_lossy()).map_err(|e| e.to_string())?;
-        }
-        let status = Command::new("ffmpeg")
-            .args([
-                "-y",
-                "-f",
-                "concat",
-                "-safe",
-                "0",
-                "-i",
-                list_path.to_str().unwrap(),
-                "-c",
-                "copy",
-                &output_path,
-            ])
-            .status()
-            .map_err(|e| format!("failed to start ffmpeg: {}", e))?;
-        if !status.success() {
-            return Err(format!("ffmpeg exited with status {:?}", status.code()));
-        }
+        workspace::move_file(&clip, &out).map_err(|e| AppError::new(ErrorKind::Io, e))?;
     }
+    Ok(out.to_string_lossy().to_string())
+}
 
-    let _ = window.emit("generate_progress", 100f64);
-    log(&window.app_handle(), "info", "generate_video done");
+/// Resolve a job and return the ffmpeg/ffprobe commands, temp files and
+/// final concat a render would use, without running or writing anything.
+#[command]
+fn generate_plan(app: AppHandle<Wry>, params: GenerateParams) -> AppResult<RenderPlan> {
+    let settings = load_settings(app.clone()).unwrap_or_default();
+    let (_, plan) = render_job(&app, None, &params, &settings, None, Some(Recorder::dry_run()))?;
+    plan.ok_or_else(|| "no plan recorded".into())
+}
 
-    Ok(output_path)
+/// Size, location and entries of the render cache.
+#[command]
+fn cache_info(app: AppHandle<Wry>) -> AppResult<CacheInfo> {
+    let settings = load_settings(app.clone()).unwrap_or_default();
+    let cache = render_cache(&app, &settings)
+        .ok_or_else(|| AppError::new(ErrorKind::Config, "render cache is disabled").with_hint("Set cacheMaxBytes above zero."))?;
+    Ok(cache.info())
 }
 
-async fn upload_video_impl(window: WebviewWindow<Wry>, file: String, opts: UploadOptions, index: Option<usize>) -> Result<String, String> {
-    log(&window.app_handle(), "info", &format!("upload_video start: {}", file));
-    let auth = build_authenticator().await?;
-
-    let client = Client::builder(TokioExecutor::new())
-        .build(
-            HttpsConnectorBuilder::new()
-                .with_native_roots()
-                .map_err(|e| e.to_string())?
-                .https_or_http()
-                .enable_http1()
-                .build(),
-        );
+/// Delete every cached render. Returns the number of bytes freed.
+#[command]
+fn cache_clear(app: AppHandle<Wry>) -> AppResult<u64> {
+    let settings = load_settings(app.clone()).unwrap_or_default();
+    let Some(cache) = render_cache(&app, &settings) else { return Ok(0) };
+    let freed = cache.clear()?;
+    log(&app, "info", &format!("cache_clear freed {} bytes", freed));
+    Ok(freed)
+}
 
-    let hub = YouTube::new(client, auth);
+/// Upload `file`. `index` is the queue position progress is reported for and
+/// `job` the id of the queue job that keeps the resumable session.
+async fn upload_video_impl(
+    window: WebviewWindow<Wry>,
+    file: String,
+    opts: UploadOptions,
+    index: Option<usize>,
+    job: Option<u64>,
+) -> AppResult<UploadResult> {
+    log(&window.app_handle(), "info", &format!("upload_video start: {}", file));
+    validate_video_metadata(&opts.video)?;
+    let caption_tracks = opts.caption_tracks.clone().unwrap_or_default();
+    validate_caption_tracks(&caption_tracks)?;
+    let hub = youtube_hub(&window.app_handle(), opts.account.as_deref()).await?;
 
     let file_name = Path::new(&file)
         .file_name()
@@ -551,6 +1592,9 @@ async fn upload_video_impl(window: WebviewWindow<Wry>, file: String, opts: Uploa
         title: Some(opts.title.clone().unwrap_or_else(|| file_name.to_string())),
         description: opts.description.clone(),
         tags: opts.tags.clone(),
+        category_id: opts.video.category_id.clone(),
+        default_language: opts.video.default_language.clone(),
+        default_audio_language: opts.video.default_audio_language.clone(),
         ..Default::default()
     });
     let mut status = google_youtube3::api::VideoStatus::default();
@@ -562,10 +1606,32 @@ async fn upload_video_impl(window: WebviewWindow<Wry>, file: String, opts: Uploa
     if let Some(ref p) = opts.publish_at {
         status.publish_at = parse_publish_at(p);
     }
+    status.self_declared_made_for_kids = opts.video.made_for_kids;
+    status.license = opts.video.license.clone();
+    status.embeddable = opts.video.embeddable;
+    status.public_stats_viewable = opts.video.public_stats_viewable;
+    let mut steps: Vec<PostStep> = Vec::new();
+    if let Some(ref th) = opts.thumbnail {
+        steps.push(PostStep::Thumbnail { file: th.clone() });
+    }
+    if let Some(ref pid) = opts.playlist_id {
+        steps.push(PostStep::Playlist { playlist_id: pid.clone() });
+    }
+    steps.extend(caption_tracks.into_iter().map(|track| PostStep::Caption { track }));
+    if let Some(value) = opts.video.contains_synthetic_media {
+        steps.push(PostStep::SyntheticMedia { value });
+    }
     video.status = Some(status);
+    let recording_date = opts.video.recording_date.as_deref().and_then(parse_recording_date);
+    if recording_date.is_some() {
+        video.recording_details = Some(google_youtube3::api::VideoRecordingDetails {
+            recording_date,
+            ..Default::default()
+        });
+    }
 
-    let f = std::fs::File::open(&file).map_err(|e| format!("Failed to open file: {}", e))?;
-    let size = f.metadata().map_err(|e| e.to_string())?.len();
+    let f = std::fs::File::open(&file)?;
+    let size = f.metadata()?.len();
     let reader = ProgressReader::new(f, window.clone(), size, index);
     let mut reader = std::io::BufReader::new(reader);
     let _ = window.emit("upload_progress", 0f64);
@@ -576,15 +1642,53 @@ async fn upload_video_impl(window: WebviewWindow<Wry>, file: String, opts: Uploa
         *active = Some(handle);
     }
 
+    let stored = job
+        .and_then(job_queue::upload_session)
+        .filter(|s| s.file == file && s.is_usable(Utc::now()));
+    if let Some(ref s) = stored {
+        log(&window.app_handle(), "info", &format!("resuming upload of {} after {} bytes", file, s.offset));
+    }
+    if stored.is_none() {
+        quota::check_upload(&window.app_handle(), quota_limit(&window.app_handle()), upload_cost(&opts))?;
+    }
+    let mut dlg = SessionDelegate::new(window.app_handle().clone(), &file, job, stored);
+
     let fut = async {
-        let response = hub
-            .videos()
-            .insert(video)
-            .add_part("snippet")
-            .upload_resumable(&mut reader, "video/mp4".parse().unwrap())
-            .await
-            .map_err(|e| format!("Upload failed: {}", e))?;
-        Ok::<_, String>(response)
+        loop {
+            let mut call = hub
+                .videos()
+                .insert(video.clone())
+                .add_part("snippet")
+                .add_part("status")
+                .add_scope(auth::YOUTUBE_SCOPE);
+            if recording_date.is_some() {
+                call = call.add_part("recordingDetails");
+            }
+            if let Some(notify) = opts.video.notify_subscribers {
+                call = call.notify_subscribers(notify);
+            }
+            let resuming = dlg.is_resuming();
+            if !resuming {
+                quota::spend(&window.app_handle(), ApiMethod::VideosInsert);
+            }
+            let result = call
+                .delegate(&mut dlg)
+                .upload_resumable(&mut reader, "video/mp4".parse().unwrap())
+                .await
+                .map_err(api_error);
+            match result {
+                Ok(response) => {
+                    dlg.clear();
+                    return Ok::<_, AppError>(response);
+                }
+                Err(_) if resuming && dlg.session_expired() => {
+                    log(&window.app_handle(), "warn", "upload session expired; starting a new one");
+                    dlg.clear();
+                    reader.seek(SeekFrom::Start(0))?;
+                }
+                Err(e) => return Err(e),
+            }
+        }
     };
 
     let result = Abortable::new(fut, reg).await;
@@ -596,103 +1700,455 @@ async fn upload_video_impl(window: WebviewWindow<Wry>, file: String, opts: Uploa
             Ok(Ok(response)) => {
                 let _ = window.emit("upload_progress", 100f64);
                 let id = response.1.id.unwrap_or_default();
-            if let Some(th) = opts.thumbnail.as_ref() {
-                if Path::new(th).exists() {
-                    if let Ok(mut tf) = File::open(th) {
-                        let mime = mime_guess::from_path(th).first_or_octet_stream();
-                        let _ = hub
-                            .thumbnails()
-                            .set(&id)
-                            .upload(&mut tf, mime)
-                            .await;
-                    }
-                }
-            }
-            if let Some(pid) = opts.playlist_id.as_ref() {
-                let item = google_youtube3::api::PlaylistItem {
-                    snippet: Some(google_youtube3::api::PlaylistItemSnippet {
-                        playlist_id: Some(pid.clone()),
-                        resource_id: Some(google_youtube3::api::ResourceId {
-                            kind: Some("youtube#video".to_string()),
-                            video_id: Some(id.clone()),
-                            ..Default::default()
-                        }),
-                        ..Default::default()
-                    }),
-                    ..Default::default()
-                };
-                let _ = hub
-                    .playlist_items()
-                    .insert(item)
-                    .add_part("snippet")
-                    .doit()
-                    .await;
-            }
                 log(&window.app_handle(), "info", &format!("upload complete: {}", id));
-                Ok(format!("Uploaded video ID: {}", id))
+                let tasks = steps.into_iter().map(PostTask::new).collect();
+                let result = post_upload::run(&window.app_handle(), &hub, &id, opts.account.clone(), tasks).await;
+                if result.partial {
+                    log(&window.app_handle(), "warn", &format!("upload {} finished with failed post-upload steps", id));
+                }
+                Ok(result)
             }
             Ok(Err(e)) => {
-                log(&window.app_handle(), "error", &e);
+                log(&window.app_handle(), "error", &e.to_string());
+                match e.kind {
+                    ErrorKind::Quota => quota::exhaust(&window.app_handle()),
+                    ErrorKind::UploadLimit => quota::limit_uploads(&window.app_handle()),
+                    _ => {}
+                }
                 Err(e)
             },
             Err(_) => {
                 let _ = window.emit("upload_canceled", ());
                 log(&window.app_handle(), "error", "upload canceled");
-                Err("upload canceled".into())
+                Err(AppError::canceled("upload"))
             }
         }
 }
 
-async fn build_authenticator() -> Result<Authenticator<HttpsConnector<HttpConnector>>, String> {
-    let secret_path = std::env::var("YOUTUBE_CLIENT_SECRET").unwrap_or_else(|_| "client_secret.json".into());
-    let secret = yup_oauth2::read_application_secret(secret_path)
-        .await
-        .map_err(|e| format!("Failed to read client secret: {}", e))?;
+/// Recording date as RFC 3339, local `YYYY-MM-DDTHH:MM` or a plain date.
+fn parse_recording_date(s: &str) -> Option<DateTime<Utc>> {
+    parse_publish_at(s).or_else(|| {
+        let date = NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?;
+        Some(Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0)?))
+    })
+}
+
+/// Loose BCP-47 check: a two or three letter language with optional
+/// alphanumeric subtags, e.g. `en`, `pt-BR` or `zh-Hant-TW`.
+fn is_language_tag(tag: &str) -> bool {
+    let mut parts = tag.split('-');
+    let primary = parts.next().unwrap_or_default();
+    (2..=3).contains(&primary.len())
+        && primary.chars().all(|c| c.is_ascii_alphabetic())
+        && parts.all(|p| (1..=8).contains(&p.len()) && p.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// Reject metadata the API would refuse before rendering or uploading.
+fn validate_video_metadata(m: &VideoMetadata) -> AppResult<()> {
+    let invalid = |message: String| -> AppResult<()> { Err(AppError::new(ErrorKind::InvalidInput, message)) };
+    if let Some(ref id) = m.category_id {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit()) {
+            return invalid(format!("categoryId must be a numeric category ID, got {:?}", id));
+        }
+    }
+    for (field, value) in [("defaultLanguage", &m.default_language), ("defaultAudioLanguage", &m.default_audio_language)] {
+        if let Some(tag) = value {
+            if !is_language_tag(tag) {
+                return invalid(format!("{} must be a language code such as en or pt-BR, got {:?}", field, tag));
+            }
+        }
+    }
+    if let Some(ref license) = m.license {
+        if license != "youtube" && license != "creativeCommon" {
+            return invalid(format!("license must be youtube or creativeCommon, got {:?}", license));
+        }
+    }
+    if let Some(ref date) = m.recording_date {
+        if parse_recording_date(date).is_none() {
+            return invalid(format!("recordingDate must be a date such as 2024-05-01, got {:?}", date));
+        }
+    }
+    Ok(())
+}
+
+/// Reject caption tracks YouTube would refuse before the video is uploaded,
+/// so a typo never leaves a published video without its captions.
+fn validate_caption_tracks(tracks: &[CaptionTrack]) -> AppResult<()> {
+    for track in tracks {
+        let ext = Path::new(&track.file)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        if !matches!(ext.as_deref(), Some("srt") | Some("vtt")) {
+            return Err(AppError::new(
+                ErrorKind::InvalidInput,
+                format!("caption track {} must be an SRT or WebVTT file", track.file),
+            ));
+        }
+        if track.language.trim().is_empty() {
+            return Err(AppError::new(ErrorKind::InvalidInput, format!("caption track {} needs a language", track.file)));
+        }
+        if !Path::new(&track.file).is_file() {
+            return Err(AppError::new(ErrorKind::NotFound, format!("caption track {} not found", track.file)));
+        }
+    }
+    Ok(())
+}
+
+fn quota_limit(app: &AppHandle<Wry>) -> u64 {
+    load_settings(app.clone())
+        .ok()
+        .and_then(|s| s.quota_daily_limit)
+        .unwrap_or(quota::DEFAULT_DAILY_LIMIT)
+}
+
+/// Quota units an upload with these options uses, post-upload steps included.
+fn upload_cost(opts: &UploadOptions) -> u64 {
+    let mut methods = vec![ApiMethod::VideosInsert];
+    if opts.thumbnail.is_some() {
+        methods.push(ApiMethod::ThumbnailsSet);
+    }
+    if opts.playlist_id.is_some() {
+        methods.push(ApiMethod::PlaylistItemsInsert);
+    }
+    for _ in opts.caption_tracks.iter().flatten() {
+        methods.push(ApiMethod::CaptionsInsert);
+    }
+    if opts.video.contains_synthetic_media.is_some() {
+        methods.push(ApiMethod::VideosUpdate);
+    }
+    quota::cost(&methods)
+}
+
+/// The upload options a generate-and-upload job ends up with, for costing.
+fn upload_options(params: &GenerateParams) -> UploadOptions {
+    UploadOptions {
+        thumbnail: params.thumbnail.clone(),
+        playlist_id: params.playlist_id.clone(),
+        caption_tracks: params.caption_tracks.clone(),
+        video: params.video.clone(),
+        ..Default::default()
+    }
+}
+
+/// Upload jobs are held while their projected cost exceeds today's quota.
+fn job_fits_quota(app: &AppHandle<Wry>, job: &Job) -> bool {
+    match job {
+        Job::Generate { .. } => true,
+        Job::GenerateUpload { params, thumbnail, .. } => {
+            let mut opts = upload_options(params);
+            opts.thumbnail = opts.thumbnail.or_else(|| thumbnail.clone());
+            quota::upload_fits(app, quota_limit(app), upload_cost(&opts))
+        }
+    }
+}
+
+/// YouTube API quota used today and the headroom left.
+#[command]
+fn quota_status(app: AppHandle<Wry>) -> AppResult<QuotaStatus> {
+    quota::status(&app, quota_limit(&app))
+}
+
+/// API client signed in as `account`, which must be registered unless it is
+/// the default account.
+async fn youtube_hub(app: &AppHandle<Wry>, account: Option<&str>) -> AppResult<Hub> {
+    let account = accounts::resolve(app, account)?;
+    hub_for(app, &account, AuthFlow::choose(None)).await
+}
+
+async fn hub_for(app: &AppHandle<Wry>, account: &str, flow: AuthFlow) -> AppResult<Hub> {
+    let auth = build_authenticator(app, account, flow).await?;
+    Ok(YouTube::new(auth::https_client()?, auth))
+}
+
+fn token_path() -> String {
+    std::env::var("YOUTUBE_TOKEN_FILE").unwrap_or_else(|_| "youtube_tokens.enc".into())
+}
 
-    let token_path = std::env::var("YOUTUBE_TOKEN_FILE").unwrap_or_else(|_| "youtube_tokens.enc".into());
+/// The token store key: a passphrase unlocked this session, then
+/// `YOUTUBE_TOKEN_PASSPHRASE`, then a raw 32-byte `YOUTUBE_TOKEN_KEY`. A
+/// passphrase from the environment becomes the session key, so its derived
+/// key is reused.
+fn token_key() -> AppResult<TokenKey> {
+    if let Some(key) = token_store::session_key() {
+        return Ok(key);
+    }
+    if let Ok(passphrase) = std::env::var("YOUTUBE_TOKEN_PASSPHRASE") {
+        let key = TokenKey::passphrase(passphrase)?;
+        token_store::set_session_key(key.clone());
+        return Ok(key);
+    }
+    let key_hint = "Unlock the token store with a passphrase, set YOUTUBE_TOKEN_PASSPHRASE, or set YOUTUBE_TOKEN_KEY to a 32-byte secret.";
     let key_env = std::env::var("YOUTUBE_TOKEN_KEY")
-        .map_err(|_| "YOUTUBE_TOKEN_KEY not set".to_string())?;
+        .map_err(|_| AppError::new(ErrorKind::Config, "no token store key set").with_hint(key_hint))?;
     let key_bytes = key_env.as_bytes();
     if key_bytes.len() != 32 {
-        return Err("YOUTUBE_TOKEN_KEY must be exactly 32 bytes".into());
+        return Err(AppError::new(ErrorKind::Config, "YOUTUBE_TOKEN_KEY must be exactly 32 bytes").with_hint(key_hint));
     }
     let mut key = [0u8; 32];
     key.copy_from_slice(key_bytes);
-    let store = EncryptedTokenStorage::new(token_path, key)
-        .await
-        .map_err(|e| format!("Token storage error: {}", e))?;
+    Ok(TokenKey::Raw(key))
+}
+
+async fn client_secret() -> AppResult<ApplicationSecret> {
+    let secret_path = std::env::var("YOUTUBE_CLIENT_SECRET").unwrap_or_else(|_| "client_secret.json".into());
+    yup_oauth2::read_application_secret(&secret_path).await.map_err(|e| {
+        AppError::new(ErrorKind::Config, format!("failed to read client secret {}", secret_path))
+            .caused_by(&e)
+            .with_hint("Download an OAuth client secret and set YOUTUBE_CLIENT_SECRET to its path.")
+    })
+}
+
+async fn build_authenticator(
+    app: &AppHandle<Wry>,
+    account: &str,
+    flow: AuthFlow,
+) -> AppResult<Authenticator<HttpsConnector<HttpConnector>>> {
+    let secret = client_secret().await?;
+    let store = EncryptedTokenStorage::new(token_path(), token_key()?, account).await?;
+
+    let auth = match flow {
+        AuthFlow::Browser => {
+            InstalledFlowAuthenticator::builder(secret, InstalledFlowReturnMethod::HTTPRedirect)
+                .with_storage(Box::new(store))
+                .build()
+                .await
+        }
+        AuthFlow::Device => {
+            DeviceFlowAuthenticator::builder(secret)
+                .flow_delegate(Box::new(auth::DeviceCodePrompt::new(app.clone())))
+                .with_storage(Box::new(store))
+                .build()
+                .await
+        }
+    };
+    auth.map_err(|e| AppError::new(ErrorKind::Auth, "failed to start sign-in").caused_by(&e))
+}
+
+#[command]
+async fn upload_video(window: WebviewWindow<Wry>, file: String, opts: Option<UploadOptions>) -> AppResult<UploadResult> {
+    upload_video_impl(window, file, opts.unwrap_or_default(), None, None).await
+}
+
+/// Uploaded videos with post-upload steps that still failed.
+#[command]
+fn upload_incomplete(app: AppHandle<Wry>) -> AppResult<Vec<UploadResult>> {
+    post_upload::incomplete(&app)
+}
+
+/// Run the failed post-upload steps of an uploaded video again, signed in
+/// as the account recorded for it at upload.
+#[command]
+async fn upload_retry_steps(app: AppHandle<Wry>, video_id: String) -> AppResult<UploadResult> {
+    let record = post_upload::record_for(&app, &video_id)?;
+    let hub = youtube_hub(&app, record.account.as_deref()).await?;
+    Ok(post_upload::run(&app, &hub, &video_id, record.account, record.steps).await)
+}
 
-    InstalledFlowAuthenticator::builder(secret, InstalledFlowReturnMethod::HTTPRedirect)
-        .with_storage(Box::new(store))
-        .build()
+/// Sign in the default account. With `device`, or `YOUTUBE_AUTH_FLOW=device`,
+/// the device flow is used and its code sent as a `youtube_device_code` event.
+#[command]
+async fn youtube_sign_in(app: AppHandle<Wry>, device: Option<bool>) -> AppResult<()> {
+    let authenticator = build_authenticator(&app, DEFAULT_ACCOUNT, AuthFlow::choose(device)).await?;
+    authenticator
+        .token(&[auth::YOUTUBE_SCOPE])
         .await
-        .map_err(|e| format!("Auth error: {}", e))
+        .map_err(|e| AppError::new(ErrorKind::Auth, "sign-in failed").caused_by(&e))?;
+    Ok(())
 }
 
+/// Import tokens exported on another machine into `account`, the default
+/// account if none. A named account is registered with its channel.
 #[command]
-async fn upload_video(window: WebviewWindow<Wry>, file: String, opts: Option<UploadOptions>) -> Result<String, String> {
-    upload_video_impl(window, file, opts.unwrap_or_default(), None).await
+async fn youtube_import_tokens(app: AppHandle<Wry>, file: String, account: Option<String>) -> AppResult<()> {
+    let name = account.unwrap_or_else(|| DEFAULT_ACCOUNT.to_string());
+    accounts::validate_name(&name)?;
+    let bundle = TokenBundle::parse(&std::fs::read_to_string(&file)?)?;
+    let count = token_store::import(Path::new(&token_path()), &token_key()?, &name, bundle).await?;
+    log(&app, "info", &format!("imported {} token(s) into account {}", count, name));
+    if name != DEFAULT_ACCOUNT {
+        let hub = hub_for(&app, &name, AuthFlow::choose(None)).await?;
+        register_account(&app, &hub, name).await?;
+    }
+    Ok(())
 }
 
+/// Write the tokens of `account` unencrypted to `file`, for importing on a
+/// machine that cannot sign in itself. The file grants access to the channel,
+/// so it is created readable by the owner only and never overwritten.
 #[command]
-async fn youtube_sign_in() -> Result<(), String> {
-    build_authenticator().await.map(|_| ())
+async fn youtube_export_tokens(file: String, account: Option<String>) -> AppResult<()> {
+    use std::io::Write;
+    let name = account.as_deref().unwrap_or(DEFAULT_ACCOUNT);
+    let bundle = token_store::export(Path::new(&token_path()), &token_key()?, name).await?;
+    let mut opts = std::fs::OpenOptions::new();
+    opts.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut opts, 0o600);
+    let mut out = opts.open(&file).map_err(|e| match e.kind() {
+        std::io::ErrorKind::AlreadyExists => AppError::new(ErrorKind::InvalidInput, format!("{} already exists", file))
+            .with_hint("Export to a new file, or delete the old one first."),
+        _ => e.into(),
+    })?;
+    out.write_all(serde_json::to_string_pretty(&bundle)?.as_bytes())?;
+    Ok(())
 }
 
+/// Whether the default account has tokens that can still be used, judged
+/// offline.
 #[command]
 async fn youtube_is_signed_in() -> bool {
-    let token_path = std::env::var("YOUTUBE_TOKEN_FILE").unwrap_or_else(|_| "youtube_tokens.enc".into());
-    tokio::fs::metadata(token_path).await.is_ok()
+    match youtube_sign_in_status(Some(DEFAULT_ACCOUNT.to_string()), None).await {
+        Ok(status) => status.signed_in,
+        Err(_) => false,
+    }
+}
+
+/// Decrypt the token store and describe the tokens of each account, or only
+/// of `account`. With `validate`, each token is also checked with Google.
+#[command]
+async fn youtube_sign_in_status(account: Option<String>, validate: Option<bool>) -> AppResult<SignInStatus> {
+    let stored = token_store::load(Path::new(&token_path()), &token_key()?).await?;
+    let online = if validate.unwrap_or(false) {
+        Some((auth::https_client()?, client_secret().await?))
+    } else {
+        None
+    };
+    let now = Utc::now();
+    let mut accounts = Vec::new();
+    for (name, tokens) in stored.iter().filter(|(name, _)| account.as_ref().map_or(true, |a| a == *name)) {
+        let mut status = auth::account_status(name, tokens, now);
+        if let Some((client, secret)) = &online {
+            for token in status.tokens.iter_mut() {
+                if let Some(info) = tokens.get(&token.scopes.join(" ")) {
+                    token.valid = Some(auth::validate(client, secret, info).await?);
+                }
+            }
+        }
+        accounts.push(status);
+    }
+    Ok(SignInStatus { signed_in: accounts.iter().any(|a| a.signed_in()), accounts })
 }
 
+/// Revoke the tokens of `account`, or of every account, at Google and then
+/// delete them. Tokens are kept when revocation fails unless `force` is set.
 #[command]
-async fn youtube_sign_out() -> Result<(), String> {
-    let token_path = std::env::var("YOUTUBE_TOKEN_FILE").unwrap_or_else(|_| "youtube_tokens.enc".into());
-    match tokio::fs::remove_file(&token_path).await {
+async fn youtube_sign_out(app: AppHandle<Wry>, account: Option<String>, force: Option<bool>) -> AppResult<()> {
+    revoke_tokens(&app, account.as_deref(), force.unwrap_or(false)).await.map(|_| ())
+}
+
+/// Revoke and delete the tokens of `account`, or of every account. Returns
+/// false when `account` had no tokens.
+async fn revoke_tokens(app: &AppHandle<Wry>, account: Option<&str>, force: bool) -> AppResult<bool> {
+    let path = PathBuf::from(token_path());
+    let opened = async {
+        let key = token_key()?;
+        let stored = token_store::load(&path, &key).await?;
+        Ok::<_, AppError>((key, stored))
+    }
+    .await;
+    let (key, stored) = match opened {
+        Ok(opened) => opened,
+        Err(e) if force && account.is_none() => {
+            log(app, "warn", &format!("deleting tokens without revoking them: {}", e));
+            return remove_token_file(&path).await.map(|_| true);
+        }
+        Err(e) => return Err(e),
+    };
+    let client = auth::https_client()?;
+    for (name, tokens) in stored.iter().filter(|(name, _)| account.map_or(true, |a| a == *name)) {
+        for info in tokens.values() {
+            match auth::revoke(&client, info).await {
+                Ok(()) => {}
+                Err(e) if force => log(app, "warn", &format!("could not revoke a token of account {}: {}", name, e)),
+                Err(e) => {
+                    return Err(e.with_hint("Check the connection and try again, or force the sign-out to only delete the tokens."))
+                }
+            }
+        }
+        log(app, "info", &format!("signed out account {}", name));
+    }
+    match account {
+        Some(name) => token_store::remove_account(&path, &key, name).await,
+        None => remove_token_file(&path).await.map(|_| true),
+    }
+}
+
+async fn remove_token_file(path: &Path) -> AppResult<()> {
+    match tokio::fs::remove_file(path).await {
         Ok(_) => Ok(()),
         Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
-        Err(e) => Err(e.to_string()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Use `passphrase` for the token store until the app exits.
+#[command]
+async fn token_unlock(passphrase: String) -> AppResult<()> {
+    let key = TokenKey::passphrase(passphrase)?;
+    token_store::verify(Path::new(&token_path()), &key).await?;
+    token_store::set_session_key(key);
+    Ok(())
+}
+
+/// Re-encrypt the token store under a new passphrase, which is used for the
+/// rest of the session.
+#[command]
+async fn token_rotate_key(app: AppHandle<Wry>, new_passphrase: String) -> AppResult<()> {
+    let new_key = TokenKey::passphrase(new_passphrase)?;
+    token_store::rotate(Path::new(&token_path()), &token_key()?, &new_key).await?;
+    token_store::set_session_key(new_key);
+    log(&app, "info", "token store re-encrypted with a new key");
+    Ok(())
+}
+
+#[command]
+fn account_list(app: AppHandle<Wry>) -> AppResult<Vec<Account>> {
+    accounts::list(&app)
+}
+
+/// Sign in a new named account and resolve the channel it posts to.
+#[command]
+async fn account_add(app: AppHandle<Wry>, name: String, device: Option<bool>) -> AppResult<Account> {
+    accounts::validate_name(&name)?;
+    let hub = hub_for(&app, &name, AuthFlow::choose(device)).await?;
+    register_account(&app, &hub, name).await
+}
+
+/// Look up the channel `hub` is signed in to and save it as account `name`.
+async fn register_account(app: &AppHandle<Wry>, hub: &Hub, name: String) -> AppResult<Account> {
+    quota::spend(app, ApiMethod::ChannelsList);
+    let resp = hub
+        .channels()
+        .list(&vec!["snippet".to_string()])
+        .mine(true)
+        .add_scope(auth::YOUTUBE_SCOPE)
+        .doit()
+        .await
+        .map_err(api_error)?;
+    let channel = resp.1.items.unwrap_or_default().into_iter().next().ok_or_else(|| {
+        AppError::new(ErrorKind::NotFound, format!("account {} has no YouTube channel", name))
+            .with_hint("Create a channel for this Google account first.")
+    })?;
+    let account = Account {
+        name,
+        channel_id: channel.id,
+        channel_title: channel.snippet.and_then(|s| s.title),
+    };
+    accounts::save(app, account.clone())?;
+    log(app, "info", &format!("account added: {} ({})", account.name, account.channel_id.as_deref().unwrap_or("?")));
+    Ok(account)
+}
+
+/// Forget a named account and revoke and delete its tokens like a sign-out.
+/// With `force` the tokens are deleted even when they cannot be revoked.
+#[command]
+async fn account_remove(app: AppHandle<Wry>, name: String, force: Option<bool>) -> AppResult<()> {
+    let had_tokens = revoke_tokens(&app, Some(&name), force.unwrap_or(false)).await?;
+    if !accounts::remove(&app, &name)? && !had_tokens {
+        return Err(AppError::new(ErrorKind::NotFound, format!("unknown account {}", name)));
     }
+    Ok(())
 }
 
 #[derive(Serialize)]
@@ -702,29 +2158,19 @@ struct PlaylistInfo {
 }
 
 #[command]
-async fn list_playlists() -> Result<Vec<PlaylistInfo>, String> {
-    let auth = build_authenticator().await?;
-
-    let client = Client::builder(TokioExecutor::new())
-        .build(
-            HttpsConnectorBuilder::new()
-                .with_native_roots()
-                .map_err(|e| e.to_string())?
-                .https_or_http()
-                .enable_http1()
-                .build(),
-        );
-
-    let hub = YouTube::new(client, auth);
+async fn list_playlists(app: AppHandle<Wry>, account: Option<String>) -> AppResult<Vec<PlaylistInfo>> {
+    let hub = youtube_hub(&app, account.as_deref()).await?;
+    quota::spend(&app, ApiMethod::PlaylistsList);
 
     let resp = hub
         .playlists()
         .list(&vec!["snippet".to_string()])
         .mine(true)
         .max_results(50)
+        .add_scope(auth::YOUTUBE_SCOPE)
         .doit()
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(api_error)?;
 
     let playlists = resp
         .1
@@ -741,30 +2187,82 @@ async fn list_playlists() -> Result<Vec<PlaylistInfo>, String> {
 }
 
 #[command]
-async fn generate_upload(window: WebviewWindow<Wry>, params: GenerateParams, queue_index: Option<usize>) -> Result<String, String> {
-    let output = generate_video(window.clone(), params.clone(), queue_index)?;
+async fn generate_upload(window: WebviewWindow<Wry>, params: GenerateParams, queue_index: Option<usize>) -> AppResult<UploadResult> {
+    generate_upload_job(window, params, queue_index, None).await.map(|(_, result)| result)
+}
+
+/// Render and upload a job. The render result is `None` when an interrupted
+/// upload was continued without rendering again.
+async fn generate_upload_job(
+    window: WebviewWindow<Wry>,
+    params: GenerateParams,
+    queue_index: Option<usize>,
+    job: Option<u64>,
+) -> AppResult<(Option<GenerateResult>, UploadResult)> {
+    validate_video_metadata(&params.video)?;
+    validate_caption_tracks(params.caption_tracks.as_deref().unwrap_or_default())?;
+    // A retried job whose upload was interrupted continues with the file it
+    // already rendered; the server has the metadata from the first attempt.
+    let dest = params.output.clone().unwrap_or_else(|| "output.mp4".to_string());
+    let resumable = job
+        .and_then(job_queue::upload_session)
+        .filter(|s| s.file == dest && s.is_usable(Utc::now()));
+    let (output, description, rendered) = match resumable {
+        Some(session) => (session.file, params.description.clone(), None),
+        None => {
+            quota::check_upload(&window.app_handle(), quota_limit(&window.app_handle()), upload_cost(&upload_options(&params)))?;
+            let rendered = render_video(&window, &params, queue_index)?;
+            (rendered.output.clone(), rendered.description.clone(), Some(rendered))
+        }
+    };
     let result = upload_video_impl(window.clone(), output.clone(), UploadOptions {
         title: params.title,
-        description: params.description,
+        description,
         tags: params.tags,
         publish_at: params.publish_at,
         thumbnail: params.thumbnail,
         privacy: params.privacy.clone(),
         playlist_id: params.playlist_id.clone(),
-        ..Default::default()
-    }, queue_index).await?;
+        caption_tracks: params.caption_tracks.clone(),
+        video: params.video.clone(),
+        account: params.account.clone(),
+    }, queue_index, job).await?;
     let _ = fs::remove_file(output);
-    Ok(result)
+    Ok((rendered, result))
 }
 
 #[command]
-async fn upload_videos(window: WebviewWindow<Wry>, files: Vec<String>, opts: Option<UploadOptions>) -> Result<Vec<String>, String> {
-    let mut results = Vec::new();
+async fn upload_videos(window: WebviewWindow<Wry>, files: Vec<String>, opts: Option<UploadOptions>) -> AppResult<BatchUploadResult> {
+    let app = window.app_handle();
+    let mut batch = BatchUploadResult::default();
     let o = opts.unwrap_or_default();
-    for file in files {
-        results.push(upload_video_impl(window.clone(), file, o.clone(), None).await?);
+    for (i, file) in files.iter().enumerate() {
+        let uploaded: AppResult<UploadResult> = async {
+            // Stop before the video that would run out of quota, not halfway through it.
+            quota::check_upload(&app, quota_limit(&app), upload_cost(&o))?;
+            upload_video_impl(window.clone(), file.clone(), o.clone(), None, None).await
+        }
+        .await;
+        match uploaded {
+            Ok(result) => batch.results.push(result),
+            Err(e) if e.holds_upload() => {
+                log(&app, "warn", &format!("upload_videos stopped for quota: {}", e));
+                batch.held = files[i..].to_vec();
+                break;
+            }
+            Err(e) => return Err(e),
+        }
     }
-    Ok(results)
+    Ok(batch)
+}
+
+/// Outcome of a batch upload that may have stopped at the daily quota.
+#[derive(Serialize, Default)]
+struct BatchUploadResult {
+    results: Vec<UploadResult>,
+    /// Files left when the quota ran out. `generate_batch_upload` queues
+    /// them, and the queue starts them after the quota resets.
+    held: Vec<String>,
 }
 
 #[derive(Deserialize)]
@@ -791,6 +2289,9 @@ struct BatchGenerateParams {
     privacy: Option<String>,
     #[serde(rename = "playlistId")]
     playlist_id: Option<String>,
+    #[serde(flatten)]
+    video: VideoMetadata,
+    account: Option<String>,
 }
 
 #[derive(Deserialize, Clone, Default)]
@@ -815,6 +2316,42 @@ struct WatchOptions {
     privacy: Option<String>,
     #[serde(rename = "playlistId")]
     playlist_id: Option<String>,
+    #[serde(flatten)]
+    video: VideoMetadata,
+    account: Option<String>,
+    /// How ID3 tags and cover art of new files are used: `fill` (default)
+    /// fills unset fields, `prefer` overrides these options, `off` ignores them.
+    #[serde(rename = "sourceMetadata")]
+    source_metadata: Option<String>,
+    /// File extensions that trigger a job. Defaults to `WATCH_EXTENSIONS`.
+    extensions: Option<Vec<String>>,
+}
+
+/// Audio extensions picked up by the directory watcher by default.
+const WATCH_EXTENSIONS: [&str; 5] = ["mp3", "wav", "m4a", "flac", "aac"];
+
+/// Destination for a watched file. Video inputs get a suffix so the render
+/// never overwrites its own source.
+fn watch_output_path(input: &Path) -> PathBuf {
+    if is_video(&input.to_string_lossy()) {
+        let stem = input.file_stem().and_then(|s| s.to_str()).unwrap_or("output");
+        input.with_file_name(format!("{}_yt.mp4", stem))
+    } else {
+        input.with_extension("mp4")
+    }
+}
+
+/// True when `path` is an output of a queued job, so the watcher does not
+/// pick up its own renders when video extensions are watched.
+fn is_queued_output(path: &Path) -> bool {
+    peek_all().iter().any(|item| {
+        let (params, dest) = match &item.job {
+            Job::Generate { params, dest } | Job::GenerateUpload { params, dest, .. } => (params, dest),
+        };
+        rendition_targets(params, dest)
+            .iter()
+            .any(|t| Path::new(&t.output) == path)
+    })
 }
 
 #[derive(Deserialize, Clone)]
@@ -826,59 +2363,100 @@ struct WatchDirectoryParams {
 }
 
 #[command]
-async fn generate_batch_upload(window: WebviewWindow<Wry>, params: BatchGenerateParams) -> Result<Vec<String>, String> {
-    let mut results = Vec::new();
-    for file in &params.files {
-        let out = if let Some(ref dir) = params.output_dir {
-            let name = Path::new(file)
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .unwrap_or("out");
-            Some(format!("{}/{}.mp4", dir, name))
-        } else {
-            None
-        };
-        let video = generate_video(window.clone(), GenerateParams {
-            file: file.clone(),
-            output: out.clone(),
-            captions: params.captions.clone(),
-            caption_options: params.caption_options.clone(),
-            background: params.background.clone(),
-            watermark: params.watermark.clone(),
-            watermark_position: params.watermark_position.clone(),
-            watermark_opacity: params.watermark_opacity,
-            watermark_scale: params.watermark_scale,
-            intro: params.intro.clone(),
-            outro: params.outro.clone(),
-            width: params.width,
-            height: params.height,
-            fps: params.fps,
-            title: params.title.clone(),
-            description: params.description.clone(),
-            tags: params.tags.clone(),
-            publish_at: params.publish_at.clone(),
-            thumbnail: params.thumbnail.clone(),
-            privacy: params.privacy.clone(),
-            playlist_id: params.playlist_id.clone(),
-        }, None)?;
-        let res = upload_video_impl(window.clone(), video.clone(), UploadOptions {
-            title: params.title.clone(),
-            description: params.description.clone(),
-            tags: params.tags.clone(),
-            publish_at: params.publish_at.clone(),
-            thumbnail: params.thumbnail.clone(),
-            privacy: params.privacy.clone(),
-            playlist_id: params.playlist_id.clone(),
-            ..Default::default()
-        }, None).await?;
-        let _ = fs::remove_file(video);
-        results.push(res);
+async fn generate_batch_upload(window: WebviewWindow<Wry>, params: BatchGenerateParams) -> AppResult<BatchUploadResult> {
+    let app = window.app_handle();
+    let mut batch = BatchUploadResult::default();
+    let cost = upload_cost(&UploadOptions {
+        thumbnail: params.thumbnail.clone(),
+        playlist_id: params.playlist_id.clone(),
+        video: params.video.clone(),
+        ..Default::default()
+    });
+    for (i, file) in params.files.iter().enumerate() {
+        let uploaded: AppResult<UploadResult> = async {
+            // Check before rendering so a batch stops before the video that would run out of quota.
+            quota::check_upload(&app, quota_limit(&app), cost)?;
+            let rendered = render_video(&window, &batch_file_params(&params, file), None)?;
+            let video = rendered.output;
+            let res = upload_video_impl(window.clone(), video.clone(), UploadOptions {
+                title: params.title.clone(),
+                description: rendered.description,
+                tags: params.tags.clone(),
+                publish_at: params.publish_at.clone(),
+                thumbnail: params.thumbnail.clone(),
+                privacy: params.privacy.clone(),
+                playlist_id: params.playlist_id.clone(),
+                video: params.video.clone(),
+                account: params.account.clone(),
+                ..Default::default()
+            }, None, None).await;
+            // A held upload renders again when its queued job runs.
+            if res.as_ref().map_or_else(AppError::holds_upload, |_| true) {
+                let _ = fs::remove_file(&video);
+            }
+            res
+        }
+        .await;
+        match uploaded {
+            Ok(result) => batch.results.push(result),
+            Err(e) if e.holds_upload() => {
+                log(&app, "warn", &format!("generate_batch_upload queued the rest for quota: {}", e));
+                for (n, file) in params.files.iter().enumerate().skip(i) {
+                    let gp = batch_file_params(&params, file);
+                    // Without an output dir every render would go to output.mp4.
+                    let dest = gp.output.clone().unwrap_or_else(|| {
+                        let stem = Path::new(file).file_stem().and_then(|s| s.to_str()).unwrap_or("out");
+                        format!("{}-{}.mp4", stem, n + 1)
+                    });
+                    let job = Job::GenerateUpload { params: gp, dest, thumbnail: None };
+                    let estimate = job_estimate(&app, &job);
+                    enqueue(&app, job, estimate)?;
+                }
+                batch.held = params.files[i..].to_vec();
+                break;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(batch)
+}
+
+/// Render params for one file of a batch.
+fn batch_file_params(params: &BatchGenerateParams, file: &str) -> GenerateParams {
+    let output = params.output_dir.as_ref().map(|dir| {
+        let name = Path::new(file).file_stem().and_then(|s| s.to_str()).unwrap_or("out");
+        format!("{}/{}.mp4", dir, name)
+    });
+    GenerateParams {
+        file: file.to_string(),
+        output,
+        captions: params.captions.clone(),
+        caption_options: params.caption_options.clone(),
+        background: params.background.clone(),
+        watermark: params.watermark.clone(),
+        watermark_position: params.watermark_position.clone(),
+        watermark_opacity: params.watermark_opacity,
+        watermark_scale: params.watermark_scale,
+        intro: params.intro.clone(),
+        outro: params.outro.clone(),
+        width: params.width,
+        height: params.height,
+        fps: params.fps,
+        title: params.title.clone(),
+        description: params.description.clone(),
+        tags: params.tags.clone(),
+        publish_at: params.publish_at.clone(),
+        thumbnail: params.thumbnail.clone(),
+        privacy: params.privacy.clone(),
+        playlist_id: params.playlist_id.clone(),
+        video: params.video.clone(),
+        account: params.account.clone(),
+        ..Default::default()
     }
-    Ok(results)
 }
 
 #[command]
-fn watch_directory(window: WebviewWindow<Wry>, params: WatchDirectoryParams) -> Result<(), String> {
+fn watch_directory(window: WebviewWindow<Wry>, params: WatchDirectoryParams) -> AppResult<()> {
     let mut guard = WATCHER.lock().unwrap();
     if let Some(mut w) = guard.take() {
         let _ = w.unwatch(Path::new(&params.dir));
@@ -888,6 +2466,13 @@ fn watch_directory(window: WebviewWindow<Wry>, params: WatchDirectoryParams) ->
     }
     let dir = params.dir.clone();
     let opts = params.options.clone().unwrap_or_default();
+    let extensions: Vec<String> = match opts.extensions {
+        Some(ref exts) => exts
+            .iter()
+            .map(|e| e.trim_start_matches('.').to_ascii_lowercase())
+            .collect(),
+        None => WATCH_EXTENSIONS.iter().map(|e| e.to_string()).collect(),
+    };
     let auto = params.auto_upload;
     let win = window.clone();
     start_queue_worker(win.clone());
@@ -901,8 +2486,8 @@ fn watch_directory(window: WebviewWindow<Wry>, params: WatchDirectoryParams) ->
                         if p.is_file() {
                             if let Some(ext) = p.extension().and_then(|s| s.to_str()) {
                                 let ext = ext.to_ascii_lowercase();
-                                if ["mp3", "wav", "m4a", "flac", "aac"].contains(&ext.as_str()) {
-                                    let gp = GenerateParams {
+                                if extensions.contains(&ext) && !is_queued_output(&p) {
+                                    let mut gp = GenerateParams {
                                         file: p.to_string_lossy().to_string(),
                                         output: None,
                                         captions: opts.captions.clone(),
@@ -924,14 +2509,26 @@ fn watch_directory(window: WebviewWindow<Wry>, params: WatchDirectoryParams) ->
                                         thumbnail: opts.thumbnail.clone(),
                                         privacy: opts.privacy.clone(),
                                         playlist_id: opts.playlist_id.clone(),
+                                        video: opts.video.clone(),
+                                        account: opts.account.clone(),
+                                        ..Default::default()
                                     };
-                                    let dest = p.with_extension("mp4").to_string_lossy().to_string();
+                                    let covers = app_handle
+                                        .path()
+                                        .app_cache_dir()
+                                        .unwrap_or_else(|_| std::env::temp_dir().join("ytapp_cache"))
+                                        .join("covers");
+                                    if let Err(e) = metadata::apply_source_metadata(&mut gp, opts.source_metadata.as_deref(), &covers) {
+                                        log(&app_handle, "error", &format!("source metadata: {}", e));
+                                    }
+                                    let dest = watch_output_path(&p).to_string_lossy().to_string();
                                     let job = if auto {
                                         Job::GenerateUpload { params: gp, dest, thumbnail: opts.thumbnail.clone() }
                                     } else {
                                         Job::Generate { params: gp, dest }
                                     };
-                                    let _ = enqueue(&app_handle, job);
+                                    let estimate = job_estimate(&app_handle, &job);
+                                    let _ = enqueue(&app_handle, job, estimate);
                                 }
                             }
                         }
@@ -940,19 +2537,19 @@ fn watch_directory(window: WebviewWindow<Wry>, params: WatchDirectoryParams) ->
             }
         },
         Config::default(),
-    ).map_err(|e| e.to_string())?;
+    )?;
     let mode = if params.recursive.unwrap_or(false) {
         RecursiveMode::Recursive
     } else {
         RecursiveMode::NonRecursive
     };
-    watcher.watch(Path::new(&dir), mode).map_err(|e| e.to_string())?;
+    watcher.watch(Path::new(&dir), mode)?;
     *guard = Some(watcher);
     Ok(())
 }
 
 #[command]
-fn watch_stop(_window: WebviewWindow<Wry>) -> Result<(), String> {
+fn watch_stop(_window: WebviewWindow<Wry>) -> AppResult<()> {
     let mut guard = WATCHER.lock().unwrap();
     if let Some(w) = guard.take() {
         drop(w);
@@ -961,14 +2558,15 @@ fn watch_stop(_window: WebviewWindow<Wry>) -> Result<(), String> {
 }
 
 #[command]
-fn load_settings(app: AppHandle<Wry>) -> Result<AppSettings, String> {
+fn load_settings(app: AppHandle<Wry>) -> AppResult<AppSettings> {
     let path = settings_path(&app)?;
     let data = match fs::read_to_string(&path) {
         Ok(d) => d,
         Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(AppSettings::default()),
-        Err(e) => return Err(e.to_string()),
+        Err(e) => return Err(e.into()),
     };
-    let mut settings: AppSettings = serde_json::from_str(&data).map_err(|e| e.to_string())?;
+    let mut settings: AppSettings = serde_json::from_str(&data)
+        .map_err(|e| AppError::new(ErrorKind::Config, format!("invalid settings file {}", path.display())).caused_by(&e))?;
     if settings.show_guide.is_none() {
         settings.show_guide = Some(true);
     }
@@ -1015,24 +2613,24 @@ fn load_settings(app: AppHandle<Wry>) -> Result<AppSettings, String> {
 }
 
 #[command]
-fn save_settings(app: AppHandle<Wry>, settings: AppSettings) -> Result<(), String> {
+fn save_settings(app: AppHandle<Wry>, settings: AppSettings) -> AppResult<()> {
     let path = settings_path(&app)?;
-    let data = serde_json::to_string(&settings).map_err(|e| e.to_string())?;
-    fs::write(path, data).map_err(|e| e.to_string())
+    let data = serde_json::to_string(&settings)?;
+    Ok(fs::write(path, data)?)
 }
 
 #[command]
-fn load_srt(path: String) -> Result<String, String> {
-    fs::read_to_string(path).map_err(|e| e.to_string())
+fn load_srt(path: String) -> AppResult<String> {
+    Ok(fs::read_to_string(path)?)
 }
 
 #[command]
-fn save_srt(path: String, data: String) -> Result<(), String> {
-    fs::write(path, data).map_err(|e| e.to_string())
+fn save_srt(path: String, data: String) -> AppResult<()> {
+    Ok(fs::write(path, data)?)
 }
 
 #[command]
-fn cancel_generate(window: WebviewWindow<Wry>) -> Result<(), String> {
+fn cancel_generate(window: WebviewWindow<Wry>) -> AppResult<()> {
     if let Some(child) = ACTIVE_FFMPEG.lock().unwrap().take() {
         let _ = child.lock().unwrap().kill();
         let _ = window.emit("generate_canceled", ());
@@ -1041,7 +2639,7 @@ fn cancel_generate(window: WebviewWindow<Wry>) -> Result<(), String> {
 }
 
 #[command]
-fn cancel_upload(window: WebviewWindow<Wry>) -> Result<(), String> {
+fn cancel_upload(window: WebviewWindow<Wry>) -> AppResult<()> {
     if let Some(handle) = ACTIVE_UPLOAD.lock().unwrap().take() {
         handle.abort();
         let _ = window.emit("upload_canceled", ());
@@ -1050,7 +2648,7 @@ fn cancel_upload(window: WebviewWindow<Wry>) -> Result<(), String> {
 }
 
 #[command]
-fn cancel_transcription(window: WebviewWindow<Wry>) -> Result<(), String> {
+fn cancel_transcription(window: WebviewWindow<Wry>) -> AppResult<()> {
     if let Some(handle) = ACTIVE_TRANSCRIBE.lock().unwrap().take() {
         handle.abort();
         let _ = window.emit("transcribe_canceled", ());
@@ -1059,61 +2657,62 @@ fn cancel_transcription(window: WebviewWindow<Wry>) -> Result<(), String> {
 }
 
 #[command]
-fn queue_add(app: AppHandle<Wry>, job: Job) -> Result<(), String> {
+fn queue_add(app: AppHandle<Wry>, job: Job) -> AppResult<()> {
     load_queue(&app).ok();
     log(&app, "info", "queue_add");
-    enqueue(&app, job)
+    let estimate = job_estimate(&app, &job);
+    enqueue(&app, job, estimate)
 }
 
 #[command]
-fn queue_list(app: AppHandle<Wry>) -> Result<Vec<QueueItem>, String> {
+fn queue_list(app: AppHandle<Wry>) -> AppResult<Vec<QueueItem>> {
     load_queue(&app).ok();
     Ok(peek_all())
 }
 
 #[command]
-fn queue_clear(app: AppHandle<Wry>) -> Result<(), String> {
+fn queue_clear(app: AppHandle<Wry>) -> AppResult<()> {
     load_queue(&app).ok();
     log(&app, "info", "queue_clear");
     clear_in_memory(&app)
 }
 
 #[command]
-fn queue_remove(app: AppHandle<Wry>, index: usize) -> Result<(), String> {
+fn queue_remove(app: AppHandle<Wry>, index: usize) -> AppResult<()> {
     load_queue(&app).ok();
     log(&app, "info", "queue_remove");
     job_queue::remove_job(&app, index)
 }
 
 #[command]
-fn queue_move(app: AppHandle<Wry>, from: usize, to: usize) -> Result<(), String> {
+fn queue_move(app: AppHandle<Wry>, from: usize, to: usize) -> AppResult<()> {
     load_queue(&app).ok();
     log(&app, "info", "queue_move");
     job_queue::move_job(&app, from, to)
 }
 
 #[command]
-fn queue_clear_completed(app: AppHandle<Wry>) -> Result<(), String> {
+fn queue_clear_completed(app: AppHandle<Wry>) -> AppResult<()> {
     load_queue(&app).ok();
     log(&app, "info", "queue_clear_completed");
     job_queue::clear_completed(&app)
 }
 
 #[command]
-fn queue_clear_failed(app: AppHandle<Wry>) -> Result<(), String> {
+fn queue_clear_failed(app: AppHandle<Wry>) -> AppResult<()> {
     load_queue(&app).ok();
     log(&app, "info", "queue_clear_failed");
     job_queue::clear_failed(&app)
 }
 
 #[command]
-fn queue_export(app: AppHandle<Wry>, path: String) -> Result<(), String> {
+fn queue_export(app: AppHandle<Wry>, path: String) -> AppResult<()> {
     load_queue(&app).ok();
     job_queue::export_queue(&app, &path)
 }
 
 #[command]
-fn queue_import(app: AppHandle<Wry>, path: String, append: Option<bool>) -> Result<(), String> {
+fn queue_import(app: AppHandle<Wry>, path: String, append: Option<bool>) -> AppResult<()> {
     load_queue(&app).ok();
     job_queue::import_queue(&app, &path, append.unwrap_or(false))
 }
@@ -1131,7 +2730,7 @@ fn queue_resume(_app: AppHandle<Wry>) {
 }
 
 #[command]
-async fn queue_process(window: WebviewWindow<Wry>, retry_failed: Option<bool>) -> Result<(), String> {
+async fn queue_process(window: WebviewWindow<Wry>, retry_failed: Option<bool>) -> AppResult<()> {
     let app = window.app_handle();
     load_queue(&app).ok();
     let settings = load_settings(app.clone()).unwrap_or_default();
@@ -1143,22 +2742,17 @@ async fn queue_process(window: WebviewWindow<Wry>, retry_failed: Option<bool>) -
             notify.notified().await;
             continue;
         }
-        let maybe = dequeue(&app, retry, max_retries)?;
+        let maybe = dequeue(&app, retry, max_retries, |job| job_fits_quota(&app, job))?;
         if let Some((idx, item)) = maybe {
-            let res: Result<(), String> = match item.job {
-                Job::Generate { mut params, dest } => {
-                    params.output = Some(dest);
-                    generate_video(window.clone(), params, Some(idx)).map(|_| ())
-                }
-            Job::GenerateUpload { mut params, dest, thumbnail } => {
-                params.output = Some(dest);
-                if params.thumbnail.is_none() { params.thumbnail = thumbnail.clone(); }
-                generate_upload(window.clone(), params, Some(idx)).await.map(|_| ())
-            }
-        };
-        match res {
-            Ok(_) => { mark_complete(&app, idx)?; log(&app, "info", "job_complete"); },
-            Err(e) => { log(&app, "error", &e); mark_failed(&app, idx, e)?; },
+        match run_job(&window, idx, item.id, item.job).await {
+            Ok((render, partial)) => {
+                mark_complete(&app, idx)?;
+                log_job_complete(&app, render.as_ref(), partial);
+            },
+            Err(e) => {
+                log(&app, "error", &e.to_string());
+                mark_failed(&app, idx, e)?;
+            },
         }
         } else {
             break;
@@ -1167,6 +2761,36 @@ async fn queue_process(window: WebviewWindow<Wry>, retry_failed: Option<bool>) -
     Ok(())
 }
 
+/// Run the dequeued job `id` at `idx`. Returns the render result, if the job
+/// rendered, and whether an upload left post-upload steps failed.
+async fn run_job(window: &WebviewWindow<Wry>, idx: usize, id: u64, job: Job) -> AppResult<(Option<GenerateResult>, bool)> {
+    match job {
+        Job::Generate { mut params, dest } => {
+            params.output = Some(dest);
+            render_video(window, &params, Some(idx)).map(|r| (Some(r), false))
+        }
+        Job::GenerateUpload { mut params, dest, thumbnail } => {
+            params.output = Some(dest);
+            if params.thumbnail.is_none() { params.thumbnail = thumbnail; }
+            generate_upload_job(window.clone(), params, Some(idx), Some(id)).await.map(|(r, up)| (r, up.partial))
+        }
+    }
+}
+
+fn log_job_complete(app: &AppHandle<Wry>, render: Option<&GenerateResult>, partial: bool) {
+    let status = if partial { "job_complete_partial" } else { "job_complete" };
+    match render {
+        Some(r) => log(app, "info", &format!(
+            "{}: {} ({}{:.1}x realtime)",
+            status,
+            r.outputs.join(", "),
+            if r.fast_path { "fast path, " } else { "" },
+            r.speed
+        )),
+        None => log(app, "info", status),
+    }
+}
+
 /// Continuously process queued jobs in the background.
 fn start_queue_worker(window: WebviewWindow<Wry>) {
     if WORKER_STARTED.swap(true, Ordering::SeqCst) {
@@ -1183,64 +2807,59 @@ fn start_queue_worker(window: WebviewWindow<Wry>) {
                 notify.notified().await;
                 continue;
             }
-            if let Some((idx, item)) = dequeue(&app, true, max_retries).unwrap_or(None) {
-                let res: Result<(), String> = match item.job {
-                    Job::Generate { mut params, dest } => {
-                        params.output = Some(dest);
-                        generate_video(window.clone(), params, Some(idx)).map(|_| ())
-                    }
-                    Job::GenerateUpload { mut params, dest, thumbnail } => {
-                        params.output = Some(dest);
-                        if params.thumbnail.is_none() { params.thumbnail = thumbnail.clone(); }
-                        generate_upload(window.clone(), params, Some(idx)).await.map(|_| ())
-                    }
-                };
-                match res {
-                    Ok(_) => {
+            if let Some((idx, item)) = dequeue(&app, true, max_retries, |job| job_fits_quota(&app, job)).unwrap_or(None) {
+                match run_job(&window, idx, item.id, item.job).await {
+                    Ok((render, partial)) => {
                         let _ = mark_complete(&app, idx);
-                        log(&app, "info", "job_complete");
+                        log_job_complete(&app, render.as_ref(), partial);
                         let _ = window.emit(
                             "queue_notify",
-                            QueueNotify { index: idx, success: true, error: None },
+                            QueueNotify { index: idx, success: true, partial, error: None, render },
                         );
                     }
                     Err(e) => {
-                        log(&app, "error", &e);
-                        let _ = mark_failed(&app, idx, e.clone());
+                        let message = e.to_string();
+                        log(&app, "error", &message);
+                        let _ = mark_failed(&app, idx, e);
                         let _ = window.emit(
                             "queue_notify",
-                            QueueNotify { index: idx, success: false, error: Some(e) },
+                            QueueNotify { index: idx, success: false, partial: false, error: Some(message), render: None },
                         );
                     }
                 }
             } else {
-                notify.notified().await;
+                // Uploads held for quota become runnable at the reset.
+                let _ = tokio::time::timeout(quota::until_reset(), notify.notified()).await;
             }
         }
     });
 }
 
 #[command]
-fn profile_list(app: AppHandle<Wry>) -> Result<Vec<String>, String> {
+fn profile_list(app: AppHandle<Wry>) -> AppResult<Vec<String>> {
     let settings = load_settings(app.clone())?;
     Ok(settings.profiles.keys().cloned().collect())
 }
 
 #[command]
-fn profile_get(app: AppHandle<Wry>, name: String) -> Result<Profile, String> {
+fn profile_get(app: AppHandle<Wry>, name: String) -> AppResult<Profile> {
     let settings = load_settings(app.clone())?;
-    settings.profiles.get(&name).cloned().ok_or_else(|| "profile not found".into())
+    settings
+        .profiles
+        .get(&name)
+        .cloned()
+        .ok_or_else(|| AppError::new(ErrorKind::NotFound, format!("profile {} not found", name)))
 }
 
 #[command]
-fn profile_save(app: AppHandle<Wry>, name: String, profile: Profile) -> Result<(), String> {
+fn profile_save(app: AppHandle<Wry>, name: String, profile: Profile) -> AppResult<()> {
     let mut settings = load_settings(app.clone())?;
     settings.profiles.insert(name, profile);
     save_settings(app, settings)
 }
 
 #[command]
-fn profile_delete(app: AppHandle<Wry>, name: String) -> Result<(), String> {
+fn profile_delete(app: AppHandle<Wry>, name: String) -> AppResult<()> {
     let mut settings = load_settings(app.clone())?;
     settings.profiles.remove(&name);
     save_settings(app, settings)
@@ -1254,7 +2873,7 @@ struct TranscribeParams {
 }
 
 #[command]
-async fn transcribe_audio(window: WebviewWindow<Wry>, params: TranscribeParams) -> Result<String, String> {
+async fn transcribe_audio(window: WebviewWindow<Wry>, params: TranscribeParams) -> AppResult<String> {
     let audio_path = PathBuf::from(&params.file);
     let srt_path = audio_path.with_extension("srt");
 
@@ -1276,9 +2895,9 @@ async fn transcribe_audio(window: WebviewWindow<Wry>, params: TranscribeParams)
         let mut whisper = Whisper::new(Model::new(size), lang).await;
         let transcript = whisper
             .transcribe(&audio_path, false, false)
-            .map_err(|e| e.to_string())?;
-        std::fs::write(&srt_path, transcript.as_srt()).map_err(|e| e.to_string())?;
-        Ok::<_, String>(srt_path.to_string_lossy().to_string())
+            .map_err(|e| format!("transcription failed: {}", e))?;
+        std::fs::write(&srt_path, transcript.as_srt())?;
+        Ok::<_, AppError>(srt_path.to_string_lossy().to_string())
     };
 
     let result = Abortable::new(fut, reg).await;
@@ -1292,14 +2911,14 @@ async fn transcribe_audio(window: WebviewWindow<Wry>, params: TranscribeParams)
         Ok(Err(e)) => Err(e),
         Err(_) => {
             let _ = window.emit("transcribe_canceled", ());
-            Err("transcription canceled".into())
+            Err(AppError::canceled("transcription"))
         }
     }
 }
 
 /// Scan common font directories and return available fonts.
 #[command]
-fn list_fonts() -> Result<Vec<SystemFont>, String> {
+fn list_fonts() -> AppResult<Vec<SystemFont>> {
     list_fonts_inner()
 }
 
@@ -1331,7 +2950,7 @@ fn scan_font_dirs(dirs: &[PathBuf]) -> Vec<SystemFont> {
 }
 
 #[cfg(target_os = "windows")]
-fn list_fonts_inner() -> Result<Vec<SystemFont>, String> {
+fn list_fonts_inner() -> AppResult<Vec<SystemFont>> {
     let windir = std::env::var("WINDIR").unwrap_or_else(|_| "C:\\Windows".into());
     let mut dirs = vec![PathBuf::from(windir).join("Fonts")];
     if let Ok(local) = std::env::var("LOCALAPPDATA") {
@@ -1342,7 +2961,7 @@ fn list_fonts_inner() -> Result<Vec<SystemFont>, String> {
 }
 
 #[cfg(target_os = "macos")]
-fn list_fonts_inner() -> Result<Vec<SystemFont>, String> {
+fn list_fonts_inner() -> AppResult<Vec<SystemFont>> {
     let mut dirs = vec![
         PathBuf::from("/System/Library/Fonts"),
         PathBuf::from("/Library/Fonts"),
@@ -1354,7 +2973,7 @@ fn list_fonts_inner() -> Result<Vec<SystemFont>, String> {
 }
 
 #[cfg(target_os = "linux")]
-fn list_fonts_inner() -> Result<Vec<SystemFont>, String> {
+fn list_fonts_inner() -> AppResult<Vec<SystemFont>> {
     if let Ok(output) = Command::new("fc-list")
         .args(["-f", "%{family}||%{style}||%{file}\n"])
         .output()
@@ -1391,16 +3010,29 @@ fn list_fonts_inner() -> Result<Vec<SystemFont>, String> {
 }
 
 #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
-fn list_fonts_inner() -> Result<Vec<SystemFont>, String> {
+fn list_fonts_inner() -> AppResult<Vec<SystemFont>> {
     Ok(Vec::new())
 }
 
+/// Version and feature support of the installed ffmpeg. Cached after the
+/// first probe unless `refresh` is set.
+#[command]
+fn ffmpeg_capabilities(refresh: Option<bool>) -> AppResult<CapabilityReport> {
+    Ok(ffmpeg_caps::capabilities(refresh.unwrap_or(false))?.report())
+}
+
 #[command]
-fn verify_dependencies(app: AppHandle<Wry>) -> Result<(), String> {
-    if Command::new("ffmpeg").arg("-version").output().is_err() {
-        log(&app, "error", "FFmpeg is required and not found in PATH");
-        return Err("ffmpeg not found".into());
+fn verify_dependencies(app: AppHandle<Wry>) -> AppResult<()> {
+    let caps = ffmpeg_caps::capabilities(true).inspect_err(|e| log(&app, "error", &e.to_string()))?;
+    let report = caps.report();
+    log(&app, "info", &format!("ffmpeg {}", report.version.as_deref().unwrap_or("(unknown version)")));
+    if report.version_supported == Some(false) {
+        log(&app, "warn", &format!("ffmpeg is older than {}; some features may fail", report.min_version));
     }
+    for status in report.features.iter().filter(|s| !s.available) {
+        log(&app, "warn", &format!("ffmpeg lacks {}; {} disabled", status.missing.join(", "), status.feature.label()));
+    }
+    caps.require(&[Feature::Render])?;
 
     // Accept multiple ways to locate Argos Translate on Windows and other OSes.
     // Try the CLI first, then fall back to `py -m argostranslate` and
@@ -1416,7 +3048,8 @@ fn verify_dependencies(app: AppHandle<Wry>) -> Result<(), String> {
     }();
     if !argos_ok {
         log(&app, "error", "Argos Translate is required and not found (tried argos-translate and Python -m)");
-        return Err("argos-translate not found".into());
+        return Err(AppError::new(ErrorKind::Config, "argos-translate not found")
+            .with_hint("Install Argos Translate with `pip install argostranslate`."));
     }
 
     let size = {
@@ -1442,7 +3075,7 @@ fn verify_dependencies(app: AppHandle<Wry>) -> Result<(), String> {
     Ok(())
 }
 
-fn tauri_deps_script_path() -> Result<PathBuf, String> {
+fn tauri_deps_script_path() -> AppResult<PathBuf> {
     let name = if cfg!(target_os = "linux") {
         "install_tauri_deps.sh"
     } else if cfg!(target_os = "macos") {
@@ -1458,10 +3091,10 @@ fn tauri_deps_script_path() -> Result<PathBuf, String> {
 }
 
 #[command]
-fn install_tauri_deps() -> Result<(), String> {
+fn install_tauri_deps() -> AppResult<()> {
     let script = tauri_deps_script_path()?;
     if !script.exists() {
-        return Err("install script not found".into());
+        return Err(AppError::new(ErrorKind::NotFound, "install script not found"));
     }
     let mut cmd = if cfg!(target_os = "windows") {
         let mut c = Command::new("powershell");
@@ -1472,11 +3105,11 @@ fn install_tauri_deps() -> Result<(), String> {
         c.arg(&script);
         c
     };
-    let status = cmd.status().map_err(|e| e.to_string())?;
+    let status = cmd.status()?;
     if status.success() {
         Ok(())
     } else {
-        Err(format!("script exited with status {:?}", status.code()))
+        Err(format!("script exited with status {:?}", status.code()).into())
     }
 }
 
@@ -1486,12 +3119,12 @@ fn get_logs(
     max_lines: Option<usize>,
     level: Option<String>,
     search: Option<String>,
-) -> Result<String, String> {
+) -> AppResult<String> {
     read_logs(&app, max_lines.unwrap_or(200), level, search)
 }
 
 #[command(rename = "clear_logs")]
-fn clear_logs_cmd(app: AppHandle<Wry>) -> Result<(), String> {
+fn clear_logs_cmd(app: AppHandle<Wry>) -> AppResult<()> {
     clear_logs(&app)
 }
 
@@ -1502,12 +3135,17 @@ fn main() {
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_shell::init())
         .setup(|app| {
+            let settings = load_settings(app.handle().clone()).unwrap_or_default();
+            let removed = workspace::sweep(&scratch_root(&settings));
+            if removed > 0 {
+                log(app.handle(), "info", &format!("removed {} leftover scratch entries", removed));
+            }
             if let Some(win) = app.get_webview_window("main") {
                 start_queue_worker(win);
             }
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![generate_video, upload_video, upload_videos, transcribe_audio, generate_upload, generate_batch_upload, watch_directory, watch_stop, youtube_sign_in, youtube_sign_out, youtube_is_signed_in, list_playlists, load_settings, save_settings, load_srt, save_srt, cancel_generate, cancel_upload, cancel_transcription, queue_add, queue_list, queue_remove, queue_move, queue_clear, queue_clear_completed, queue_clear_failed, queue_export, queue_import, queue_pause, queue_resume, queue_process, profile_list, profile_get, profile_save, profile_delete, verify_dependencies, install_tauri_deps, list_fonts, get_logs, clear_logs_cmd])
+        .invoke_handler(tauri::generate_handler![generate_video, generate_preview, generate_plan, cache_info, cache_clear, upload_video, upload_incomplete, upload_retry_steps, quota_status, account_list, account_add, account_remove, token_unlock, token_rotate_key, upload_videos, transcribe_audio, generate_upload, generate_batch_upload, watch_directory, watch_stop, youtube_sign_in, youtube_sign_out, youtube_is_signed_in, youtube_sign_in_status, youtube_import_tokens, youtube_export_tokens, list_playlists, load_settings, save_settings, load_srt, save_srt, cancel_generate, cancel_upload, cancel_transcription, queue_add, queue_list, queue_remove, queue_move, queue_clear, queue_clear_completed, queue_clear_failed, queue_export, queue_import, queue_pause, queue_resume, queue_process, profile_list, profile_get, profile_save, profile_delete, verify_dependencies, ffmpeg_capabilities, install_tauri_deps, list_fonts, get_logs, clear_logs_cmd])
         .run(context)
         .expect("error while running tauri application");
 }
@@ -1616,4 +3254,234 @@ mod tests {
     fn parse_publish_at_invalid() {
         assert!(parse_publish_at("not a date").is_none());
     }
+
+    #[test]
+    fn rendition_targets_derive_outputs() {
+        let params = GenerateParams {
+            file: "a.mp3".into(),
+            width: Some(1920),
+            height: Some(1080),
+            renditions: Some(vec![
+                schema::Rendition { name: Some("shorts".into()), width: Some(1080), height: Some(1920), ..Default::default() },
+                schema::Rendition { bitrate: Some("2M".into()), ..Default::default() },
+            ]),
+            ..Default::default()
+        };
+        let targets = rendition_targets(&params, "out/ep1.mp4");
+        assert_eq!(targets.len(), 3);
+        assert_eq!(targets[0].output, "out/ep1.mp4");
+        assert_eq!(Path::new(&targets[1].output), Path::new("out/ep1_shorts.mp4"));
+        assert_eq!((targets[1].width, targets[1].height), (1080, 1920));
+        assert_eq!(Path::new(&targets[2].output), Path::new("out/ep1_2.mp4"));
+        assert_eq!((targets[2].width, targets[2].height), (1920, 1080));
+        assert_eq!(targets[2].bitrate.as_deref(), Some("2M"));
+    }
+
+    #[test]
+    fn preview_cache_key_tracks_params() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        let params = GenerateParams { file: "a.mp3".into(), ..Default::default() };
+        let range = TimeRange { start: 5.0, duration: 10.0 };
+        let a = preview_path(dir, &params, range, "mp4").unwrap();
+        assert_eq!(a, preview_path(dir, &params, range, "mp4").unwrap());
+        let changed = GenerateParams { watermark_position: Some("top-left".into()), ..params.clone() };
+        assert_ne!(a, preview_path(dir, &changed, range, "mp4").unwrap());
+        assert_ne!(a, preview_path(dir, &params, TimeRange { start: 6.0, duration: 10.0 }, "mp4").unwrap());
+
+        let font = dir.join("a.ttf");
+        fs::write(&font, b"font").unwrap();
+        let text = schema::TextWatermark { text: "hi".into(), font_path: Some(font.to_string_lossy().into()), ..Default::default() };
+        let params = GenerateParams { watermark_text: Some(text), ..params };
+        let before = preview_path(dir, &params, range, "mp4").unwrap();
+        fs::write(&font, b"edited font").unwrap();
+        assert_ne!(before, preview_path(dir, &params, range, "mp4").unwrap());
+    }
+
+    #[test]
+    fn old_previews_are_pruned() {
+        let dir = tempfile::tempdir().unwrap();
+        let preview = dir.path().join("preview.mp4");
+        fs::write(&preview, b"video").unwrap();
+        prune_previews(dir.path(), PREVIEW_MAX_AGE);
+        assert!(preview.exists());
+        prune_previews(dir.path(), std::time::Duration::ZERO);
+        assert!(!preview.exists());
+    }
+
+    #[test]
+    fn output_cache_key_tracks_font_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let audio = dir.path().join("a.mp3");
+        let font = dir.path().join("a.ttf");
+        let copy = dir.path().join("b.ttf");
+        fs::write(&audio, b"audio").unwrap();
+        fs::write(&font, b"font").unwrap();
+        fs::write(&copy, b"font").unwrap();
+        let with_font = |path: &Path| GenerateParams {
+            file: audio.to_string_lossy().into(),
+            caption_options: Some(CaptionOptions { font_path: Some(path.to_string_lossy().into()), ..Default::default() }),
+            ..Default::default()
+        };
+        let keys = |params: &GenerateParams| {
+            output_cache_keys(params, &rendition_targets(params, "out.mp4"), &EncodingOptions::default()).unwrap()
+        };
+        let a = keys(&with_font(&font));
+        assert_eq!(a, keys(&with_font(&copy)));
+        fs::write(&font, b"edited font").unwrap();
+        assert_ne!(a, keys(&with_font(&font)));
+    }
+
+    #[test]
+    fn watermark_offsets() {
+        let mut params = GenerateParams { file: "a.mp3".into(), watermark_position: Some("bottom-left".into()), ..Default::default() };
+        assert_eq!(watermark_xy(&params, "W-w", "H-h").unwrap(), ("10".to_string(), "H-h-10".to_string()));
+        params.watermark_margin = Some(24);
+        params.watermark_x = Some("50%".into());
+        params.watermark_y = Some("-40".into());
+        assert_eq!(watermark_xy(&params, "W-w", "H-h").unwrap(), ("(W-w)*0.5000".to_string(), "H-h-40".to_string()));
+        params.watermark_x = Some("left".into());
+        assert_eq!(watermark_xy(&params, "W-w", "H-h").unwrap_err().kind, ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn watermark_time_window() {
+        let params = GenerateParams { file: "a.mp3".into(), watermark_start: Some(120.0), watermark_end: Some(130.0), ..Default::default() };
+        assert_eq!(watermark_enable(&params, 0.0).as_deref(), Some("between(t,120.000,130.000)"));
+        assert_eq!(watermark_enable(&params, 115.0).as_deref(), Some("between(t,5.000,15.000)"));
+        assert!(watermark_enable(&GenerateParams::default(), 0.0).is_none());
+    }
+
+    #[test]
+    fn drawtext_escapes_special_characters() {
+        assert_eq!(escape_drawtext("Ep 1: it's"), "Ep 1\\\\: it\\\\\\'s");
+    }
+
+    #[test]
+    fn watch_output_avoids_overwriting_video_source() {
+        assert_eq!(watch_output_path(Path::new("in/ep1.mp3")), Path::new("in/ep1.mp4"));
+        assert_eq!(watch_output_path(Path::new("in/cam.MP4")), Path::new("in/cam_yt.mp4"));
+    }
+
+    #[test]
+    fn video_input_is_letterboxed() {
+        let params = GenerateParams { file: "cam.mov".into(), ..Default::default() };
+        let targets = rendition_targets(&params, "o.mp4");
+        let filter = rendition_filter(&params, &targets[0], "0:v", "v0", 0.0).unwrap();
+        assert!(filter.contains("force_original_aspect_ratio=decrease,pad=1280:720"));
+        assert!(!is_static_render(&params));
+    }
+
+    #[test]
+    fn silence_input_matches_sample_rate() {
+        let enc = EncodingOptions { sample_rate: Some(44100), ..Default::default() };
+        let args = silence_input(3.5, &enc);
+        assert_eq!(args[..4], ["-f", "lavfi", "-t", "3.5"]);
+        assert_eq!(args[5], "anullsrc=r=44100:cl=stereo");
+    }
+
+    #[test]
+    fn static_render_detection() {
+        let mut params = GenerateParams { file: "a.mp3".into(), background: Some("bg.png".into()), ..Default::default() };
+        assert!(is_static_render(&params));
+        params.captions = Some("a.srt".into());
+        assert!(!is_static_render(&params));
+        params.captions = None;
+        params.background = Some("loop.mp4".into());
+        assert!(!is_static_render(&params));
+        params.background = None;
+        params.watermark = Some("badge.gif".into());
+        assert!(!is_static_render(&params));
+    }
+
+    #[test]
+    fn rendition_filter_labels_output() {
+        let params = GenerateParams { file: "a.mp3".into(), ..Default::default() };
+        let targets = rendition_targets(&params, "o.mp4");
+        assert_eq!(rendition_filter(&params, &targets[0], "s0", "v0", 0.0).unwrap(), "[s0]scale=1280x720[v0]");
+    }
+
+    #[test]
+    fn dry_run_records_concat_without_writing() {
+        let root = tempfile::tempdir().unwrap();
+        let ctx = RenderContext {
+            enc: EncodingOptions::default(),
+            still: None,
+            workspace: Workspace::planned(root.path()),
+            cache: None,
+            plan: Some(Recorder::dry_run()),
+        };
+        let segments = vec![ctx.temp_file("segment", "mp4"), ctx.temp_file("main0", "mp4")];
+        let out = root.path().join("out.mp4");
+        concat_segments(&segments, out.to_str().unwrap(), &ctx).unwrap();
+        // A single segment is moved into place rather than concatenated.
+        concat_segments(&segments[..1], out.to_str().unwrap(), &ctx).unwrap();
+        assert!(!ctx.workspace.path().exists());
+        assert!(!out.exists());
+        let plan = ctx.plan.unwrap().finish(vec![]);
+        assert_eq!(plan.temp_files.len(), 3);
+        assert_eq!(plan.files.len(), 1);
+        assert!(plan.files[0].contents.starts_with("file '"));
+        assert_eq!(plan.steps.len(), 1);
+        assert!(plan.steps[0].args.contains(&"concat".to_string()));
+        assert_eq!(plan.moves[0].to, out.to_string_lossy());
+    }
+
+    #[test]
+    fn size_estimate_covers_renditions_and_still_path() {
+        let mut params = GenerateParams { file: "a.mp3".into(), ..Default::default() };
+        let enc = EncodingOptions::default();
+        let single = estimate_size(&params, &rendition_targets(&params, "o.mp4"), &enc, None, 600.0);
+        assert!(single.output_bytes > 0);
+        assert_eq!(single.intermediate_bytes, 2 * single.output_bytes);
+        params.renditions = Some(vec![schema::Rendition { name: Some("copy".into()), ..Default::default() }]);
+        let double = estimate_size(&params, &rendition_targets(&params, "o.mp4"), &enc, None, 600.0);
+        assert_eq!(double.output_bytes, 2 * single.output_bytes);
+        params.renditions = None;
+        let still = StillRender { low_fps: true, copy_audio: false };
+        let fast = estimate_size(&params, &rendition_targets(&params, "o.mp4"), &enc, Some(still), 600.0);
+        assert!(fast.output_bytes < single.output_bytes);
+    }
+
+    #[test]
+    fn caption_tracks_are_validated_before_upload() {
+        let dir = tempfile::tempdir().unwrap();
+        let srt = dir.path().join("en.srt");
+        fs::write(&srt, "1\n00:00:00,000 --> 00:00:01,000\nhi\n").unwrap();
+        let track = |file: &Path, language: &str| CaptionTrack {
+            file: file.to_string_lossy().to_string(),
+            language: language.into(),
+            name: None,
+            draft: None,
+        };
+        assert!(validate_caption_tracks(&[track(&srt, "en")]).is_ok());
+        assert_eq!(validate_caption_tracks(&[track(&srt, " ")]).unwrap_err().kind, ErrorKind::InvalidInput);
+        let txt = dir.path().join("en.txt");
+        assert_eq!(validate_caption_tracks(&[track(&txt, "en")]).unwrap_err().kind, ErrorKind::InvalidInput);
+        let missing = dir.path().join("de.vtt");
+        assert_eq!(validate_caption_tracks(&[track(&missing, "de")]).unwrap_err().kind, ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn video_metadata_validation() {
+        let ok = VideoMetadata {
+            category_id: Some("22".into()),
+            default_language: Some("pt-BR".into()),
+            license: Some("creativeCommon".into()),
+            recording_date: Some("2024-05-01".into()),
+            ..Default::default()
+        };
+        assert!(validate_video_metadata(&ok).is_ok());
+        for bad in [
+            VideoMetadata { category_id: Some("music".into()), ..Default::default() },
+            VideoMetadata { default_audio_language: Some("english".into()), ..Default::default() },
+            VideoMetadata { license: Some("cc".into()), ..Default::default() },
+            VideoMetadata { recording_date: Some("yesterday".into()), ..Default::default() },
+        ] {
+            assert_eq!(validate_video_metadata(&bad).unwrap_err().kind, ErrorKind::InvalidInput);
+        }
+        let params: GenerateParams = serde_json::from_str(r#"{"file": "a.mp3", "categoryId": "10", "madeForKids": false}"#).unwrap();
+        assert_eq!(params.video.category_id.as_deref(), Some("10"));
+        assert_eq!(params.video.made_for_kids, Some(false));
+    }
 }
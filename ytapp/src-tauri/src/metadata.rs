@@ -0,0 +1,386 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use sha2::{Digest, Sha256};
+
+use crate::schema::{Chapter, GenerateParams};
+
+/// Shortest chapter YouTube accepts in a description.
+const MIN_CHAPTER_SECONDS: f64 = 10.0;
+/// A pause in speech of at least this long may start a transcript chapter.
+const TRANSCRIPT_GAP_SECONDS: f64 = 3.0;
+/// Transcript chapters are at least this long unless the talk runs on.
+const TRANSCRIPT_MIN_CHAPTER: f64 = 60.0;
+/// Start a new transcript chapter at the next cue after this long regardless of pauses.
+const TRANSCRIPT_MAX_CHAPTER: f64 = 300.0;
+const TRANSCRIPT_TITLE_WORDS: usize = 6;
+
+pub fn chapters_probe(file: &str) -> Command {
+    let mut cmd = Command::new("ffprobe");
+    cmd.args(["-v", "error", "-show_chapters", "-of", "json", file]);
+    cmd
+}
+
+/// Read chapter markers (ID3 CHAP frames, MP4 chapters) from a media file.
+pub fn probe_chapters(file: &str) -> Result<Vec<Chapter>, String> {
+    let output = chapters_probe(file)
+        .output()
+        .map_err(|e| format!("failed to run ffprobe: {}", e))?;
+    if !output.status.success() {
+        return Err("ffprobe failed".into());
+    }
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).map_err(|e| e.to_string())?;
+    let mut chapters = Vec::new();
+    for (i, ch) in json["chapters"].as_array().cloned().unwrap_or_default().iter().enumerate() {
+        let start = ch["start_time"]
+            .as_str()
+            .and_then(|s| s.parse::<f64>().ok())
+            .unwrap_or(0.0);
+        let title = ch["tags"]["title"]
+            .as_str()
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| format!("Chapter {}", i + 1));
+        chapters.push(Chapter { start, title });
+    }
+    Ok(chapters)
+}
+
+/// Tags and cover art found in a source audio file.
+#[derive(Debug, Default, PartialEq)]
+pub struct SourceTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub description: Option<String>,
+    pub episode: Option<u32>,
+    pub tags: Option<Vec<String>>,
+    /// Index of an attached picture stream, if any.
+    pub cover_stream: Option<u64>,
+}
+
+fn tag<'a>(tags: &'a serde_json::Value, names: &[&str]) -> Option<&'a str> {
+    let map = tags.as_object()?;
+    names.iter().find_map(|name| {
+        map.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .and_then(|(_, v)| v.as_str())
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+    })
+}
+
+/// Extract tags from `ffprobe -show_format -show_streams -of json` output.
+pub fn parse_source_tags(json: &serde_json::Value) -> SourceTags {
+    let tags = &json["format"]["tags"];
+    let episode = tag(tags, &["episode_sort", "episode_id", "track"])
+        .and_then(|t| t.split('/').next())
+        .and_then(|t| t.trim().parse::<u32>().ok());
+    let keywords: Vec<String> = [tag(tags, &["keywords"]), tag(tags, &["genre"])]
+        .into_iter()
+        .flatten()
+        .flat_map(|t| t.split([',', ';']))
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect();
+    let cover_stream = json["streams"].as_array().and_then(|streams| {
+        streams
+            .iter()
+            .find(|s| s["disposition"]["attached_pic"].as_i64() == Some(1))
+            .and_then(|s| s["index"].as_u64())
+    });
+    SourceTags {
+        title: tag(tags, &["title"]).map(|s| s.to_string()),
+        artist: tag(tags, &["artist", "album_artist"]).map(|s| s.to_string()),
+        description: tag(tags, &["description", "comment", "synopsis"]).map(|s| s.to_string()),
+        episode,
+        tags: if keywords.is_empty() { None } else { Some(keywords) },
+        cover_stream,
+    }
+}
+
+pub fn probe_source_tags(file: &str) -> Result<SourceTags, String> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-show_format", "-show_streams", "-of", "json", file])
+        .output()
+        .map_err(|e| format!("failed to run ffprobe: {}", e))?;
+    if !output.status.success() {
+        return Err("ffprobe failed".into());
+    }
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).map_err(|e| e.to_string())?;
+    Ok(parse_source_tags(&json))
+}
+
+/// Copy the attached picture stream of `file` into `dest`.
+pub fn extract_cover_art(file: &str, stream: u64, dest: &Path) -> Result<(), String> {
+    let status = Command::new("ffmpeg")
+        .args(["-y", "-v", "error", "-i", file, "-map", &format!("0:{}", stream), "-frames:v", "1"])
+        .arg(dest)
+        .status()
+        .map_err(|e| format!("failed to start ffmpeg: {}", e))?;
+    if status.success() { Ok(()) } else { Err("failed to extract cover art".into()) }
+}
+
+/// Where the cover art of `file` is extracted to inside `cover_dir`, so the
+/// user's folder is left untouched and each source gets its own file.
+fn cover_path(file: &str, cover_dir: &Path) -> PathBuf {
+    let digest = hex::encode(Sha256::digest(file.as_bytes()));
+    cover_dir.join(format!("cover-{}.jpg", &digest[..16]))
+}
+
+/// Fill `params` from the tags and cover art of its source file. Cover art
+/// is extracted into `cover_dir`.
+///
+/// `precedence` is `fill` (default) to only use source values for fields
+/// that are unset, `prefer` to let source values override explicit ones,
+/// or `off` to ignore the source metadata entirely.
+pub fn apply_source_metadata(params: &mut GenerateParams, precedence: Option<&str>, cover_dir: &Path) -> Result<(), String> {
+    let prefer = match precedence.unwrap_or("fill") {
+        "off" => return Ok(()),
+        "prefer" => true,
+        _ => false,
+    };
+    let src = probe_source_tags(&params.file)?;
+    fn pick<T>(field: &mut Option<T>, value: Option<T>, prefer: bool) {
+        if value.is_some() && (prefer || field.is_none()) {
+            *field = value;
+        }
+    }
+    if let Some(stream) = src.cover_stream {
+        if prefer || params.background.is_none() {
+            std::fs::create_dir_all(cover_dir).map_err(|e| format!("{}: {}", cover_dir.display(), e))?;
+            let cover = cover_path(&params.file, cover_dir);
+            if extract_cover_art(&params.file, stream, &cover).is_ok() {
+                params.background = Some(cover.to_string_lossy().to_string());
+            }
+        }
+    }
+    pick(&mut params.title, src.title, prefer);
+    pick(&mut params.artist, src.artist, prefer);
+    pick(&mut params.description, src.description, prefer);
+    pick(&mut params.episode, src.episode, prefer);
+    pick(&mut params.tags, src.tags, prefer);
+    Ok(())
+}
+
+fn parse_srt_time(s: &str) -> Option<f64> {
+    let s = s.trim().replace(',', ".");
+    let mut parts = s.split(':');
+    let h = parts.next()?.parse::<f64>().ok()?;
+    let m = parts.next()?.parse::<f64>().ok()?;
+    let sec = parts.next()?.parse::<f64>().ok()?;
+    Some(h * 3600.0 + m * 60.0 + sec)
+}
+
+/// Derive chapters from an SRT transcript by splitting at pauses in speech.
+/// Each chapter is titled with the first words spoken in it.
+pub fn transcript_chapters(srt: &str) -> Vec<Chapter> {
+    let mut chapters: Vec<Chapter> = Vec::new();
+    let mut last_end = 0.0;
+    let mut lines = srt.lines();
+    while let Some(line) = lines.next() {
+        let Some((from, to)) = line.split_once("-->") else { continue };
+        let (Some(start), Some(end)) = (parse_srt_time(from), parse_srt_time(to)) else { continue };
+        let mut text = Vec::new();
+        for l in lines.by_ref() {
+            if l.trim().is_empty() {
+                break;
+            }
+            text.push(l.trim());
+        }
+        let since = chapters.last().map(|c| start - c.start).unwrap_or(f64::MAX);
+        let paused = start - last_end >= TRANSCRIPT_GAP_SECONDS;
+        if since >= TRANSCRIPT_MAX_CHAPTER || (since >= TRANSCRIPT_MIN_CHAPTER && paused) {
+            let spoken = text.join(" ");
+            let words: Vec<&str> = spoken.split_whitespace().take(TRANSCRIPT_TITLE_WORDS).collect();
+            let title = if words.is_empty() {
+                format!("Chapter {}", chapters.len() + 1)
+            } else {
+                words.join(" ")
+            };
+            let start = if chapters.is_empty() { 0.0 } else { start };
+            chapters.push(Chapter { start, title });
+        }
+        last_end = end;
+    }
+    chapters
+}
+
+/// `chapterSource` of a job. Defaults to the explicit list when there is
+/// one, else to chapters embedded in the source audio.
+pub fn chapter_source(params: &GenerateParams) -> &str {
+    params.chapter_source.as_deref().unwrap_or(if params.chapters.is_some() { "manual" } else { "audio" })
+}
+
+/// Resolve the chapter list for a job from `chapterSource`.
+pub fn resolve_chapters(params: &GenerateParams) -> Result<Vec<Chapter>, String> {
+    let mut chapters = match chapter_source(params) {
+        "manual" => params.chapters.clone().unwrap_or_default(),
+        "audio" => probe_chapters(&params.file).unwrap_or_default(),
+        "transcript" => {
+            let path = params.captions.as_ref().ok_or("transcript chapters require captions")?;
+            let srt = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+            transcript_chapters(&srt)
+        }
+        "none" => Vec::new(),
+        other => return Err(format!("unknown chapter source: {}", other)),
+    };
+    chapters.sort_by(|a, b| a.start.total_cmp(&b.start));
+    Ok(chapters)
+}
+
+fn escape_ffmetadata(value: &str) -> String {
+    let mut out = String::new();
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Build an FFMETADATA1 document with the job's tags and chapters.
+/// `offset` shifts chapters past the intro; `total` ends the last chapter.
+pub fn ffmetadata(params: &GenerateParams, chapters: &[Chapter], offset: f64, total: f64) -> String {
+    let mut out = String::from(";FFMETADATA1\n");
+    let tags = [
+        ("title", params.title.clone()),
+        ("artist", params.artist.clone()),
+        ("comment", params.description.clone()),
+        ("description", params.description.clone()),
+        ("season_number", params.season.map(|s| s.to_string())),
+        ("episode_sort", params.episode.map(|e| e.to_string())),
+        ("episode_id", params.episode.map(|e| e.to_string())),
+    ];
+    for (key, value) in tags {
+        if let Some(v) = value {
+            out.push_str(&format!("{}={}\n", key, escape_ffmetadata(&v)));
+        }
+    }
+    for (i, ch) in chapters.iter().enumerate() {
+        let start = if i == 0 { 0.0 } else { ch.start + offset };
+        // Chapters past the rendered length would end before they start.
+        if start >= total {
+            break;
+        }
+        let end = chapters.get(i + 1).map(|n| n.start + offset).unwrap_or(total).min(total);
+        out.push_str("[CHAPTER]\nTIMEBASE=1/1000\n");
+        out.push_str(&format!("START={}\nEND={}\n", (start * 1000.0) as u64, (end * 1000.0) as u64));
+        out.push_str(&format!("title={}\n", escape_ffmetadata(&ch.title)));
+    }
+    out
+}
+
+fn format_timestamp(secs: f64) -> String {
+    let total = secs.max(0.0) as u64;
+    let (h, m, s) = (total / 3600, (total % 3600) / 60, total % 60);
+    if h > 0 {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{:02}:{:02}", m, s)
+    }
+}
+
+/// Append chapter timestamp lines to a YouTube description. The first
+/// chapter is pinned to 00:00 and chapters shorter than YouTube's minimum
+/// are merged into the previous one.
+pub fn description_with_chapters(description: Option<&str>, chapters: &[Chapter], offset: f64) -> Option<String> {
+    let mut lines: Vec<(f64, &str)> = Vec::new();
+    for (i, ch) in chapters.iter().enumerate() {
+        let start = if i == 0 { 0.0 } else { ch.start + offset };
+        if let Some(&(prev, _)) = lines.last() {
+            if start - prev < MIN_CHAPTER_SECONDS {
+                continue;
+            }
+        }
+        lines.push((start, &ch.title));
+    }
+    if lines.is_empty() {
+        return description.map(|d| d.to_string());
+    }
+    let block = lines
+        .iter()
+        .map(|(start, title)| format!("{} {}", format_timestamp(*start), title))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(match description {
+        Some(d) if !d.trim().is_empty() => format!("{}\n\n{}", d.trim_end(), block),
+        _ => block,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chapters_from_transcript_pauses() {
+        let srt = "1\n00:00:01,000 --> 00:00:04,000\nWelcome to the show everyone today\n\n\
+2\n00:00:30,000 --> 00:00:35,000\nStill the intro\n\n\
+3\n00:01:20,000 --> 00:01:25,000\nLet's talk about Rust async runtimes\n";
+        let chapters = transcript_chapters(srt);
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(chapters[0], Chapter { start: 0.0, title: "Welcome to the show everyone today".into() });
+        assert_eq!(chapters[1].start, 80.0);
+        assert_eq!(chapters[1].title, "Let's talk about Rust async runtimes");
+    }
+
+    #[test]
+    fn source_tags_from_ffprobe_json() {
+        let json = serde_json::json!({
+            "streams": [
+                { "index": 0, "codec_type": "audio", "disposition": { "attached_pic": 0 } },
+                { "index": 1, "codec_type": "video", "disposition": { "attached_pic": 1 } }
+            ],
+            "format": { "tags": {
+                "TITLE": "Episode 12: Async",
+                "artist": "The Show",
+                "comment": "Notes",
+                "track": "12/40",
+                "genre": "Technology; Podcast"
+            } }
+        });
+        let tags = parse_source_tags(&json);
+        assert_eq!(tags.title.as_deref(), Some("Episode 12: Async"));
+        assert_eq!(tags.artist.as_deref(), Some("The Show"));
+        assert_eq!(tags.description.as_deref(), Some("Notes"));
+        assert_eq!(tags.episode, Some(12));
+        assert_eq!(tags.tags, Some(vec!["Technology".to_string(), "Podcast".to_string()]));
+        assert_eq!(tags.cover_stream, Some(1));
+    }
+
+    #[test]
+    fn cover_art_goes_to_the_cover_dir() {
+        let dir = Path::new("cache/covers");
+        let a = cover_path("watch/ep1.mp3", dir);
+        assert_eq!(a.parent(), Some(dir));
+        assert_ne!(a, cover_path("watch/ep2.mp3", dir));
+    }
+
+    #[test]
+    fn description_lists_chapters() {
+        let chapters = vec![
+            Chapter { start: 0.0, title: "Intro".into() },
+            Chapter { start: 4.0, title: "Too short".into() },
+            Chapter { start: 3725.0, title: "Wrap up".into() },
+        ];
+        let desc = description_with_chapters(Some("Episode notes"), &chapters, 5.0).unwrap();
+        assert_eq!(desc, "Episode notes\n\n00:00 Intro\n1:02:10 Wrap up");
+    }
+
+    #[test]
+    fn ffmetadata_escapes_and_offsets() {
+        let params = GenerateParams { file: "a.mp3".into(), title: Some("A=B; #1".into()), ..Default::default() };
+        let chapters = vec![
+            Chapter { start: 0.0, title: "Start".into() },
+            Chapter { start: 10.0, title: "Next".into() },
+        ];
+        let meta = ffmetadata(&params, &chapters, 5.0, 60.0);
+        assert!(meta.starts_with(";FFMETADATA1\ntitle=A\\=B\\; \\#1\n"));
+        assert!(meta.contains("START=0\nEND=15000\ntitle=Start"));
+        assert!(meta.contains("START=15000\nEND=60000\ntitle=Next"));
+
+        let short = ffmetadata(&params, &chapters, 5.0, 12.0);
+        assert!(short.contains("START=0\nEND=12000\ntitle=Start"));
+        assert!(!short.contains("title=Next"));
+    }
+}
@@ -0,0 +1,154 @@
+use std::path::Path;
+use std::process::Command;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Wry};
+
+use crate::logger::log;
+
+/// One external command of a render.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct PlanStep {
+    pub program: String,
+    pub args: Vec<String>,
+    /// POSIX shell command line, ready to paste into a terminal.
+    pub line: String,
+}
+
+/// A text file the render writes, such as a concat list or ffmetadata.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct PlanFile {
+    pub path: String,
+    pub contents: String,
+}
+
+/// An intermediate file that becomes `to` by a plain rename or copy.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct PlanMove {
+    pub from: String,
+    pub to: String,
+}
+
+/// Everything a render does, in order.
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct RenderPlan {
+    pub steps: Vec<PlanStep>,
+    /// Intermediate files in the scratch workspace, in creation order.
+    #[serde(rename = "tempFiles")]
+    pub temp_files: Vec<String>,
+    pub files: Vec<PlanFile>,
+    /// Intermediate files moved into place, usually as a final output.
+    pub moves: Vec<PlanMove>,
+    pub outputs: Vec<String>,
+}
+
+/// Collects the plan while the render pipeline runs. In dry-run mode the
+/// pipeline only records its commands; otherwise each one is also logged
+/// before it runs.
+pub struct Recorder {
+    dry_run: bool,
+    app: Option<AppHandle<Wry>>,
+    plan: Mutex<RenderPlan>,
+}
+
+impl Recorder {
+    pub fn dry_run() -> Self {
+        Recorder { dry_run: true, app: None, plan: Mutex::new(RenderPlan::default()) }
+    }
+
+    /// Record and log the commands of a real render.
+    pub fn logging(app: AppHandle<Wry>) -> Self {
+        Recorder { dry_run: false, app: Some(app), plan: Mutex::new(RenderPlan::default()) }
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    pub fn command(&self, cmd: &Command) {
+        let step = PlanStep {
+            program: cmd.get_program().to_string_lossy().to_string(),
+            args: cmd.get_args().map(|a| a.to_string_lossy().to_string()).collect(),
+            line: command_line(cmd),
+        };
+        if let Some(ref app) = self.app {
+            log(app, "info", &format!("plan: {}", step.line));
+        }
+        self.plan.lock().unwrap().steps.push(step);
+    }
+
+    pub fn temp_file(&self, path: &Path) {
+        self.plan.lock().unwrap().temp_files.push(path.to_string_lossy().to_string());
+    }
+
+    pub fn file(&self, path: &Path, contents: &str) {
+        self.plan.lock().unwrap().files.push(PlanFile {
+            path: path.to_string_lossy().to_string(),
+            contents: contents.to_string(),
+        });
+    }
+
+    pub fn moved(&self, from: &Path, to: &Path) {
+        self.plan.lock().unwrap().moves.push(PlanMove {
+            from: from.to_string_lossy().to_string(),
+            to: to.to_string_lossy().to_string(),
+        });
+    }
+
+    pub fn finish(self, outputs: Vec<String>) -> RenderPlan {
+        let mut plan = self.plan.into_inner().unwrap();
+        plan.outputs = outputs;
+        plan
+    }
+}
+
+/// Quote an argument for a POSIX shell when it contains anything special.
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=,+@%".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Render a command as a single shell line.
+pub fn command_line(cmd: &Command) -> String {
+    std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|a| shell_quote(&a.to_string_lossy()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_only_when_needed() {
+        assert_eq!(shell_quote("-c:v"), "-c:v");
+        assert_eq!(shell_quote("/tmp/a b.mp4"), "'/tmp/a b.mp4'");
+        assert_eq!(shell_quote("[0:v]split=2[s0][s1]"), "'[0:v]split=2[s0][s1]'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn records_commands_in_order() {
+        let rec = Recorder::dry_run();
+        let mut probe = Command::new("ffprobe");
+        probe.args(["-v", "error", "in put.mp3"]);
+        rec.command(&probe);
+        rec.command(Command::new("ffmpeg").args(["-y", "-i", "a.mp4", "out.mp4"]));
+        let plan = rec.finish(vec!["out.mp4".into()]);
+        assert_eq!(plan.steps.len(), 2);
+        assert_eq!(plan.steps[0].line, "ffprobe -v error 'in put.mp3'");
+        assert_eq!(plan.steps[1].program, "ffmpeg");
+        assert_eq!(plan.outputs, vec!["out.mp4".to_string()]);
+    }
+}
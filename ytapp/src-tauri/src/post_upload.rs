@@ -0,0 +1,375 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use google_youtube3::hyper::{header, Method, Request};
+use google_youtube3::hyper_rustls::HttpsConnector;
+use google_youtube3::hyper_util::client::legacy::connect::HttpConnector;
+use google_youtube3::YouTube;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::auth;
+use crate::error::{api_error, AppError, AppResult, ErrorKind};
+use crate::logger::log;
+use crate::quota::{self, ApiMethod};
+use crate::schema::CaptionTrack;
+
+pub type Hub = YouTube<HttpsConnector<HttpConnector>>;
+
+/// Attempts per step before it is left failed.
+const MAX_ATTEMPTS: u32 = 3;
+/// Delay before the second attempt; doubled for each later one.
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Work done on a video after the insert succeeded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PostStep {
+    Thumbnail { file: String },
+    Playlist {
+        #[serde(rename = "playlistId")]
+        playlist_id: String,
+    },
+    Caption { track: CaptionTrack },
+    /// `status.containsSyntheticMedia`, set on the video's current status.
+    SyntheticMedia { value: bool },
+}
+
+impl PostStep {
+    pub fn method(&self) -> ApiMethod {
+        match self {
+            PostStep::Thumbnail { .. } => ApiMethod::ThumbnailsSet,
+            PostStep::Playlist { .. } => ApiMethod::PlaylistItemsInsert,
+            PostStep::Caption { .. } => ApiMethod::CaptionsInsert,
+            PostStep::SyntheticMedia { .. } => ApiMethod::VideosUpdate,
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            PostStep::Thumbnail { file } => format!("thumbnail {}", file),
+            PostStep::Playlist { playlist_id } => format!("playlist {}", playlist_id),
+            PostStep::Caption { track } => format!("caption track {} ({})", track.file, track.language),
+            PostStep::SyntheticMedia { .. } => "containsSyntheticMedia".into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Pending,
+    Done,
+    Failed,
+}
+
+/// A post-upload step and the outcome of its latest run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PostTask {
+    pub step: PostStep,
+    pub status: StepStatus,
+    /// Attempts made in the latest run.
+    pub attempts: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<AppError>,
+}
+
+impl PostTask {
+    pub fn new(step: PostStep) -> Self {
+        PostTask { step, status: StepStatus::Pending, attempts: 0, error: None }
+    }
+}
+
+/// Outcome of an upload: the video exists once this is returned, but some of
+/// its post-upload steps may have failed.
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadResult {
+    #[serde(rename = "videoId")]
+    pub video_id: String,
+    /// Account the video was uploaded with; the default account when unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account: Option<String>,
+    pub steps: Vec<PostTask>,
+    /// The video was uploaded but at least one step failed.
+    pub partial: bool,
+}
+
+impl UploadResult {
+    pub fn new(video_id: String, account: Option<String>, steps: Vec<PostTask>) -> Self {
+        let partial = steps.iter().any(|t| t.status == StepStatus::Failed);
+        UploadResult { video_id, account, steps, partial }
+    }
+}
+
+/// Run every pending or failed task, retrying retryable errors, and record
+/// the video with the `account` that uploaded it if anything is left failed.
+pub async fn run(app: &AppHandle<Wry>, hub: &Hub, video_id: &str, account: Option<String>, mut tasks: Vec<PostTask>) -> UploadResult {
+    for task in tasks.iter_mut().filter(|t| t.status != StepStatus::Done) {
+        task.attempts = 0;
+        loop {
+            task.attempts += 1;
+            match run_step(app, hub, video_id, &task.step).await {
+                Ok(()) => {
+                    task.status = StepStatus::Done;
+                    task.error = None;
+                    log(app, "info", &format!("{} done for {}", task.step.label(), video_id));
+                    break;
+                }
+                Err(e) => {
+                    log(app, "error", &format!("{} failed for {} (attempt {}): {}", task.step.label(), video_id, task.attempts, e));
+                    if e.kind == ErrorKind::Quota {
+                        quota::exhaust(app);
+                    }
+                    let retry = should_retry(task.attempts, &e);
+                    task.status = StepStatus::Failed;
+                    task.error = Some(e);
+                    if !retry {
+                        break;
+                    }
+                    tokio::time::sleep(RETRY_DELAY * 2u32.pow(task.attempts - 1)).await;
+                }
+            }
+        }
+    }
+    let result = UploadResult::new(video_id.to_string(), account, tasks);
+    if let Err(e) = save_record(app, &result) {
+        log(app, "error", &format!("failed to record post-upload steps for {}: {}", video_id, e));
+    }
+    result
+}
+
+fn should_retry(attempts: u32, e: &AppError) -> bool {
+    attempts < MAX_ATTEMPTS && e.is_retryable()
+}
+
+async fn run_step(app: &AppHandle<Wry>, hub: &Hub, video_id: &str, step: &PostStep) -> AppResult<()> {
+    match step {
+        PostStep::Thumbnail { file } => {
+            let mut f = File::open(file).map_err(|e| {
+                AppError::new(ErrorKind::NotFound, format!("thumbnail {} could not be opened", file)).caused_by(&e)
+            })?;
+            let mime = mime_guess::from_path(file).first_or_octet_stream();
+            quota::spend(app, step.method());
+            hub.thumbnails()
+                .set(video_id)
+                .add_scope(auth::YOUTUBE_SCOPE)
+                .upload(&mut f, mime)
+                .await
+                .map_err(api_error)?;
+        }
+        PostStep::Playlist { playlist_id } => {
+            let item = google_youtube3::api::PlaylistItem {
+                snippet: Some(google_youtube3::api::PlaylistItemSnippet {
+                    playlist_id: Some(playlist_id.clone()),
+                    resource_id: Some(google_youtube3::api::ResourceId {
+                        kind: Some("youtube#video".to_string()),
+                        video_id: Some(video_id.to_string()),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            quota::spend(app, step.method());
+            hub.playlist_items()
+                .insert(item)
+                .add_part("snippet")
+                .add_scope(auth::YOUTUBE_SCOPE)
+                .doit()
+                .await
+                .map_err(api_error)?;
+        }
+        PostStep::Caption { track } => {
+            let caption = google_youtube3::api::Caption {
+                snippet: Some(google_youtube3::api::CaptionSnippet {
+                    video_id: Some(video_id.to_string()),
+                    language: Some(track.language.clone()),
+                    name: Some(track.name.clone().unwrap_or_default()),
+                    is_draft: track.draft,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            let mut f = File::open(&track.file)?;
+            quota::spend(app, step.method());
+            hub.captions()
+                .insert(caption)
+                .add_part("snippet")
+                .add_scope(auth::YOUTUBE_SCOPE)
+                .upload(&mut f, "application/octet-stream".parse().unwrap())
+                .await
+                .map_err(api_error)?;
+        }
+        PostStep::SyntheticMedia { value } => set_synthetic_media(app, hub, video_id, *value).await?,
+    }
+    Ok(())
+}
+
+const VIDEOS_URI: &str = "https://youtube.googleapis.com/youtube/v3/videos";
+
+/// Send a raw request to the videos endpoint and return the response body.
+async fn videos_request(hub: &Hub, method: Method, query: &str, body: Option<String>) -> AppResult<String> {
+    use google_youtube3::common::GetToken;
+    let token = hub
+        .auth
+        .get_token(&[auth::YOUTUBE_SCOPE])
+        .await
+        .map_err(|e| AppError::new(ErrorKind::Auth, format!("failed to get access token: {}", e)))?
+        .ok_or_else(|| AppError::new(ErrorKind::NotSignedIn, "no access token"))?;
+    let name = if method == Method::GET { "videos.list" } else { "videos.update" };
+    let request = Request::builder()
+        .method(method)
+        .uri(format!("{}?{}", VIDEOS_URI, query))
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(google_youtube3::common::to_body(body))
+        .map_err(|e| e.to_string())?;
+    let mut response = hub.client.request(request).await.map_err(api_error)?;
+    let text = google_youtube3::common::get_body_as_string(response.body_mut()).await;
+    if !response.status().is_success() {
+        return Err(api_error(format!("{} returned {}: {}", name, response.status(), text)));
+    }
+    Ok(text)
+}
+
+/// Set `status.containsSyntheticMedia`, which the pinned API client does not
+/// model yet. `videos.update` replaces the whole status part, so the current
+/// status is read first and sent back with only the flag changed; privacy
+/// and schedule changes made since the upload are kept.
+async fn set_synthetic_media(app: &AppHandle<Wry>, hub: &Hub, video_id: &str, value: bool) -> AppResult<()> {
+    quota::spend(app, ApiMethod::VideosList);
+    let listed = videos_request(hub, Method::GET, &format!("part=status&id={}", video_id), None).await?;
+    let listed: serde_json::Value = serde_json::from_str(&listed)?;
+    let status = with_synthetic_media(&listed, value)
+        .ok_or_else(|| AppError::new(ErrorKind::NotFound, format!("video {} not found", video_id)))?;
+    let body = serde_json::json!({ "id": video_id, "status": status }).to_string();
+    quota::spend(app, ApiMethod::VideosUpdate);
+    videos_request(hub, Method::PUT, "part=status", Some(body)).await?;
+    Ok(())
+}
+
+/// The status part of a `videos.list` response with `containsSyntheticMedia`
+/// set, or None when the video was not listed.
+fn with_synthetic_media(listed: &serde_json::Value, value: bool) -> Option<serde_json::Value> {
+    let mut status = listed["items"].get(0)?.get("status")?.clone();
+    status.as_object_mut()?.insert("containsSyntheticMedia".into(), value.into());
+    Some(status)
+}
+
+/// Post-upload steps of a video and the account to run them as.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UploadRecord {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account: Option<String>,
+    pub steps: Vec<PostTask>,
+}
+
+type Records = BTreeMap<String, UploadRecord>;
+
+fn records_path(app: &AppHandle<Wry>) -> AppResult<PathBuf> {
+    let mut dir = match std::env::var("YTAPP_TEST_DIR") {
+        Ok(p) => PathBuf::from(p),
+        Err(_) => app.path().app_config_dir()?,
+    };
+    fs::create_dir_all(&dir)?;
+    dir.push("post_upload.json");
+    Ok(dir)
+}
+
+fn read_records(path: &Path) -> AppResult<Records> {
+    match fs::read_to_string(path) {
+        Ok(data) => Ok(serde_json::from_str(&data)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Records::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Keep a video's tasks while any of them failed and forget it otherwise.
+fn update_records(records: &mut Records, result: &UploadResult) {
+    if result.partial {
+        let record = UploadRecord { account: result.account.clone(), steps: result.steps.clone() };
+        records.insert(result.video_id.clone(), record);
+    } else {
+        records.remove(&result.video_id);
+    }
+}
+
+fn save_record(app: &AppHandle<Wry>, result: &UploadResult) -> AppResult<()> {
+    let path = records_path(app)?;
+    let mut records = read_records(&path)?;
+    update_records(&mut records, result);
+    fs::write(path, serde_json::to_string(&records)?)?;
+    Ok(())
+}
+
+/// Uploads whose post-upload steps have not all succeeded.
+pub fn incomplete(app: &AppHandle<Wry>) -> AppResult<Vec<UploadResult>> {
+    let records = read_records(&records_path(app)?)?;
+    Ok(records.into_iter().map(|(id, r)| UploadResult::new(id, r.account, r.steps)).collect())
+}
+
+/// Record of a video, or `NotFound` when none of its steps failed.
+pub fn record_for(app: &AppHandle<Wry>, video_id: &str) -> AppResult<UploadRecord> {
+    read_records(&records_path(app)?)?.remove(video_id).ok_or_else(|| {
+        AppError::new(ErrorKind::NotFound, format!("no failed post-upload steps for video {}", video_id))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(step: PostStep, status: StepStatus) -> PostTask {
+        PostTask { status, ..PostTask::new(step) }
+    }
+
+    #[test]
+    fn partial_results_are_recorded_until_complete() {
+        let thumb = PostStep::Thumbnail { file: "t.png".into() };
+        let playlist = PostStep::Playlist { playlist_id: "PL1".into() };
+        let mut records = Records::new();
+
+        let first = UploadResult::new("vid".into(), Some("music".into()), vec![task(thumb.clone(), StepStatus::Done), task(playlist.clone(), StepStatus::Failed)]);
+        assert!(first.partial);
+        update_records(&mut records, &first);
+        assert_eq!(records["vid"].steps.len(), 2);
+        assert_eq!(records["vid"].account.as_deref(), Some("music"));
+
+        let retried = UploadResult::new("vid".into(), Some("music".into()), vec![task(thumb, StepStatus::Done), task(playlist, StepStatus::Done)]);
+        assert!(!retried.partial);
+        update_records(&mut records, &retried);
+        assert!(records.is_empty());
+    }
+
+    #[test]
+    fn retries_only_retryable_errors() {
+        let network = AppError::new(ErrorKind::Network, "reset");
+        assert!(should_retry(1, &network));
+        assert!(!should_retry(MAX_ATTEMPTS, &network));
+        assert!(!should_retry(1, &AppError::new(ErrorKind::NotFound, "gone")));
+        assert!(!should_retry(1, &AppError::new(ErrorKind::Quota, "quota")));
+    }
+
+    #[test]
+    fn synthetic_media_keeps_current_status() {
+        let listed = serde_json::json!({
+            "items": [{ "id": "vid", "status": { "privacyStatus": "public", "license": "youtube" } }]
+        });
+        let status = with_synthetic_media(&listed, true).unwrap();
+        assert_eq!(status["privacyStatus"], "public");
+        assert_eq!(status["containsSyntheticMedia"], true);
+        assert!(with_synthetic_media(&serde_json::json!({ "items": [] }), true).is_none());
+    }
+
+    #[test]
+    fn steps_round_trip() {
+        let t = PostTask::new(PostStep::Playlist { playlist_id: "PL1".into() });
+        let json = serde_json::to_value(&t).unwrap();
+        assert_eq!(json["step"]["kind"], "playlist");
+        assert_eq!(json["step"]["playlistId"], "PL1");
+        assert_eq!(json["status"], "pending");
+        assert_eq!(serde_json::from_value::<PostTask>(json).unwrap(), t);
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::encoding;
+use crate::schema::EncodingOptions;
+
+/// x264 bits per pixel per frame at the default CRF. Deliberately on the
+/// high side so the preflight check errs towards refusing.
+const BITS_PER_PIXEL: f64 = 0.1;
+/// Container and muxing overhead on top of the stream bitrates.
+const CONTAINER_OVERHEAD: f64 = 1.05;
+/// Free space that must remain after the job has written everything.
+const SAFETY_MARGIN: u64 = 100 * 1024 * 1024;
+
+/// Expected disk usage of a render job.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SizeEstimate {
+    /// Peak size of the segments and tagged copies in the scratch workspace.
+    #[serde(rename = "intermediateBytes")]
+    pub intermediate_bytes: u64,
+    /// Combined size of all final outputs.
+    #[serde(rename = "outputBytes")]
+    pub output_bytes: u64,
+}
+
+/// Parse ffmpeg-style bitrates such as `192k`, `2.5M` or `800000`.
+pub fn parse_bitrate(s: &str) -> Option<u64> {
+    let s = s.trim();
+    let (num, mult) = match s.chars().last()? {
+        'k' | 'K' => (&s[..s.len() - 1], 1_000.0),
+        'm' | 'M' => (&s[..s.len() - 1], 1_000_000.0),
+        'g' | 'G' => (&s[..s.len() - 1], 1_000_000_000.0),
+        _ => (s, 1.0),
+    };
+    let value: f64 = num.parse().ok()?;
+    if value < 0.0 {
+        return None;
+    }
+    Some((value * mult) as u64)
+}
+
+/// Expected video bitrate in bits per second. An explicit bitrate wins;
+/// otherwise it is derived from the frame size, rate and CRF, halving for
+/// every six CRF steps like x264 does.
+pub fn video_bitrate(opts: &EncodingOptions, width: u32, height: u32, fps: u32, bitrate: Option<&str>) -> u64 {
+    if let Some(b) = bitrate.or(opts.video_bitrate.as_deref()).and_then(parse_bitrate) {
+        return b;
+    }
+    let crf = opts.crf.unwrap_or(encoding::DEFAULT_CRF) as f64;
+    let bpp = BITS_PER_PIXEL * 2f64.powf((encoding::DEFAULT_CRF as f64 - crf) / 6.0);
+    (width as f64 * height as f64 * fps.max(1) as f64 * bpp) as u64
+}
+
+/// Expected audio bitrate in bits per second.
+pub fn audio_bitrate(opts: &EncodingOptions) -> u64 {
+    opts.audio_bitrate
+        .as_deref()
+        .and_then(parse_bitrate)
+        .or_else(|| parse_bitrate(encoding::DEFAULT_AUDIO_BITRATE))
+        .unwrap_or(192_000)
+}
+
+/// Bytes written for `seconds` of a stream pair at the given bitrates.
+pub fn stream_bytes(video_bps: u64, audio_bps: u64, seconds: f64) -> u64 {
+    ((video_bps + audio_bps) as f64 / 8.0 * seconds.max(0.0) * CONTAINER_OVERHEAD) as u64
+}
+
+/// Human-readable size for log lines and error messages.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Closest ancestor of `path` that exists, so free space can be checked
+/// before output or scratch directories have been created.
+fn existing_ancestor(path: &Path) -> PathBuf {
+    let mut current = if path.as_os_str().is_empty() { Path::new(".") } else { path };
+    loop {
+        if current.exists() {
+            return current.to_path_buf();
+        }
+        match current.parent() {
+            Some(p) if !p.as_os_str().is_empty() => current = p,
+            _ => return PathBuf::from("."),
+        }
+    }
+}
+
+/// Identifier of the volume holding `path`, used to add up the needs of
+/// directories that share a disk.
+#[cfg(unix)]
+fn volume_id(path: &Path) -> String {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path)
+        .map(|m| m.dev().to_string())
+        .unwrap_or_else(|_| path.to_string_lossy().to_string())
+}
+
+#[cfg(not(unix))]
+fn volume_id(path: &Path) -> String {
+    let abs = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    abs.components()
+        .next()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Free bytes on the volume holding `path`.
+pub fn available_space(path: &Path) -> Option<u64> {
+    fs2::available_space(existing_ancestor(path)).ok()
+}
+
+/// Compare the bytes each location needs with the free space on its volume.
+/// Locations on the same volume are added up before the comparison.
+pub fn check_space(needs: &[(&Path, u64)]) -> Result<(), String> {
+    let mut volumes: HashMap<String, (PathBuf, u64)> = HashMap::new();
+    for (path, bytes) in needs {
+        let dir = existing_ancestor(path);
+        let entry = volumes.entry(volume_id(&dir)).or_insert((dir, 0));
+        entry.1 += bytes;
+    }
+    for (dir, need) in volumes.values() {
+        let Some(free) = available_space(dir) else { continue };
+        if need + SAFETY_MARGIN > free {
+            return Err(format!(
+                "not enough disk space on {}: job needs about {} but only {} is free",
+                dir.display(),
+                format_bytes(*need),
+                format_bytes(free)
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_bitrates() {
+        assert_eq!(parse_bitrate("192k"), Some(192_000));
+        assert_eq!(parse_bitrate("2.5M"), Some(2_500_000));
+        assert_eq!(parse_bitrate("800000"), Some(800_000));
+        assert_eq!(parse_bitrate("fast"), None);
+    }
+
+    #[test]
+    fn video_bitrate_follows_crf_and_overrides() {
+        let opts = EncodingOptions::default();
+        let base = video_bitrate(&opts, 1920, 1080, 30, None);
+        let lower = video_bitrate(&EncodingOptions { crf: Some(29), ..Default::default() }, 1920, 1080, 30, None);
+        assert_eq!(lower, base / 2);
+        assert_eq!(video_bitrate(&opts, 1920, 1080, 30, Some("4M")), 4_000_000);
+        let fixed = EncodingOptions { video_bitrate: Some("3M".into()), ..Default::default() };
+        assert_eq!(video_bitrate(&fixed, 1920, 1080, 30, None), 3_000_000);
+    }
+
+    #[test]
+    fn refuses_when_estimate_exceeds_free_space() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("not/yet/created.mp4");
+        assert!(check_space(&[(missing.as_path(), 1024)]).is_ok());
+        let err = check_space(&[(dir.path(), u64::MAX / 4)]).unwrap_err();
+        assert!(err.contains("not enough disk space"));
+    }
+}
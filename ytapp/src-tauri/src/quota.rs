@@ -0,0 +1,255 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use chrono_tz::America::Los_Angeles;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::error::{AppError, AppResult, ErrorKind};
+use crate::logger::log;
+
+/// Units a project gets per day unless Google granted more.
+pub const DEFAULT_DAILY_LIMIT: u64 = 10_000;
+
+/// Today's ledger, loaded from disk on first use.
+static LEDGER: Lazy<Mutex<Option<Ledger>>> = Lazy::new(|| Mutex::new(None));
+
+/// YouTube Data API methods the app calls, with their quota cost.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiMethod {
+    VideosInsert,
+    VideosUpdate,
+    VideosList,
+    ThumbnailsSet,
+    PlaylistItemsInsert,
+    CaptionsInsert,
+    PlaylistsList,
+    ChannelsList,
+}
+
+impl ApiMethod {
+    pub fn name(self) -> &'static str {
+        match self {
+            ApiMethod::VideosInsert => "videos.insert",
+            ApiMethod::VideosUpdate => "videos.update",
+            ApiMethod::VideosList => "videos.list",
+            ApiMethod::ThumbnailsSet => "thumbnails.set",
+            ApiMethod::PlaylistItemsInsert => "playlistItems.insert",
+            ApiMethod::CaptionsInsert => "captions.insert",
+            ApiMethod::PlaylistsList => "playlists.list",
+            ApiMethod::ChannelsList => "channels.list",
+        }
+    }
+
+    pub fn cost(self) -> u64 {
+        match self {
+            ApiMethod::VideosInsert => 1600,
+            ApiMethod::CaptionsInsert => 400,
+            ApiMethod::VideosUpdate | ApiMethod::ThumbnailsSet | ApiMethod::PlaylistItemsInsert => 50,
+            ApiMethod::VideosList | ApiMethod::PlaylistsList | ApiMethod::ChannelsList => 1,
+        }
+    }
+}
+
+/// Units spent per method on one Pacific-time day, when the quota resets.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct Ledger {
+    day: Option<NaiveDate>,
+    spent: BTreeMap<String, u64>,
+    /// The API reported the quota as exceeded, whatever the ledger says.
+    #[serde(default)]
+    exhausted: bool,
+    /// YouTube refused an upload for the channel's upload limit.
+    #[serde(default, rename = "uploadLimited")]
+    upload_limited: bool,
+}
+
+impl Ledger {
+    fn roll(&mut self, today: NaiveDate) {
+        if self.day != Some(today) {
+            *self = Ledger { day: Some(today), ..Ledger::default() };
+        }
+    }
+
+    fn used(&self) -> u64 {
+        self.spent.values().sum()
+    }
+
+    fn remaining(&self, limit: u64) -> u64 {
+        if self.exhausted {
+            0
+        } else {
+            limit.saturating_sub(self.used())
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct QuotaStatus {
+    pub day: NaiveDate,
+    pub limit: u64,
+    pub used: u64,
+    pub remaining: u64,
+    #[serde(rename = "resetsAt")]
+    pub resets_at: DateTime<Utc>,
+    pub exhausted: bool,
+    /// Uploads wait for the reset because the channel hit its upload limit.
+    #[serde(rename = "uploadLimited")]
+    pub upload_limited: bool,
+    /// Units spent today per API method.
+    pub methods: BTreeMap<String, u64>,
+}
+
+/// The quota day, which starts at midnight Pacific time.
+pub fn pacific_day(now: DateTime<Utc>) -> NaiveDate {
+    now.with_timezone(&Los_Angeles).date_naive()
+}
+
+/// When the quota of the day containing `now` resets.
+pub fn next_reset(now: DateTime<Utc>) -> DateTime<Utc> {
+    let tomorrow = pacific_day(now).succ_opt().unwrap_or(NaiveDate::MAX);
+    Los_Angeles
+        .from_local_datetime(&tomorrow.and_hms_opt(0, 0, 0).unwrap_or_default())
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or(now)
+}
+
+/// Time left until the next reset.
+pub fn until_reset() -> std::time::Duration {
+    let now = Utc::now();
+    (next_reset(now) - now).to_std().unwrap_or_default()
+}
+
+pub fn cost(methods: &[ApiMethod]) -> u64 {
+    methods.iter().map(|m| m.cost()).sum()
+}
+
+fn ledger_path(app: &AppHandle<Wry>) -> AppResult<PathBuf> {
+    let mut dir = match std::env::var("YTAPP_TEST_DIR") {
+        Ok(p) => PathBuf::from(p),
+        Err(_) => app.path().app_config_dir()?,
+    };
+    fs::create_dir_all(&dir)?;
+    dir.push("quota.json");
+    Ok(dir)
+}
+
+/// Run `f` on today's ledger and save it when `f` changed it.
+fn with_ledger<T>(app: &AppHandle<Wry>, f: impl FnOnce(&mut Ledger) -> T) -> AppResult<T> {
+    let path = ledger_path(app)?;
+    let mut guard = LEDGER.lock().unwrap();
+    if guard.is_none() {
+        *guard = Some(match fs::read_to_string(&path) {
+            Ok(data) => serde_json::from_str(&data).unwrap_or_default(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ledger::default(),
+            Err(e) => return Err(e.into()),
+        });
+    }
+    let ledger = guard.as_mut().unwrap();
+    let before = ledger.clone();
+    ledger.roll(pacific_day(Utc::now()));
+    let out = f(ledger);
+    if *ledger != before {
+        fs::write(path, serde_json::to_string(ledger)?)?;
+    }
+    Ok(out)
+}
+
+/// Record a call. Google charges failed calls too, so this is done before
+/// the result is known.
+pub fn spend(app: &AppHandle<Wry>, method: ApiMethod) {
+    let res = with_ledger(app, |l| *l.spent.entry(method.name().to_string()).or_default() += method.cost());
+    if let Err(e) = res {
+        log(app, "error", &format!("failed to update quota ledger: {}", e));
+    }
+}
+
+/// Note that the API refused a call for quota until the next reset.
+pub fn exhaust(app: &AppHandle<Wry>) {
+    if let Err(e) = with_ledger(app, |l| l.exhausted = true) {
+        log(app, "error", &format!("failed to update quota ledger: {}", e));
+    }
+}
+
+/// Note that YouTube refused an upload for the channel's upload limit. Other
+/// API calls go on; uploads wait until the next reset.
+pub fn limit_uploads(app: &AppHandle<Wry>) {
+    if let Err(e) = with_ledger(app, |l| l.upload_limited = true) {
+        log(app, "error", &format!("failed to update quota ledger: {}", e));
+    }
+}
+
+pub fn status(app: &AppHandle<Wry>, limit: u64) -> AppResult<QuotaStatus> {
+    let now = Utc::now();
+    with_ledger(app, |l| QuotaStatus {
+        day: pacific_day(now),
+        limit,
+        used: l.used(),
+        remaining: l.remaining(limit),
+        resets_at: next_reset(now),
+        exhausted: l.exhausted,
+        upload_limited: l.upload_limited,
+        methods: l.spent.clone(),
+    })
+}
+
+/// Whether an upload costing `units` can start today: it fits into the
+/// quota and the channel is below its upload limit.
+pub fn upload_fits(app: &AppHandle<Wry>, limit: u64, units: u64) -> bool {
+    with_ledger(app, |l| !l.upload_limited && l.remaining(limit) >= units).unwrap_or(true)
+}
+
+/// Fail with a `Quota` error when an upload costing `units` would exceed
+/// today's quota, or an `UploadLimit` error while uploads are held.
+pub fn check_upload(app: &AppHandle<Wry>, limit: u64, units: u64) -> AppResult<()> {
+    let (limited, fits) = with_ledger(app, |l| (l.upload_limited, l.remaining(limit) >= units)).unwrap_or((false, true));
+    let resets = next_reset(Utc::now()).with_timezone(&chrono::Local);
+    let hint = format!("Uploads start again at {}.", resets.format("%Y-%m-%d %H:%M"));
+    if limited {
+        Err(AppError::new(ErrorKind::UploadLimit, "the channel reached its YouTube upload limit for today").with_hint(hint))
+    } else if !fits {
+        Err(AppError::new(ErrorKind::Quota, format!("not enough YouTube API quota left today for {} units", units))
+            .with_hint(hint))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quota_day_follows_pacific_time() {
+        // 07:30 UTC is still the previous evening in California.
+        let now = Utc.with_ymd_and_hms(2024, 3, 5, 7, 30, 0).unwrap();
+        assert_eq!(pacific_day(now), NaiveDate::from_ymd_opt(2024, 3, 4).unwrap());
+        assert_eq!(next_reset(now), Utc.with_ymd_and_hms(2024, 3, 5, 8, 0, 0).unwrap());
+        // Daylight saving time moves midnight to 07:00 UTC.
+        let summer = Utc.with_ymd_and_hms(2024, 7, 1, 12, 0, 0).unwrap();
+        assert_eq!(next_reset(summer), Utc.with_ymd_and_hms(2024, 7, 2, 7, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn ledger_rolls_over_each_day() {
+        let mut l = Ledger::default();
+        let day = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        l.roll(day);
+        l.spent.insert("videos.insert".into(), 3 * ApiMethod::VideosInsert.cost());
+        l.spent.insert("thumbnails.set".into(), 100);
+        assert_eq!(l.remaining(DEFAULT_DAILY_LIMIT), 5_100);
+        l.roll(day);
+        assert_eq!(l.used(), 4_900);
+        l.exhausted = true;
+        assert_eq!(l.remaining(DEFAULT_DAILY_LIMIT), 0);
+        l.upload_limited = true;
+        l.roll(day.succ_opt().unwrap());
+        assert_eq!(l.used(), 0);
+        assert!(!l.exhausted && !l.upload_limited);
+    }
+}
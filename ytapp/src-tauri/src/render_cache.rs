@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
+
+/// Default size cap when `cacheMaxBytes` is not configured.
+pub const DEFAULT_MAX_BYTES: u64 = 10 * 1024 * 1024 * 1024;
+
+/// Content digests of input files, keyed by path, size and modification time
+/// so unchanged inputs are hashed once per session.
+static DIGESTS: Lazy<Mutex<HashMap<(PathBuf, u64, SystemTime), String>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// SHA-256 of a file's contents.
+fn file_digest(path: &Path) -> Result<String, String> {
+    let meta = fs::metadata(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let id = (path.to_path_buf(), meta.len(), meta.modified().map_err(|e| e.to_string())?);
+    if let Some(d) = DIGESTS.lock().unwrap().get(&id) {
+        return Ok(d.clone());
+    }
+    let mut file = fs::File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1 << 16];
+    loop {
+        let n = file.read(&mut buf).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    let digest = hex::encode(hasher.finalize());
+    DIGESTS.lock().unwrap().insert(id, digest.clone());
+    Ok(digest)
+}
+
+/// Builds a cache key from render settings and the contents of input files.
+/// Paths never enter the key, so a renamed or copied input still hits.
+#[derive(Clone)]
+pub struct CacheKey {
+    kind: String,
+    hasher: Sha256,
+}
+
+impl CacheKey {
+    pub fn new(kind: &str) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(kind.as_bytes());
+        CacheKey { kind: kind.to_string(), hasher }
+    }
+
+    /// Mix in any serializable value, e.g. params or encoding options.
+    pub fn value<T: Serialize + ?Sized>(mut self, value: &T) -> Result<Self, String> {
+        let json = serde_json::to_vec(value).map_err(|e| e.to_string())?;
+        self.hasher.update((json.len() as u64).to_le_bytes());
+        self.hasher.update(json);
+        Ok(self)
+    }
+
+    /// Mix in the contents of an input file.
+    pub fn file(mut self, path: &str) -> Result<Self, String> {
+        let digest = file_digest(Path::new(path))?;
+        self.hasher.update(digest.as_bytes());
+        Ok(self)
+    }
+
+    /// Entry name such as `segment-<sha256>`.
+    pub fn finish(self) -> String {
+        format!("{}-{}", self.kind, hex::encode(self.hasher.finalize()))
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CacheEntry {
+    pub name: String,
+    pub kind: String,
+    pub bytes: u64,
+    /// Last use as an RFC 3339 timestamp.
+    #[serde(rename = "lastUsed")]
+    pub last_used: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CacheInfo {
+    pub dir: String,
+    #[serde(rename = "totalBytes")]
+    pub total_bytes: u64,
+    #[serde(rename = "maxBytes")]
+    pub max_bytes: u64,
+    pub entries: Vec<CacheEntry>,
+}
+
+/// Content-addressed store for rendered segments and finished outputs.
+///
+/// Entries are plain files named after their key. A hit refreshes the
+/// file's modification time, which doubles as the LRU clock for eviction.
+pub struct RenderCache {
+    dir: PathBuf,
+    max_bytes: u64,
+}
+
+impl RenderCache {
+    pub fn new(dir: PathBuf, max_bytes: u64) -> Self {
+        RenderCache { dir, max_bytes }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Free space storing `bytes` of renders needs: the cap bounds what the
+    /// cache keeps, so nothing beyond it is ever copied in at once.
+    pub fn store_bytes(&self, bytes: u64) -> u64 {
+        bytes.min(self.max_bytes)
+    }
+
+    fn entry_path(&self, key: &str, ext: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", key, ext))
+    }
+
+    /// Copy a cached entry to `dest`. Returns false on a miss.
+    pub fn restore(&self, key: &str, ext: &str, dest: &Path) -> Result<bool, String> {
+        let path = self.entry_path(key, ext);
+        if !path.exists() {
+            return Ok(false);
+        }
+        touch(&path);
+        fs::copy(&path, dest).map_err(|e| format!("failed to restore cached render: {}", e))?;
+        Ok(true)
+    }
+
+    /// Copy `file` into the cache and evict old entries beyond the size cap.
+    /// Files larger than the whole cap are not stored.
+    pub fn store(&self, key: &str, ext: &str, file: &Path) -> Result<(), String> {
+        let size = fs::metadata(file).map_err(|e| e.to_string())?.len();
+        if size > self.max_bytes {
+            return Ok(());
+        }
+        fs::create_dir_all(&self.dir).map_err(|e| e.to_string())?;
+        let path = self.entry_path(key, ext);
+        // Copy under a temporary name so a crash never leaves a truncated entry.
+        let partial = self.dir.join(format!(".{}.{}.partial", key, ext));
+        fs::copy(file, &partial).map_err(|e| e.to_string())?;
+        fs::rename(&partial, &path).map_err(|e| e.to_string())?;
+        self.evict(&path);
+        Ok(())
+    }
+
+    /// Remove least recently used entries until the cache fits its cap.
+    /// `keep` is the entry that was just stored.
+    fn evict(&self, keep: &Path) {
+        let mut entries = self.scan();
+        let mut total: u64 = entries.iter().map(|(_, size, _)| size).sum();
+        entries.sort_by_key(|(_, _, used)| *used);
+        for (path, size, _) in entries {
+            if total <= self.max_bytes {
+                break;
+            }
+            if path != keep && fs::remove_file(&path).is_ok() {
+                total -= size;
+            }
+        }
+    }
+
+    fn scan(&self) -> Vec<(PathBuf, u64, SystemTime)> {
+        let mut out = Vec::new();
+        if let Ok(read) = fs::read_dir(&self.dir) {
+            for entry in read.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if name.starts_with('.') {
+                    continue;
+                }
+                if let Ok(meta) = entry.metadata() {
+                    if meta.is_file() {
+                        out.push((entry.path(), meta.len(), meta.modified().unwrap_or(SystemTime::UNIX_EPOCH)));
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// Entries ordered from most to least recently used.
+    pub fn info(&self) -> CacheInfo {
+        let mut scanned = self.scan();
+        scanned.sort_by(|a, b| b.2.cmp(&a.2));
+        let entries: Vec<CacheEntry> = scanned
+            .into_iter()
+            .map(|(path, bytes, used)| {
+                let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+                let kind = name.split('-').next().unwrap_or_default().to_string();
+                let last_used: chrono::DateTime<chrono::Utc> = used.into();
+                CacheEntry { name, kind, bytes, last_used: last_used.to_rfc3339() }
+            })
+            .collect();
+        CacheInfo {
+            dir: self.dir.to_string_lossy().to_string(),
+            total_bytes: entries.iter().map(|e| e.bytes).sum(),
+            max_bytes: self.max_bytes,
+            entries,
+        }
+    }
+
+    /// Delete every entry. Returns the number of bytes freed.
+    pub fn clear(&self) -> Result<u64, String> {
+        let mut freed = 0;
+        if let Ok(read) = fs::read_dir(&self.dir) {
+            for entry in read.flatten() {
+                let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                if entry.path().is_file() {
+                    fs::remove_file(entry.path()).map_err(|e| e.to_string())?;
+                    freed += size;
+                }
+            }
+        }
+        Ok(freed)
+    }
+}
+
+/// Mark an entry as used now.
+fn touch(path: &Path) {
+    if let Ok(file) = fs::File::options().write(true).open(path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn key_depends_on_contents_not_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.png");
+        let b = dir.path().join("b.png");
+        fs::write(&a, b"same").unwrap();
+        fs::write(&b, b"same").unwrap();
+        let ka = CacheKey::new("segment").value(&(1280, 720)).unwrap().file(a.to_str().unwrap()).unwrap().finish();
+        let kb = CacheKey::new("segment").value(&(1280, 720)).unwrap().file(b.to_str().unwrap()).unwrap().finish();
+        let kc = CacheKey::new("segment").value(&(1920, 1080)).unwrap().file(a.to_str().unwrap()).unwrap().finish();
+        assert_eq!(ka, kb);
+        assert_ne!(ka, kc);
+        assert!(ka.starts_with("segment-"));
+    }
+
+    #[test]
+    fn store_restore_and_clear() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = RenderCache::new(dir.path().join("cache"), 1024);
+        let src = dir.path().join("out.mp4");
+        fs::write(&src, b"video").unwrap();
+        let dest = dir.path().join("restored.mp4");
+        assert!(!cache.restore("output-1", "mp4", &dest).unwrap());
+        cache.store("output-1", "mp4", &src).unwrap();
+        assert!(cache.restore("output-1", "mp4", &dest).unwrap());
+        assert_eq!(fs::read(&dest).unwrap(), b"video");
+        assert_eq!(cache.info().entries.len(), 1);
+        assert_eq!(cache.clear().unwrap(), 5);
+        assert!(cache.info().entries.is_empty());
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = RenderCache::new(dir.path().join("cache"), 10);
+        let src = dir.path().join("seg.mp4");
+        fs::write(&src, b"12345").unwrap();
+        cache.store("segment-old", "mp4", &src).unwrap();
+        cache.store("segment-used", "mp4", &src).unwrap();
+        let old = cache.entry_path("segment-old", "mp4");
+        let used = cache.entry_path("segment-used", "mp4");
+        let past = SystemTime::now() - Duration::from_secs(60);
+        fs::File::options().write(true).open(&old).unwrap().set_modified(past).unwrap();
+        fs::File::options().write(true).open(&used).unwrap().set_modified(past + Duration::from_secs(1)).unwrap();
+        cache.store("segment-new", "mp4", &src).unwrap();
+        assert!(!old.exists());
+        assert!(used.exists());
+        assert!(cache.entry_path("segment-new", "mp4").exists());
+    }
+}
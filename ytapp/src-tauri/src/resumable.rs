@@ -0,0 +1,151 @@
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use chrono::{DateTime, Duration, Utc};
+use google_youtube3::common::{ContentRange, Delegate, Response, Retry};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Wry};
+
+use crate::error::AppResult;
+use crate::job_queue;
+use crate::logger::log;
+
+/// YouTube keeps an unfinished resumable session for about a week.
+const SESSION_LIFETIME_DAYS: i64 = 6;
+
+/// A resumable upload session started for a file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UploadSession {
+    pub uri: String,
+    pub file: String,
+    pub size: u64,
+    /// Modification time of `file` in seconds, to notice a re-render.
+    pub modified: u64,
+    /// Bytes the server confirmed before the last chunk was sent.
+    pub offset: u64,
+    pub created: DateTime<Utc>,
+}
+
+impl UploadSession {
+    pub fn new(uri: &str, file: &str) -> AppResult<Self> {
+        let (size, modified) = file_stamp(file)?;
+        Ok(UploadSession { uri: uri.to_string(), file: file.to_string(), size, modified, offset: 0, created: Utc::now() })
+    }
+
+    /// Whether the session can still continue the upload of its file.
+    pub fn is_usable(&self, now: DateTime<Utc>) -> bool {
+        now - self.created < Duration::days(SESSION_LIFETIME_DAYS)
+            && file_stamp(&self.file).map(|s| s == (self.size, self.modified)).unwrap_or(false)
+    }
+}
+
+fn file_stamp(file: &str) -> AppResult<(u64, u64)> {
+    let meta = std::fs::metadata(Path::new(file))?;
+    let modified = meta.modified()?.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    Ok((meta.len(), modified))
+}
+
+/// Whether the HTTP status of a failed resume means the server no longer
+/// knows the session.
+fn is_expired(status: Option<u16>) -> bool {
+    matches!(status, Some(404) | Some(410))
+}
+
+/// Hands a stored session to `upload_resumable`, which then asks the server
+/// for the committed range and continues from there, and persists the
+/// session and confirmed offset on the queue item as the upload advances.
+pub struct SessionDelegate {
+    app: AppHandle<Wry>,
+    file: String,
+    /// Id of the queue job the session is stored on; direct uploads keep it
+    /// in memory.
+    job: Option<u64>,
+    session: Option<UploadSession>,
+    /// HTTP status of the last failed request of the current attempt.
+    failed_status: Option<u16>,
+}
+
+impl SessionDelegate {
+    pub fn new(app: AppHandle<Wry>, file: &str, job: Option<u64>, session: Option<UploadSession>) -> Self {
+        SessionDelegate { app, file: file.to_string(), job, session, failed_status: None }
+    }
+
+    pub fn is_resuming(&self) -> bool {
+        self.session.is_some()
+    }
+
+    /// Whether the last attempt failed because the stored session is gone.
+    pub fn session_expired(&self) -> bool {
+        is_expired(self.failed_status)
+    }
+
+    /// Forget the session once the upload finished or the server dropped it.
+    pub fn clear(&mut self) {
+        if self.session.take().is_some() {
+            self.persist();
+        }
+    }
+
+    fn persist(&self) {
+        if let Some(id) = self.job {
+            if let Err(e) = job_queue::set_upload_session(&self.app, id, self.session.clone()) {
+                log(&self.app, "error", &format!("failed to save upload session: {}", e));
+            }
+        }
+    }
+}
+
+impl Delegate for SessionDelegate {
+    fn upload_url(&mut self) -> Option<String> {
+        self.failed_status = None;
+        self.session.as_ref().map(|s| s.uri.clone())
+    }
+
+    fn http_failure(&mut self, response: &Response, _err: Option<&serde_json::Value>) -> Retry {
+        self.failed_status = Some(response.status().as_u16());
+        Retry::Abort
+    }
+
+    fn store_upload_url(&mut self, url: Option<&str>) {
+        self.session = url.and_then(|u| UploadSession::new(u, &self.file).ok());
+        self.persist();
+    }
+
+    fn cancel_chunk_upload(&mut self, chunk: &ContentRange) -> bool {
+        if let (Some(session), Some(range)) = (self.session.as_mut(), chunk.range.as_ref()) {
+            if range.first != session.offset {
+                session.offset = range.first;
+                self.persist();
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sessions_follow_their_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("out.mp4");
+        std::fs::write(&file, b"video").unwrap();
+        let session = UploadSession::new("https://upload.example/s", file.to_str().unwrap()).unwrap();
+        assert!(session.is_usable(Utc::now()));
+        assert!(!session.is_usable(Utc::now() + Duration::days(7)));
+        std::fs::write(&file, b"re-rendered video").unwrap();
+        assert!(!session.is_usable(Utc::now()));
+        std::fs::remove_file(&file).unwrap();
+        assert!(!session.is_usable(Utc::now()));
+    }
+
+    #[test]
+    fn expired_sessions_are_recognized() {
+        assert!(is_expired(Some(404)));
+        assert!(is_expired(Some(410)));
+        assert!(!is_expired(Some(503)));
+        // Connection failures never reached the server.
+        assert!(!is_expired(None));
+    }
+}
@@ -12,6 +12,107 @@ pub struct CaptionOptions {
     pub background: Option<String>,
 }
 
+/// A chapter marker, written into the MP4 and the YouTube description.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Chapter {
+    /// Start time in seconds from the beginning of the main section.
+    pub start: f64,
+    pub title: String,
+}
+
+/// Text drawn over the video, e.g. a channel handle or episode number.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct TextWatermark {
+    pub text: String,
+    pub font: Option<String>,
+    #[serde(rename = "fontPath")]
+    pub font_path: Option<String>,
+    pub size: Option<u32>,
+    pub color: Option<String>,
+    /// Background box colour; no box is drawn when unset.
+    #[serde(rename = "boxColor")]
+    pub box_color: Option<String>,
+    #[serde(rename = "shadowColor")]
+    pub shadow_color: Option<String>,
+    #[serde(rename = "shadowOffset")]
+    pub shadow_offset: Option<u32>,
+}
+
+/// YouTube video settings beyond title, description and tags. Flattened
+/// into the structs that carry upload settings.
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoMetadata {
+    /// Numeric YouTube category, e.g. `"22"` for People & Blogs.
+    pub category_id: Option<String>,
+    /// BCP-47 language of the title and description.
+    pub default_language: Option<String>,
+    /// BCP-47 language spoken in the video.
+    pub default_audio_language: Option<String>,
+    /// Sent as `selfDeclaredMadeForKids`; YouTube derives `madeForKids` from it.
+    pub made_for_kids: Option<bool>,
+    /// `youtube` or `creativeCommon`.
+    pub license: Option<String>,
+    pub embeddable: Option<bool>,
+    pub public_stats_viewable: Option<bool>,
+    /// Whether subscribers are notified of the upload. YouTube defaults to true.
+    pub notify_subscribers: Option<bool>,
+    /// RFC 3339 timestamp or `YYYY-MM-DD` date the video was recorded.
+    pub recording_date: Option<String>,
+    /// Discloses realistic altered or synthetic content.
+    pub contains_synthetic_media: Option<bool>,
+}
+
+/// A subtitle file uploaded as a YouTube caption track instead of being
+/// burned into the video.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CaptionTrack {
+    /// SRT or WebVTT file.
+    pub file: String,
+    /// BCP-47 language code such as `en` or `pt-BR`.
+    pub language: String,
+    /// Track name shown in the player. Defaults to no name.
+    pub name: Option<String>,
+    /// Upload as a draft that is not visible to viewers.
+    pub draft: Option<bool>,
+}
+
+/// x264/AAC encoder settings shared by every segment of a render.
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct EncodingOptions {
+    /// x264 preset such as `veryfast` or `slow`.
+    pub preset: Option<String>,
+    /// Constant rate factor, used when no `videoBitrate` is given.
+    pub crf: Option<u32>,
+    /// Target video bitrate, e.g. `"8M"`. Takes precedence over `crf`.
+    #[serde(rename = "videoBitrate")]
+    pub video_bitrate: Option<String>,
+    /// Frames between keyframes. Defaults to two seconds of video.
+    #[serde(rename = "keyframeInterval")]
+    pub keyframe_interval: Option<u32>,
+    /// x264 tune such as `stillimage` or `film`.
+    pub tune: Option<String>,
+    #[serde(rename = "audioBitrate")]
+    pub audio_bitrate: Option<String>,
+    #[serde(rename = "sampleRate")]
+    pub sample_rate: Option<u32>,
+}
+
+/// An additional output rendered from the same audio as the main video.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Rendition {
+    /// Suffix used to derive the output file name when `output` is unset.
+    pub name: Option<String>,
+    pub output: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub fps: Option<u32>,
+    /// Target video bitrate passed to `-b:v`, e.g. `"8M"`.
+    pub bitrate: Option<String>,
+    #[serde(rename = "captionOptions")]
+    pub caption_options: Option<CaptionOptions>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Default)]
 pub struct Profile {
     pub captions: Option<String>,
@@ -27,6 +128,24 @@ pub struct Profile {
     pub watermark_opacity: Option<f32>,
     #[serde(rename = "watermarkScale")]
     pub watermark_scale: Option<f32>,
+    #[serde(rename = "watermarkText")]
+    pub watermark_text: Option<TextWatermark>,
+    /// Horizontal offset overriding `watermarkPosition`: pixels from the
+    /// left, negative pixels from the right, or a percentage such as `50%`.
+    #[serde(rename = "watermarkX")]
+    pub watermark_x: Option<String>,
+    /// Vertical offset, same format as `watermarkX`.
+    #[serde(rename = "watermarkY")]
+    pub watermark_y: Option<String>,
+    /// Distance from the edges for corner positions. Defaults to 10px.
+    #[serde(rename = "watermarkMargin")]
+    pub watermark_margin: Option<u32>,
+    /// Seconds from the start of the main section when the watermark appears.
+    #[serde(rename = "watermarkStart")]
+    pub watermark_start: Option<f64>,
+    /// Seconds from the start of the main section when the watermark disappears.
+    #[serde(rename = "watermarkEnd")]
+    pub watermark_end: Option<f64>,
     pub width: Option<u32>,
     pub height: Option<u32>,
     pub fps: Option<u32>,
@@ -39,9 +158,19 @@ pub struct Profile {
     pub privacy: Option<String>,
     #[serde(rename = "playlistId")]
     pub playlist_id: Option<String>,
+    #[serde(flatten)]
+    pub video: VideoMetadata,
+    /// Account to upload with. Defaults to the default account.
+    pub account: Option<String>,
+    pub renditions: Option<Vec<Rendition>>,
+    pub encoding: Option<EncodingOptions>,
+    pub artist: Option<String>,
+    /// Where chapters come from: `manual`, `audio` (ID3 CHAP frames) or `transcript`.
+    #[serde(rename = "chapterSource")]
+    pub chapter_source: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct GenerateParams {
     pub file: String,
     pub output: Option<String>,
@@ -58,6 +187,24 @@ pub struct GenerateParams {
     pub watermark_opacity: Option<f32>,
     #[serde(rename = "watermarkScale")]
     pub watermark_scale: Option<f32>,
+    #[serde(rename = "watermarkText")]
+    pub watermark_text: Option<TextWatermark>,
+    /// Horizontal offset overriding `watermarkPosition`: pixels from the
+    /// left, negative pixels from the right, or a percentage such as `50%`.
+    #[serde(rename = "watermarkX")]
+    pub watermark_x: Option<String>,
+    /// Vertical offset, same format as `watermarkX`.
+    #[serde(rename = "watermarkY")]
+    pub watermark_y: Option<String>,
+    /// Distance from the edges for corner positions. Defaults to 10px.
+    #[serde(rename = "watermarkMargin")]
+    pub watermark_margin: Option<u32>,
+    /// Seconds from the start of the main section when the watermark appears.
+    #[serde(rename = "watermarkStart")]
+    pub watermark_start: Option<f64>,
+    /// Seconds from the start of the main section when the watermark disappears.
+    #[serde(rename = "watermarkEnd")]
+    pub watermark_end: Option<f64>,
     pub width: Option<u32>,
     pub height: Option<u32>,
     pub fps: Option<u32>,
@@ -70,4 +217,21 @@ pub struct GenerateParams {
     pub privacy: Option<String>,
     #[serde(rename = "playlistId")]
     pub playlist_id: Option<String>,
-}
\ No newline at end of file
+    /// Caption tracks uploaded with the video.
+    #[serde(rename = "captionTracks")]
+    pub caption_tracks: Option<Vec<CaptionTrack>>,
+    #[serde(flatten)]
+    pub video: VideoMetadata,
+    /// Account to upload with. Defaults to the default account.
+    pub account: Option<String>,
+    pub renditions: Option<Vec<Rendition>>,
+    pub encoding: Option<EncodingOptions>,
+    pub artist: Option<String>,
+    pub season: Option<u32>,
+    pub episode: Option<u32>,
+    pub chapters: Option<Vec<Chapter>>,
+    /// Where chapters come from: `manual`, `audio` (ID3 CHAP frames) or `transcript`.
+    /// Defaults to `chapters` when given, otherwise to the source audio.
+    #[serde(rename = "chapterSource")]
+    pub chapter_source: Option<String>,
+}
@@ -1,65 +1,278 @@
-use anyhow::{Context, Result};
+use anyhow::Result;
+use argon2::{Algorithm, Argon2, Params, Version};
 use chacha20poly1305::{
-    aead::{Aead, KeyInit, OsRng},
+    aead::{Aead, KeyInit, OsRng, Payload},
     Key, XChaCha20Poly1305, XNonce,
 };
+use once_cell::sync::Lazy;
 use rand_core::RngCore;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 use tokio::sync::Mutex;
 use yup_oauth2::storage::{TokenInfo, TokenStorage};
 
+use crate::accounts::DEFAULT_ACCOUNT;
+use crate::error::{AppError, AppResult, ErrorKind};
+
+fn unreadable() -> AppError {
+    AppError::new(ErrorKind::TokenStore, "token store could not be decrypted")
+        .with_hint("Check the passphrase or YOUTUBE_TOKEN_KEY, or sign out and sign in again.")
+}
+
+fn corrupt(path: &Path) -> AppError {
+    AppError::new(ErrorKind::TokenStore, format!("token store {} is corrupt", path.display()))
+        .with_hint("Move the file aside and sign in again.")
+}
+
+/// Serializes read-modify-write cycles on the token file.
+static WRITE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// Key unlocked with a passphrase for the rest of the session.
+static SESSION_KEY: Lazy<std::sync::Mutex<Option<TokenKey>>> = Lazy::new(|| std::sync::Mutex::new(None));
+
+pub fn session_key() -> Option<TokenKey> {
+    SESSION_KEY.lock().unwrap().clone()
+}
+
+pub fn set_session_key(key: TokenKey) {
+    *SESSION_KEY.lock().unwrap() = Some(key);
+}
+
+/// Secret the token file is encrypted with.
+#[derive(Clone)]
+pub enum TokenKey {
+    /// 32 raw bytes used as the cipher key directly.
+    Raw([u8; 32]),
+    /// A passphrase stretched with Argon2id. Files encrypted this way start
+    /// with the salt and cost parameters.
+    Passphrase(Arc<PassphraseKey>),
+}
+
+/// A passphrase and the key last derived from it. Clones of a `TokenKey`
+/// share the derived key, so the session key derives it only once.
+pub struct PassphraseKey {
+    passphrase: String,
+    derived: std::sync::Mutex<Option<(KdfParams, [u8; 32])>>,
+}
+
+impl PassphraseKey {
+    /// Cipher key for `kdf`, derived on a blocking thread unless cached.
+    async fn derive(self: &Arc<Self>, kdf: &KdfParams) -> AppResult<[u8; 32]> {
+        let cached = self.derived.lock().unwrap().clone();
+        if let Some((_, key)) = cached.filter(|(params, _)| params == kdf) {
+            return Ok(key);
+        }
+        let (this, params) = (Arc::clone(self), kdf.clone());
+        let key = tauri::async_runtime::spawn_blocking(move || params.derive(&this.passphrase))
+            .await
+            .map_err(|e| e.to_string())??;
+        *self.derived.lock().unwrap() = Some((kdf.clone(), key));
+        Ok(key)
+    }
+}
+
+impl TokenKey {
+    pub fn passphrase(passphrase: String) -> AppResult<Self> {
+        if passphrase.is_empty() {
+            return Err(AppError::new(ErrorKind::InvalidInput, "passphrase must not be empty"));
+        }
+        Ok(TokenKey::Passphrase(Arc::new(PassphraseKey { passphrase, derived: std::sync::Mutex::new(None) })))
+    }
+
+    /// Cipher key for a file with KDF parameters `kdf`.
+    async fn cipher_key(&self, kdf: Option<&KdfParams>) -> AppResult<[u8; 32]> {
+        match (self, kdf) {
+            (TokenKey::Raw(key), None) => Ok(*key),
+            (TokenKey::Passphrase(p), Some(kdf)) => p.derive(kdf).await,
+            (TokenKey::Raw(_), Some(_)) => Err(AppError::new(ErrorKind::TokenStore, "token store is encrypted with a passphrase")
+                .with_hint("Unlock it with the passphrase or set YOUTUBE_TOKEN_PASSPHRASE.")),
+            (TokenKey::Passphrase(_), None) => Err(AppError::new(ErrorKind::TokenStore, "token store is encrypted with a raw key")
+                .with_hint("Set YOUTUBE_TOKEN_KEY to the key it was written with.")),
+        }
+    }
+
+    /// KDF parameters for a write: those the key was last derived with, so
+    /// writes keep the file's salt and need no new derivation.
+    fn kdf_for_write(&self) -> Option<KdfParams> {
+        match self {
+            TokenKey::Raw(_) => None,
+            TokenKey::Passphrase(p) => {
+                Some(p.derived.lock().unwrap().as_ref().map(|(kdf, _)| kdf.clone()).unwrap_or_else(KdfParams::generate))
+            }
+        }
+    }
+
+    /// Forget the derived key so that the next write picks a fresh salt.
+    fn new_salt(&self) {
+        if let TokenKey::Passphrase(p) = self {
+            *p.derived.lock().unwrap() = None;
+        }
+    }
+}
+
+/// Argon2id salt and costs, stored in the header of passphrase-encrypted files.
+#[derive(Debug, Clone, PartialEq)]
+struct KdfParams {
+    salt: [u8; 16],
+    /// Memory in KiB.
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+}
+
+impl KdfParams {
+    const LEN: usize = 28;
+    /// Refuse headers asking for more than 1 GiB of memory.
+    const MAX_M_COST: u32 = 1 << 20;
+
+    fn generate() -> Self {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        KdfParams {
+            salt,
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = self.salt.to_vec();
+        for v in [self.m_cost, self.t_cost, self.p_cost] {
+            out.extend(v.to_le_bytes());
+        }
+        out
+    }
+
+    fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::LEN {
+            return None;
+        }
+        let word = |i: usize| u32::from_le_bytes(data[16 + 4 * i..20 + 4 * i].try_into().unwrap());
+        let params = KdfParams {
+            salt: data[..16].try_into().unwrap(),
+            m_cost: word(0),
+            t_cost: word(1),
+            p_cost: word(2),
+        };
+        (params.m_cost <= Self::MAX_M_COST).then_some(params)
+    }
+
+    fn derive(&self, passphrase: &str) -> AppResult<[u8; 32]> {
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(32)).map_err(|_| unreadable())?;
+        let mut key = [0u8; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &self.salt, &mut key)
+            .map_err(|e| AppError::new(ErrorKind::TokenStore, format!("failed to derive key: {}", e)))?;
+        Ok(key)
+    }
+}
+
+const MAGIC: &[u8; 4] = b"YTTK";
+const VERSION: u8 = 1;
+const NONCE_LEN: usize = 24;
+/// Poly1305 tag appended to the ciphertext.
+const TAG_LEN: usize = 16;
+
+/// Start of the token file: magic, format version and how the key is
+/// derived. It is authenticated as associated data, so it cannot be altered
+/// without failing decryption.
+#[derive(Debug, Clone, PartialEq)]
+struct Header {
+    kdf: Option<KdfParams>,
+}
+
+impl Header {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        out.push(VERSION);
+        match &self.kdf {
+            None => out.push(0),
+            Some(kdf) => {
+                out.push(1);
+                out.extend(kdf.to_bytes());
+            }
+        }
+        out
+    }
+
+    /// Parse a header and return it with its length.
+    fn parse(data: &[u8], path: &Path) -> AppResult<(Self, usize)> {
+        if data.len() < MAGIC.len() + 2 || !data.starts_with(MAGIC) {
+            return Err(corrupt(path));
+        }
+        let version = data[MAGIC.len()];
+        if version != VERSION {
+            return Err(AppError::new(
+                ErrorKind::TokenStore,
+                format!("token store {} has unsupported format version {}", path.display(), version),
+            )
+            .with_hint("It was written by a newer version of the app; update the app."));
+        }
+        let start = MAGIC.len() + 2;
+        match data[MAGIC.len() + 1] {
+            0 => Ok((Header { kdf: None }, start)),
+            1 => {
+                let kdf = KdfParams::from_bytes(&data[start..]).ok_or_else(|| corrupt(path))?;
+                Ok((Header { kdf: Some(kdf) }, start + KdfParams::LEN))
+            }
+            _ => Err(corrupt(path)),
+        }
+    }
+}
+
+/// Tokens of one account, keyed by their sorted scopes.
+pub type AccountTokens = HashMap<String, TokenInfo>;
+
+/// Decrypted contents of the token file: the tokens of every account.
 #[derive(Serialize, Deserialize, Default)]
-struct StoredTokens(HashMap<String, TokenInfo>);
+#[serde(deny_unknown_fields)]
+struct StoredTokens {
+    accounts: BTreeMap<String, AccountTokens>,
+}
 
+impl StoredTokens {
+    /// Files written before accounts existed hold one account's tokens.
+    fn parse(data: &[u8]) -> serde_json::Result<Self> {
+        serde_json::from_slice(data).or_else(|_| {
+            let legacy: AccountTokens = serde_json::from_slice(data)?;
+            Ok(StoredTokens { accounts: BTreeMap::from([(DEFAULT_ACCOUNT.to_string(), legacy)]) })
+        })
+    }
+}
+
+/// Token storage of one account. All accounts share an encrypted file; each
+/// write merges this account's tokens into the current file contents.
 pub struct EncryptedTokenStorage {
     path: PathBuf,
-    key: [u8; 32],
-    tokens: Mutex<StoredTokens>,
+    key: TokenKey,
+    account: String,
+    tokens: Mutex<AccountTokens>,
 }
 
 impl EncryptedTokenStorage {
-    pub async fn new(path: impl Into<PathBuf>, key: [u8; 32]) -> Result<Self> {
+    pub async fn new(path: impl Into<PathBuf>, key: TokenKey, account: &str) -> AppResult<Self> {
         let path = path.into();
-        let tokens = match tokio::fs::read(&path).await {
-            Ok(data) => {
-                if data.len() < 24 {
-                    StoredTokens::default()
-                } else {
-                    let (nonce_bytes, cipher_bytes) = data.split_at(24);
-                    let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
-                    let nonce = XNonce::from_slice(nonce_bytes);
-                    let decrypted = cipher
-                        .decrypt(nonce, cipher_bytes)
-                        .context("decrypt token store")?;
-                    serde_json::from_slice(&decrypted)?
-                }
-            }
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => StoredTokens::default(),
-            Err(e) => return Err(e.into()),
-        };
+        migrate(&path, &key).await?;
+        let tokens = read_all(&path, &key).await?.accounts.remove(account).unwrap_or_default();
         Ok(Self {
             path,
             key,
+            account: account.to_string(),
             tokens: Mutex::new(tokens),
         })
     }
 
-    async fn write(&self) -> Result<()> {
-        use tokio::io::AsyncWriteExt;
+    async fn write(&self) -> AppResult<()> {
         let tokens = self.tokens.lock().await;
-        let data = serde_json::to_vec(&*tokens)?;
-        let cipher = XChaCha20Poly1305::new(Key::from_slice(&self.key));
-        let mut nonce = [0u8; 24];
-        OsRng.fill_bytes(&mut nonce);
-        let ciphertext = cipher
-            .encrypt(XNonce::from_slice(&nonce), data.as_ref())
-            .context("encrypt tokens")?;
-        let mut output = nonce.to_vec();
-        output.extend(ciphertext);
-        let mut file = tokio::fs::File::create(&self.path).await?;
-        file.write_all(&output).await?;
-        Ok(())
+        let _guard = WRITE_LOCK.lock().await;
+        let mut all = read_all(&self.path, &self.key).await?;
+        all.accounts.insert(self.account.clone(), tokens.clone());
+        write_all(&self.path, &self.key, &all).await
     }
 
     fn key_for(scopes: &[&str]) -> String {
@@ -69,19 +282,217 @@ impl EncryptedTokenStorage {
     }
 }
 
+/// Delete an account's tokens. Returns whether it had any.
+pub async fn remove_account(path: &Path, key: &TokenKey, account: &str) -> AppResult<bool> {
+    let _guard = WRITE_LOCK.lock().await;
+    let mut all = read_all(path, key).await?;
+    if all.accounts.remove(account).is_none() {
+        return Ok(false);
+    }
+    write_all(path, key, &all).await?;
+    Ok(true)
+}
+
+/// Tokens of one account in portable, unencrypted form, for moving a sign-in
+/// to a machine that cannot run the browser flow.
+#[derive(Serialize, Deserialize)]
+pub struct TokenBundle {
+    pub version: u32,
+    pub tokens: AccountTokens,
+}
+
+/// Entry of yup-oauth2's own JSON token cache, which is also accepted.
+#[derive(Deserialize)]
+struct CachedToken {
+    scopes: Vec<String>,
+    token: TokenInfo,
+}
+
+impl TokenBundle {
+    const VERSION: u32 = 1;
+
+    pub fn new(tokens: AccountTokens) -> Self {
+        TokenBundle { version: Self::VERSION, tokens }
+    }
+
+    pub fn parse(data: &str) -> AppResult<Self> {
+        let bundle = match serde_json::from_str::<TokenBundle>(data) {
+            Ok(bundle) if bundle.version > Self::VERSION => {
+                return Err(AppError::new(
+                    ErrorKind::InvalidInput,
+                    format!("token bundle version {} is not supported", bundle.version),
+                ))
+            }
+            Ok(bundle) => bundle,
+            Err(_) => {
+                let cached: Vec<CachedToken> = serde_json::from_str(data).map_err(|e| {
+                    AppError::new(ErrorKind::InvalidInput, "not a token bundle")
+                        .caused_by(&e)
+                        .with_hint("Export the tokens with the token export command on the signed-in machine.")
+                })?;
+                let tokens = cached
+                    .into_iter()
+                    .map(|c| {
+                        let scopes: Vec<&str> = c.scopes.iter().map(String::as_str).collect();
+                        (EncryptedTokenStorage::key_for(&scopes), c.token)
+                    })
+                    .collect();
+                TokenBundle::new(tokens)
+            }
+        };
+        if !bundle.tokens.values().any(|t| t.refresh_token.is_some() || t.access_token.is_some()) {
+            return Err(AppError::new(ErrorKind::InvalidInput, "token bundle contains no tokens"));
+        }
+        Ok(bundle)
+    }
+}
+
+/// Add a bundle's tokens to `account`, replacing tokens for the same scopes.
+pub async fn import(path: &Path, key: &TokenKey, account: &str, bundle: TokenBundle) -> AppResult<usize> {
+    let _guard = WRITE_LOCK.lock().await;
+    let mut all = read_all(path, key).await?;
+    let count = bundle.tokens.len();
+    let tokens = all.accounts.entry(account.to_string()).or_default();
+    for (scopes, token) in bundle.tokens {
+        let scopes: Vec<&str> = scopes.split_whitespace().collect();
+        tokens.insert(EncryptedTokenStorage::key_for(&scopes), token);
+    }
+    write_all(path, key, &all).await?;
+    Ok(count)
+}
+
+pub async fn export(path: &Path, key: &TokenKey, account: &str) -> AppResult<TokenBundle> {
+    let tokens = read_all(path, key).await?.accounts.remove(account).ok_or_else(|| {
+        AppError::new(ErrorKind::NotSignedIn, format!("account {} has no stored tokens", account))
+    })?;
+    Ok(TokenBundle::new(tokens))
+}
+
+/// Tokens of every account in the store.
+pub async fn load(path: &Path, key: &TokenKey) -> AppResult<BTreeMap<String, AccountTokens>> {
+    Ok(read_all(path, key).await?.accounts)
+}
+
+/// Check that `key` opens the store, if there is one.
+pub async fn verify(path: &Path, key: &TokenKey) -> AppResult<()> {
+    read_all(path, key).await.map(|_| ())
+}
+
+/// Re-encrypt the store under `new`. Passphrase keys get a fresh salt.
+pub async fn rotate(path: &Path, old: &TokenKey, new: &TokenKey) -> AppResult<()> {
+    let _guard = WRITE_LOCK.lock().await;
+    let all = read_all(path, old).await?;
+    new.new_salt();
+    write_all(path, new, &all).await
+}
+
+/// Marker next to the token file, created once the store was written with a
+/// header. From then on a headerless file can only be a downgrade.
+fn migrated_marker(path: &Path) -> PathBuf {
+    let mut marker = path.as_os_str().to_owned();
+    marker.push(".migrated");
+    PathBuf::from(marker)
+}
+
+/// Rewrite a store from before the header in the current format. This runs
+/// once; afterwards headerless files are rejected.
+async fn migrate(path: &Path, key: &TokenKey) -> AppResult<()> {
+    if tokio::fs::metadata(migrated_marker(path)).await.is_ok() {
+        return Ok(());
+    }
+    let _guard = WRITE_LOCK.lock().await;
+    let all = read_all(path, key).await?;
+    write_all(path, key, &all).await
+}
+
+async fn read_all(path: &Path, key: &TokenKey) -> AppResult<StoredTokens> {
+    let data = match tokio::fs::read(path).await {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(StoredTokens::default()),
+        Err(e) => return Err(e.into()),
+    };
+    let (kdf, aad, body) = if data.starts_with(MAGIC) {
+        let (header, len) = Header::parse(&data, path)?;
+        (header.kdf, &data[..len], &data[len..])
+    } else if tokio::fs::metadata(migrated_marker(path)).await.is_ok() {
+        return Err(corrupt(path));
+    } else {
+        // Files from before the header: nonce and ciphertext under a raw key.
+        // They are read until migrated.
+        (None, &data[..0], &data[..])
+    };
+    if body.len() < NONCE_LEN + TAG_LEN {
+        return Err(corrupt(path));
+    }
+    let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&key.cipher_key(kdf.as_ref()).await?));
+    let decrypted = cipher
+        .decrypt(XNonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+        .map_err(|_| unreadable())?;
+    StoredTokens::parse(&decrypted).map_err(|e| corrupt(path).caused_by(&e))
+}
+
+async fn write_all(path: &Path, key: &TokenKey, tokens: &StoredTokens) -> AppResult<()> {
+    let data = serde_json::to_vec(tokens)?;
+    let header = Header { kdf: key.kdf_for_write() };
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&key.cipher_key(header.kdf.as_ref()).await?));
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    let mut output = header.to_bytes();
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce), Payload { msg: &data, aad: &output })
+        .map_err(|_| AppError::new(ErrorKind::TokenStore, "failed to encrypt tokens"))?;
+    output.extend(nonce);
+    output.extend(ciphertext);
+    write_atomic(path, &output).await?;
+    let marker = migrated_marker(path);
+    if tokio::fs::metadata(&marker).await.is_err() {
+        tokio::fs::write(&marker, b"").await?;
+    }
+    Ok(())
+}
+
+/// Replace `path` with `data` so that a crash leaves either the old or the
+/// new file, never a truncated one.
+async fn write_atomic(path: &Path, data: &[u8]) -> AppResult<()> {
+    use tokio::io::AsyncWriteExt;
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let res = async {
+        let mut file = tokio::fs::File::create(&tmp).await?;
+        file.write_all(data).await?;
+        file.sync_all().await?;
+        drop(file);
+        tokio::fs::rename(&tmp, path).await
+    }
+    .await;
+    if let Err(e) = res {
+        let _ = tokio::fs::remove_file(&tmp).await;
+        return Err(e.into());
+    }
+    // Make the rename itself durable.
+    #[cfg(unix)]
+    {
+        let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        tokio::fs::File::open(dir).await?.sync_all().await?;
+    }
+    Ok(())
+}
+
 #[async_trait::async_trait]
 impl TokenStorage for EncryptedTokenStorage {
     async fn set(&self, scopes: &[&str], token: TokenInfo) -> Result<()> {
         {
             let mut lock = self.tokens.lock().await;
-            lock.0.insert(Self::key_for(scopes), token);
+            lock.insert(Self::key_for(scopes), token);
         }
-        self.write().await
+        Ok(self.write().await?)
     }
 
     async fn get(&self, scopes: &[&str]) -> Option<TokenInfo> {
         let lock = self.tokens.lock().await;
-        lock.0.get(&Self::key_for(scopes)).cloned()
+        lock.get(&Self::key_for(scopes)).cloned()
     }
 }
 
@@ -95,9 +506,9 @@ mod token_store_tests {
         tauri::async_runtime::block_on(async {
             let dir = tempdir().unwrap();
             let path = dir.path().join("tokens.bin");
-            let key = [1u8; 32];
+            let key = TokenKey::Raw([1u8; 32]);
 
-            let storage = EncryptedTokenStorage::new(&path, key).await.unwrap();
+            let storage = EncryptedTokenStorage::new(&path, key.clone(), DEFAULT_ACCOUNT).await.unwrap();
             let token = TokenInfo {
                 access_token: Some("access".into()),
                 refresh_token: Some("refresh".into()),
@@ -117,9 +528,9 @@ mod token_store_tests {
         tauri::async_runtime::block_on(async {
             let dir = tempdir().unwrap();
             let path = dir.path().join("tokens.bin");
-            let correct_key = [2u8; 32];
+            let correct_key = TokenKey::Raw([2u8; 32]);
 
-            let storage = EncryptedTokenStorage::new(&path, correct_key)
+            let storage = EncryptedTokenStorage::new(&path, correct_key, DEFAULT_ACCOUNT)
                 .await
                 .unwrap();
             let token = TokenInfo {
@@ -132,9 +543,157 @@ mod token_store_tests {
 
             drop(storage);
 
-            let wrong_key = [3u8; 32];
-            let result = EncryptedTokenStorage::new(&path, wrong_key).await;
+            let wrong_key = TokenKey::Raw([3u8; 32]);
+            let result = EncryptedTokenStorage::new(&path, wrong_key, DEFAULT_ACCOUNT).await;
             assert!(result.is_err());
         });
     }
+
+    #[test]
+    fn accounts_share_the_file() {
+        tauri::async_runtime::block_on(async {
+            let dir = tempdir().unwrap();
+            let path = dir.path().join("tokens.bin");
+            let key = TokenKey::Raw([4u8; 32]);
+            let token = |t: &str| TokenInfo {
+                access_token: Some(t.into()),
+                refresh_token: None,
+                expires_at: None,
+                id_token: None,
+            };
+
+            let main = EncryptedTokenStorage::new(&path, key.clone(), "main").await.unwrap();
+            let second = EncryptedTokenStorage::new(&path, key.clone(), "second").await.unwrap();
+            main.set(&["s"], token("m")).await.unwrap();
+            second.set(&["s"], token("x")).await.unwrap();
+
+            let reloaded = EncryptedTokenStorage::new(&path, key.clone(), "main").await.unwrap();
+            assert_eq!(reloaded.get(&["s"]).await, Some(token("m")));
+            assert!(remove_account(&path, &key, "main").await.unwrap());
+            assert!(!remove_account(&path, &key, "main").await.unwrap());
+            let second = EncryptedTokenStorage::new(&path, key.clone(), "second").await.unwrap();
+            assert_eq!(second.get(&["s"]).await, Some(token("x")));
+        });
+    }
+
+    #[test]
+    fn passphrase_keys_and_rotation() {
+        tauri::async_runtime::block_on(async {
+            let dir = tempdir().unwrap();
+            let path = dir.path().join("tokens.bin");
+            let raw = TokenKey::Raw([5u8; 32]);
+            let token = TokenInfo {
+                access_token: Some("a".into()),
+                refresh_token: Some("r".into()),
+                expires_at: None,
+                id_token: None,
+            };
+            let storage = EncryptedTokenStorage::new(&path, raw.clone(), DEFAULT_ACCOUNT).await.unwrap();
+            storage.set(&["s"], token.clone()).await.unwrap();
+
+            let pass = TokenKey::passphrase("correct horse".into()).unwrap();
+            rotate(&path, &raw, &pass).await.unwrap();
+            assert!(verify(&path, &raw).await.is_err());
+            assert!(verify(&path, &TokenKey::passphrase("wrong horse".into()).unwrap()).await.is_err());
+            let reopened = EncryptedTokenStorage::new(&path, pass.clone(), DEFAULT_ACCOUNT).await.unwrap();
+            assert_eq!(reopened.get(&["s"]).await, Some(token.clone()));
+
+            let header = |path: &Path| Header::parse(&std::fs::read(path).unwrap(), path).unwrap().0;
+            let kdf = header(&path).kdf.unwrap();
+            assert_eq!(kdf.m_cost, Params::DEFAULT_M_COST);
+            // Writes keep the salt; only rotation replaces it.
+            reopened.set(&["t"], token).await.unwrap();
+            assert_eq!(header(&path).kdf.unwrap(), kdf);
+            rotate(&path, &pass, &pass).await.unwrap();
+            assert_ne!(header(&path).kdf.unwrap().salt, kdf.salt);
+        });
+    }
+
+    #[test]
+    fn corrupt_files_are_reported() {
+        tauri::async_runtime::block_on(async {
+            let dir = tempdir().unwrap();
+            let path = dir.path().join("tokens.bin");
+            let key = TokenKey::Raw([6u8; 32]);
+            for data in [&b""[..], b"short", b"YTTK\x01\x00truncated"] {
+                std::fs::write(&path, data).unwrap();
+                let err = read_all(&path, &key).await.unwrap_err();
+                assert!(err.message.contains("corrupt"), "{}", err.message);
+            }
+            std::fs::write(&path, b"YTTK\x09\x00").unwrap();
+            assert!(read_all(&path, &key).await.unwrap_err().message.contains("version 9"));
+
+            // Unknown key derivation.
+            write_all(&path, &key, &StoredTokens::default()).await.unwrap();
+            let mut data = std::fs::read(&path).unwrap();
+            data[MAGIC.len() + 1] = 2;
+            std::fs::write(&path, &data).unwrap();
+            assert!(read_all(&path, &key).await.is_err());
+        });
+    }
+
+    #[test]
+    fn headerless_files_are_upgraded() {
+        tauri::async_runtime::block_on(async {
+            let dir = tempdir().unwrap();
+            let path = dir.path().join("tokens.bin");
+            let raw = [7u8; 32];
+            let cipher = XChaCha20Poly1305::new(Key::from_slice(&raw));
+            let nonce = [0u8; NONCE_LEN];
+            let mut legacy = nonce.to_vec();
+            let tokens = br#"{"s": {"access_token": "a", "refresh_token": "r", "expires_at": null, "id_token": null}}"#;
+            legacy.extend(cipher.encrypt(XNonce::from_slice(&nonce), &tokens[..]).unwrap());
+            std::fs::write(&path, &legacy).unwrap();
+
+            let key = TokenKey::Raw(raw);
+            assert!(read_all(&path, &key).await.is_ok());
+            let storage = EncryptedTokenStorage::new(&path, key.clone(), DEFAULT_ACCOUNT).await.unwrap();
+            assert!(std::fs::read(&path).unwrap().starts_with(MAGIC));
+            assert_eq!(storage.get(&["s"]).await.unwrap().refresh_token.as_deref(), Some("r"));
+            storage.set(&["s"], TokenInfo { access_token: None, refresh_token: None, expires_at: None, id_token: None }).await.unwrap();
+            assert!(!dir.path().join("tokens.bin.tmp").exists());
+            assert!(read_all(&path, &key).await.unwrap().accounts.contains_key(DEFAULT_ACCOUNT));
+
+            // Once migrated, a headerless file is a downgrade.
+            std::fs::write(&path, &legacy).unwrap();
+            assert!(read_all(&path, &key).await.unwrap_err().message.contains("corrupt"));
+        });
+    }
+
+    #[test]
+    fn bundles_move_tokens_between_stores() {
+        tauri::async_runtime::block_on(async {
+            let dir = tempdir().unwrap();
+            let (from, to) = (dir.path().join("a.bin"), dir.path().join("b.bin"));
+            let key = TokenKey::Raw([8u8; 32]);
+            let token = TokenInfo {
+                access_token: Some("a".into()),
+                refresh_token: Some("r".into()),
+                expires_at: None,
+                id_token: None,
+            };
+            let storage = EncryptedTokenStorage::new(&from, key.clone(), DEFAULT_ACCOUNT).await.unwrap();
+            storage.set(&["y", "x"], token.clone()).await.unwrap();
+
+            let exported = serde_json::to_string(&export(&from, &key, DEFAULT_ACCOUNT).await.unwrap()).unwrap();
+            assert!(export(&from, &key, "other").await.is_err());
+            assert_eq!(import(&to, &key, "server", TokenBundle::parse(&exported).unwrap()).await.unwrap(), 1);
+            let imported = EncryptedTokenStorage::new(&to, key.clone(), "server").await.unwrap();
+            assert_eq!(imported.get(&["x", "y"]).await, Some(token));
+
+            let cache = r#"[{"scopes": ["y", "x"], "token": {"access_token": "a", "refresh_token": "r", "expires_at": null, "id_token": null}}]"#;
+            assert!(TokenBundle::parse(cache).unwrap().tokens.contains_key("x y"));
+            assert!(TokenBundle::parse(r#"{"version": 1, "tokens": {}}"#).is_err());
+            assert!(TokenBundle::parse(r#"{"version": 2, "tokens": {}}"#).is_err());
+        });
+    }
+
+    #[test]
+    fn legacy_files_become_the_default_account() {
+        let legacy = r#"{"s": {"access_token": "a", "refresh_token": null, "expires_at": null, "id_token": null}}"#;
+        let stored = StoredTokens::parse(legacy.as_bytes()).unwrap();
+        assert!(stored.accounts[DEFAULT_ACCOUNT].contains_key("s"));
+        let current = StoredTokens::parse(br#"{"accounts": {}}"#).unwrap();
+        assert!(current.accounts.is_empty());
+    }
 }
@@ -0,0 +1,180 @@
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use fs2::FileExt;
+
+/// Prefix of per-job scratch directories inside the scratch root.
+const JOB_PREFIX: &str = "job-";
+/// Held locked by the running job for as long as its directory exists.
+const LOCK_FILE: &str = ".lock";
+/// Job directories without a lock file (from older versions, or caught
+/// between creation and locking) are only removed once this old.
+const STALE_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+/// Names of the loose files written to the system temp dir before jobs had
+/// their own scratch directory, as `<name>_<nanos>.mp4`.
+const LEGACY_NAMES: [&str; 3] = ["segment", "main", "list"];
+
+/// Default scratch root when `scratchDir` is not configured.
+pub fn default_root() -> PathBuf {
+    std::env::temp_dir().join("ytapp")
+}
+
+/// Per-job scratch directory for intermediate segments and lists.
+///
+/// The directory and everything in it is removed when the workspace is
+/// dropped, so intermediates are cleaned up on success, on error and when
+/// a job is canceled.
+pub struct Workspace {
+    dir: PathBuf,
+    lock: Option<File>,
+}
+
+impl Workspace {
+    pub fn new(root: &Path) -> Result<Self, String> {
+        let mut ws = Workspace::planned(root);
+        fs::create_dir_all(&ws.dir).map_err(|e| format!("failed to create scratch dir: {}", e))?;
+        let lock = File::create(ws.dir.join(LOCK_FILE)).map_err(|e| format!("failed to create scratch dir: {}", e))?;
+        lock.try_lock_exclusive().map_err(|e| format!("failed to lock scratch dir: {}", e))?;
+        ws.lock = Some(lock);
+        Ok(ws)
+    }
+
+    /// Workspace paths for a dry run; nothing is created on disk.
+    pub fn planned(root: &Path) -> Self {
+        let ts = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        Workspace { dir: root.join(format!("{}{}-{}", JOB_PREFIX, std::process::id(), ts)), lock: None }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+
+    /// A unique path inside the workspace, e.g. `file("segment", "mp4")`.
+    pub fn file(&self, name: &str, ext: &str) -> PathBuf {
+        let ts = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        self.dir.join(format!("{}_{}.{}", name, ts, ext))
+    }
+}
+
+impl Drop for Workspace {
+    fn drop(&mut self) {
+        // Close the lock first; Windows cannot delete a file that is open.
+        drop(self.lock.take());
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Move a file out of the workspace, falling back to copy and delete when
+/// the scratch root is on a different volume than the destination.
+pub fn move_file(from: &Path, to: &Path) -> Result<(), String> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to).map_err(|e| e.to_string())?;
+    let _ = fs::remove_file(from);
+    Ok(())
+}
+
+/// Remove scratch directories left behind by runs that crashed, plus loose
+/// segment files from older versions in the system temp dir. Directories
+/// whose lock is still held, by this or another running instance, are kept.
+/// Returns the number of entries removed.
+pub fn sweep(root: &Path) -> usize {
+    let mut removed = 0;
+    if let Ok(entries) = fs::read_dir(root) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with(JOB_PREFIX) && abandoned(&entry.path()) && fs::remove_dir_all(entry.path()).is_ok() {
+                removed += 1;
+            }
+        }
+    }
+    if let Ok(entries) = fs::read_dir(std::env::temp_dir()) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if is_legacy_temp(&name) && fs::remove_file(entry.path()).is_ok() {
+                removed += 1;
+            }
+        }
+    }
+    removed
+}
+
+/// Whether no running job owns `dir`: its lock can be taken, or it has no
+/// lock file and is older than `STALE_AFTER`.
+fn abandoned(dir: &Path) -> bool {
+    match File::open(dir.join(LOCK_FILE)) {
+        // The handle is closed before returning so the directory can be removed.
+        Ok(lock) => lock.try_lock_exclusive().is_ok(),
+        Err(_) => fs::metadata(dir)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.elapsed().ok())
+            .map(|age| age >= STALE_AFTER)
+            .unwrap_or(false),
+    }
+}
+
+/// Matches the names produced by the old `temp_file` helper: `segment`,
+/// `main` or `list`, followed by `_<nanos>.mp4`.
+fn is_legacy_temp(name: &str) -> bool {
+    let Some(stem) = name.strip_suffix(".mp4") else { return false };
+    let Some((prefix, ts)) = stem.rsplit_once('_') else { return false };
+    LEGACY_NAMES.contains(&prefix) && ts.len() >= 15 && ts.chars().all(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn workspace_removed_on_drop() {
+        let root = tempfile::tempdir().unwrap();
+        let ws = Workspace::new(root.path()).unwrap();
+        let seg = ws.file("segment", "mp4");
+        fs::write(&seg, b"data").unwrap();
+        let dir = ws.path().to_path_buf();
+        assert!(dir.exists());
+        drop(ws);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn sweep_keeps_locked_recent_and_unrelated_dirs() {
+        let root = tempfile::tempdir().unwrap();
+        let crashed = root.path().join("job-1-123");
+        fs::create_dir_all(&crashed).unwrap();
+        File::create(crashed.join(LOCK_FILE)).unwrap();
+        let unlocked = root.path().join("job-2-456");
+        fs::create_dir_all(&unlocked).unwrap();
+        let other = root.path().join("keep-me");
+        fs::create_dir_all(&other).unwrap();
+        let ws = Workspace::new(root.path()).unwrap();
+        sweep(root.path());
+        assert!(!crashed.exists());
+        assert!(unlocked.exists());
+        assert!(other.exists());
+        assert!(ws.path().exists());
+    }
+
+    #[test]
+    fn legacy_temp_names() {
+        assert!(is_legacy_temp("segment_1712345678901234567.mp4"));
+        assert!(is_legacy_temp("list_1712345678901234567.mp4"));
+        assert!(!is_legacy_temp("main0_1712345678901234567.mp4"));
+        assert!(!is_legacy_temp("meta_1712345678901234567.txt"));
+        assert!(!is_legacy_temp("tagged_1712345678901234567.mp4"));
+        assert!(!is_legacy_temp("segment_final.mp4"));
+        assert!(!is_legacy_temp("holiday_1712345678901234567.mp4"));
+        assert!(!is_legacy_temp("maintenance_1712345678901234567.mp4"));
+        assert!(!is_legacy_temp("main_backup_1712345678901234567.mp4"));
+        assert!(!is_legacy_temp("list_1712345678901234567.txt"));
+    }
+}
@@ -16,6 +16,7 @@ import ProfilesPage from './components/ProfilesPage';
 import FontSelector from './components/FontSelector';
 import LanguageSelector from './components/LanguageSelector';
 import PlaylistSelector from './components/PlaylistSelector';
+import AccountSelector from './components/AccountSelector';
 import SizeSlider from './components/SizeSlider';
 import Section from './components/Section';
 import Accordion from './components/Accordion';
@@ -35,6 +36,7 @@ import UpdateModal from './components/UpdateModal';
 import QueuePage from './components/QueuePage';
 import LogsPage from './components/LogsPage';
 import { check } from '@tauri-apps/plugin-updater';
+import { getFfmpegCapabilities, featureAvailable, FfmpegCapabilities } from './features/dependencies';
 import { relaunch } from '@tauri-apps/plugin-process';
 
 const App: React.FC = () => {
@@ -76,12 +78,14 @@ const App: React.FC = () => {
     const [outputs, setOutputs] = useState<string[]>([]);
     const [preview, setPreview] = useState('');
     const [showUpdate, setShowUpdate] = useState(false);
+    const [ffmpegCaps, setFfmpegCaps] = useState<FfmpegCapabilities | null>(null);
     const [title, setTitle] = useState('');
     const [description, setDescription] = useState('');
     const [tags, setTags] = useState('');
     const [publishDate, setPublishDate] = useState('');
     const [privacy, setPrivacy] = useState('public');
     const [playlistId, setPlaylistId] = useState('');
+    const [account, setAccount] = useState('');
     const [thumbnail, setThumbnail] = useState('');
     const [showEditor, setShowEditor] = useState(false);
     const [output, setOutput] = useState('');
@@ -119,6 +123,7 @@ const App: React.FC = () => {
                 watchDirectory(s.watchDir, { autoUpload: s.autoUpload });
             }
         });
+        getFfmpegCapabilities().then(setFfmpegCaps).catch(() => {});
         check().then(res => {
             if (res) setShowUpdate(true);
         }).catch(() => {});
@@ -200,10 +205,13 @@ const App: React.FC = () => {
             setProgress(pct);
             setAnnouncement(`Generating... ${pct}%`);
         }, () => setGenerating(false));
-        setOutputs(o => [...o, out]);
+        setOutputs(o => [...o, ...out.outputs]);
         setGenerating(false);
         setAnnouncement('');
-        notify('Generation complete', 'Video created successfully');
+        notify(
+            'Generation complete',
+            `Video created successfully in ${out.renderSeconds.toFixed(1)}s (${out.speed.toFixed(1)}x realtime${out.fastPath ? ', fast path' : ''})`,
+        );
     };
 
     const buildParams = (): GenerateParams => ({
@@ -236,6 +244,7 @@ const App: React.FC = () => {
         thumbnail: thumbnail || undefined,
         privacy: privacy || undefined,
         playlistId: playlistId || undefined,
+        account: account || undefined,
     });
 
     const handleSaveCurrentProfile = async () => {
@@ -267,6 +276,7 @@ const App: React.FC = () => {
             tags: tags ? tags.split(',').map(t => t.trim()).filter(Boolean) : undefined,
             publishAt: publishDate || undefined,
             thumbnail: thumbnail || undefined,
+            account: account || undefined,
         };
         await saveProfile(name, p);
     };
@@ -311,6 +321,7 @@ const App: React.FC = () => {
         setTags(p.tags ? p.tags.join(', ') : '');
         setPublishDate(p.publishAt || '');
         setThumbnail(p.thumbnail || '');
+        setAccount(p.account || '');
     };
 
     const handleGenerateUpload = async () => {
@@ -324,11 +335,14 @@ const App: React.FC = () => {
                 setAnnouncement(`Upload... ${pct}%`);
             }
         });
-        await generateUpload(buildParams(), () => setGenerating(false));
+        const result = await generateUpload(buildParams(), () => setGenerating(false));
         unlisten();
         setGenerating(false);
         setAnnouncement('');
-        notify('Upload complete', 'Video uploaded successfully');
+        notify(
+            'Upload complete',
+            result.partial ? 'Video uploaded, but some post-upload steps failed' : 'Video uploaded successfully',
+        );
     };
 
     const cancelGenerate = async () => {
@@ -495,14 +509,18 @@ const App: React.FC = () => {
                     targets={translations}
                     onComplete={handleTranscriptionComplete}
                 />
-                <FilePicker
-                    label="Captions"
-                    onSelect={p => {
-                        if (typeof p === 'string') setCaptions(p);
-                        else if (Array.isArray(p) && p.length) setCaptions(p[0]);
-                    }}
-                    filters={[{ name: 'Subtitles', extensions: ['srt'] }]}
-                />
+                {featureAvailable(ffmpegCaps, 'captions') ? (
+                    <FilePicker
+                        label="Captions"
+                        onSelect={p => {
+                            if (typeof p === 'string') setCaptions(p);
+                            else if (Array.isArray(p) && p.length) setCaptions(p[0]);
+                        }}
+                        filters={[{ name: 'Subtitles', extensions: ['srt'] }]}
+                    />
+                ) : (
+                    <span className="error">{t('captions_unavailable')}</span>
+                )}
                 {captions && (
                     <>
                         <span>{captions}</span>
@@ -534,9 +552,13 @@ const App: React.FC = () => {
                     <option value="private">private</option>
                 </select>
             </div>
+            <div className="row">
+                <label>{t('account')}</label>
+                <AccountSelector value={account} onChange={setAccount} />
+            </div>
             <div className="row">
                 <label>{t('playlist')}</label>
-                <PlaylistSelector value={playlistId} onChange={setPlaylistId} />
+                <PlaylistSelector value={playlistId} onChange={setPlaylistId} account={account || undefined} />
             </div>
             <div className="row">
                 <FilePicker
@@ -5,6 +5,7 @@ import { parseCsv, CsvRow } from '../utils/csv';
 import { generateBatchWithProgress } from '../features/batch';
 import { verifyDependencies } from '../features/dependencies';
 import { watchDirectory } from '../features/watch';
+import { generatePlan } from '../features/processing';
 import type { GenerateParams } from '../schema';
 import { mergeProfile, showProgress, withInterrupt, generateVideo, generateAndUpload, transcribeAudio, callWithUploadProgress, callWithProgress } from './utils';
 
@@ -40,6 +41,7 @@ export function registerGenerateCommands(program: Command): void {
     .option('--publish-at <date>', 'schedule publish date (ISO)')
     .option('-p, --profile <name>', 'load profile')
     .option('-q, --quiet', 'suppress progress output')
+    .option('--dry-run', 'print the ffmpeg commands without rendering')
     .action(async (file: string, options: any) => {
       try {
         await verifyDependencies();
@@ -76,6 +78,12 @@ export function registerGenerateCommands(program: Command): void {
           output: options.output,
           ...merged,
         } as any;
+        if (options.dryRun) {
+          const plan = await generatePlan(params);
+          for (const step of plan.steps) console.log(step.line);
+          for (const move of plan.moves) console.log(`# ${move.from} becomes ${move.to}`);
+          return;
+        }
         const result = await withInterrupt(
           () => invoke('cancel_generate'),
           () => generateVideo(
@@ -83,7 +91,10 @@ export function registerGenerateCommands(program: Command): void {
             options.quiet ? undefined : showProgress,
           ),
         );
-        console.log(result);
+        result.outputs.forEach((o: string) => console.log(o));
+        if (!options.quiet) {
+          console.error(`Rendered in ${result.renderSeconds.toFixed(1)}s, ${result.speed.toFixed(1)}x realtime${result.fastPath ? ' (fast path)' : ''}${result.cached ? ' (cached)' : ''}`);
+        }
       } catch (err) {
         console.error('Error generating video:', err);
         process.exitCode = 1;
@@ -500,6 +511,8 @@ export function registerGenerateCommands(program: Command): void {
     .option('--publish-at <date>', 'schedule publish date (ISO)')
     .option('--recursive', 'watch subdirectories')
     .option('--auto-upload', 'upload after generating')
+    .option('--source-metadata <mode>', 'use ID3 tags and cover art: fill, prefer or off')
+    .option('--extensions <exts>', 'comma separated file extensions to watch')
     .action(async (dir: string, options: any) => {
       if (options.color && !options.captionColor) options.captionColor = options.color;
       if (options.bgColor && !options.captionBg) options.captionBg = options.bgColor;
@@ -531,6 +544,8 @@ export function registerGenerateCommands(program: Command): void {
         publishAt: options.publishAt,
         recursive: options.recursive,
         autoUpload: options.autoUpload,
+        sourceMetadata: options.sourceMetadata,
+        extensions: options.extensions ? options.extensions.split(',').map((e: string) => e.trim()).filter(Boolean) : undefined,
       } as any);
     });
 
@@ -1,11 +1,23 @@
 import { Command } from 'commander';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { parseCsv, CsvRow } from '../utils/csv';
 import { verifyDependencies } from '../features/dependencies';
 import { listProfiles, saveProfile, deleteProfile } from '../features/profiles';
-import type { Profile } from '../schema';
+import type { CaptionTrack, Profile } from '../schema';
 import { uploadVideo, uploadVideos, showProgress, withInterrupt, signIn, signOut, isSignedIn } from './utils';
 
+/** Collect `--caption <file:lang[:name]>` options into caption tracks. */
+function collectCaption(value: string, previous: CaptionTrack[] = []): CaptionTrack[] {
+  // Keep a Windows drive prefix such as `C:` in the path.
+  const drive = /^[A-Za-z]:[\\/]/.test(value) ? value.slice(0, 2) : '';
+  const [file, language, name] = value.slice(drive.length).split(':');
+  if (!file || !language) {
+    throw new Error(`invalid caption track "${value}", expected file:lang[:name]`);
+  }
+  return [...previous, { file: drive + file, language, name }];
+}
+
 export function registerUploadCommands(program: Command): void {
   program
     .command('upload')
@@ -18,6 +30,19 @@ export function registerUploadCommands(program: Command): void {
     .option('--thumbnail <file>', 'thumbnail image')
     .option('--privacy <privacy>', 'video privacy')
     .option('--playlist-id <id>', 'playlist ID')
+    .option('--caption <file:lang[:name]>', 'upload a caption track (repeatable)', collectCaption)
+    .option('--caption-draft', 'upload caption tracks as drafts')
+    .option('--category-id <id>', 'numeric YouTube category ID')
+    .option('--default-language <lang>', 'language of the title and description')
+    .option('--audio-language <lang>', 'language spoken in the video')
+    .option('--made-for-kids', 'declare the video as made for kids')
+    .option('--license <license>', 'youtube or creativeCommon')
+    .option('--no-embeddable', 'disallow embedding on other sites')
+    .option('--no-public-stats', 'hide view statistics')
+    .option('--no-notify-subscribers', 'do not notify subscribers')
+    .option('--recording-date <date>', 'recording date (YYYY-MM-DD)')
+    .option('--synthetic-media', 'disclose altered or synthetic content')
+    .option('--account <name>', 'account to upload with')
     .action(async (file: string, options: any) => {
       try {
         await verifyDependencies();
@@ -33,6 +58,21 @@ export function registerUploadCommands(program: Command): void {
               thumbnail: options.thumbnail,
               privacy: options.privacy,
               playlistId: options.playlistId,
+              categoryId: options.categoryId,
+              defaultLanguage: options.defaultLanguage,
+              defaultAudioLanguage: options.audioLanguage,
+              madeForKids: options.madeForKids,
+              license: options.license,
+              embeddable: options.embeddable === false ? false : undefined,
+              publicStatsViewable: options.publicStats === false ? false : undefined,
+              notifySubscribers: options.notifySubscribers === false ? false : undefined,
+              recordingDate: options.recordingDate,
+              containsSyntheticMedia: options.syntheticMedia,
+              account: options.account,
+              captionTracks: options.caption?.map((c: CaptionTrack) => ({
+                ...c,
+                draft: options.captionDraft || undefined,
+              })),
             },
             showProgress,
           ),
@@ -56,6 +96,7 @@ export function registerUploadCommands(program: Command): void {
     .option('--thumbnail <file>', 'thumbnail image')
     .option('--privacy <privacy>', 'video privacy')
     .option('--playlist-id <id>', 'playlist ID')
+    .option('--account <name>', 'account to upload with')
     .action(async (files: string[], options: any) => {
       try {
         await verifyDependencies();
@@ -79,6 +120,7 @@ export function registerUploadCommands(program: Command): void {
                 thumbnail: options.thumbnail,
                 privacy: options.privacy,
                 playlistId: options.playlistId,
+                account: options.account,
               },
               showProgress,
             );
@@ -97,11 +139,16 @@ export function registerUploadCommands(program: Command): void {
                 thumbnail: options.thumbnail,
                 privacy: options.privacy,
                 playlistId: options.playlistId,
+                account: options.account,
               },
               showProgress,
             ),
-          ) as any[];
-          results.forEach((res: any) => console.log(res));
+          );
+          results.results.forEach((res: any) => console.log(res));
+          if (results.held.length) {
+            console.error(`Daily quota reached; not uploaded: ${results.held.join(', ')}`);
+            process.exitCode = 1;
+          }
         }
       } catch (err) {
         console.error('Error uploading videos:', err);
@@ -112,9 +159,10 @@ export function registerUploadCommands(program: Command): void {
   program
     .command('sign-in')
     .description('Authenticate with YouTube')
-    .action(async () => {
+    .option('--device', 'sign in by entering a code on another device')
+    .action(async (options: any) => {
       try {
-        await signIn();
+        await signIn(options.device || process.env.YOUTUBE_AUTH_FLOW === 'device');
         console.log('Sign-in complete');
       } catch (err) {
         console.error('Error during sign-in:', err);
@@ -124,10 +172,12 @@ export function registerUploadCommands(program: Command): void {
 
   program
     .command('sign-out')
-    .description('Remove stored YouTube credentials')
-    .action(async () => {
+    .description('Revoke and remove stored YouTube credentials')
+    .option('--account <name>', 'only sign out this account')
+    .option('--force', 'delete the credentials even if they cannot be revoked')
+    .action(async (options: any) => {
       try {
-        await signOut();
+        await signOut(options.account, options.force);
         console.log('Signed out');
       } catch (err) {
         console.error('Error during sign-out:', err);
@@ -148,6 +198,31 @@ export function registerUploadCommands(program: Command): void {
       }
     });
 
+  program
+    .command('auth-status')
+    .description('Show stored YouTube credentials per account')
+    .option('--account <name>', 'only show this account')
+    .option('--validate', 'check the credentials with Google')
+    .action(async (options: any) => {
+      try {
+        const status: any = await invoke('youtube_sign_in_status', { account: options.account, validate: options.validate });
+        for (const a of status.accounts) {
+          console.log(a.account);
+          for (const t of a.tokens) {
+            const state = t.valid === false ? 'revoked' : t.expired ? 'expired' : 'valid';
+            console.log(`  ${t.scopes.join(' ')}: access token ${state}${t.expiresAt ? ` (expires ${t.expiresAt})` : ''}, ${t.hasRefreshToken ? 'refresh token stored' : 'no refresh token'}`);
+          }
+        }
+        if (!status.signedIn) {
+          console.log('Not signed in');
+          process.exitCode = 1;
+        }
+      } catch (err) {
+        console.error('Error reading sign-in status:', err);
+        process.exitCode = 1;
+      }
+    });
+
   program
     .command('upload-cancel')
     .description('Cancel running upload')
@@ -160,6 +235,141 @@ export function registerUploadCommands(program: Command): void {
       }
     });
 
+  program
+    .command('quota')
+    .description('Show YouTube API quota used today')
+    .action(async () => {
+      try {
+        const status: any = await invoke('quota_status');
+        console.log(`${status.used}/${status.limit} units used, ${status.remaining} left; resets ${status.resetsAt}`);
+        for (const [method, units] of Object.entries(status.methods)) {
+          console.log(`  ${method}: ${units}`);
+        }
+      } catch (err) {
+        console.error('Error reading quota:', err);
+        process.exitCode = 1;
+      }
+    });
+
+  program
+    .command('upload-incomplete')
+    .description('List uploaded videos with failed post-upload steps')
+    .action(async () => {
+      try {
+        const results: any[] = await invoke('upload_incomplete');
+        console.log(JSON.stringify(results, null, 2));
+      } catch (err) {
+        console.error('Error listing uploads:', err);
+        process.exitCode = 1;
+      }
+    });
+
+  program
+    .command('upload-retry')
+    .description('Re-run the failed post-upload steps of a video')
+    .argument('<videoId>', 'YouTube video ID')
+    .action(async (videoId: string) => {
+      try {
+        const result: any = await invoke('upload_retry_steps', { videoId });
+        console.log(JSON.stringify(result, null, 2));
+        if (result.partial) process.exitCode = 1;
+      } catch (err) {
+        console.error('Error retrying upload steps:', err);
+        process.exitCode = 1;
+      }
+    });
+
+  program
+    .command('token-rotate')
+    .description('Re-encrypt stored tokens under the passphrase in YOUTUBE_TOKEN_NEW_PASSPHRASE')
+    .action(async () => {
+      try {
+        const newPassphrase = process.env.YOUTUBE_TOKEN_NEW_PASSPHRASE;
+        if (!newPassphrase) throw new Error('YOUTUBE_TOKEN_NEW_PASSPHRASE is not set');
+        await invoke('token_rotate_key', { newPassphrase });
+        console.log('Token store re-encrypted. Set YOUTUBE_TOKEN_PASSPHRASE to the new passphrase.');
+      } catch (err) {
+        console.error('Error rotating token key:', err);
+        process.exitCode = 1;
+      }
+    });
+
+  program
+    .command('token-import')
+    .description('Import YouTube tokens exported on another machine')
+    .argument('<file>', 'token bundle file')
+    .option('--account <name>', 'account to import into')
+    .action(async (file: string, options: any) => {
+      try {
+        await invoke('youtube_import_tokens', { file, account: options.account });
+        console.log('Tokens imported');
+      } catch (err) {
+        console.error('Error importing tokens:', err);
+        process.exitCode = 1;
+      }
+    });
+
+  program
+    .command('token-export')
+    .description('Export YouTube tokens unencrypted for another machine')
+    .argument('<file>', 'new token bundle file to write')
+    .option('--account <name>', 'account to export')
+    .action(async (file: string, options: any) => {
+      try {
+        await invoke('youtube_export_tokens', { file, account: options.account });
+        console.log(`Tokens written to ${file}; keep this file secret and delete it after importing`);
+      } catch (err) {
+        console.error('Error exporting tokens:', err);
+        process.exitCode = 1;
+      }
+    });
+
+  program
+    .command('account-list')
+    .description('List YouTube accounts')
+    .action(async () => {
+      try {
+        const accounts: any[] = await invoke('account_list');
+        accounts.forEach(a => console.log(a.channelTitle ? `${a.name}\t${a.channelTitle}` : a.name));
+      } catch (err) {
+        console.error('Error listing accounts:', err);
+        process.exitCode = 1;
+      }
+    });
+
+  program
+    .command('account-add')
+    .description('Sign in a new named YouTube account')
+    .argument('<name>', 'account name')
+    .option('--device', 'sign in by entering a code on another device')
+    .action(async (name: string, options: any) => {
+      try {
+        const unlisten = await listen<any>('youtube_device_code', (e) => {
+          console.log(`Open ${e.payload.verificationUrl} and enter the code ${e.payload.userCode}`);
+        });
+        const account: any = await invoke('account_add', { name, device: options.device }).finally(unlisten);
+        console.log(`Added ${account.name}${account.channelTitle ? ` (${account.channelTitle})` : ''}`);
+      } catch (err) {
+        console.error('Error adding account:', err);
+        process.exitCode = 1;
+      }
+    });
+
+  program
+    .command('account-remove')
+    .description('Remove a YouTube account and its tokens')
+    .argument('<name>', 'account name')
+    .option('--force', 'delete the credentials even if they cannot be revoked')
+    .action(async (name: string, options: any) => {
+      try {
+        await invoke('account_remove', { name, force: options.force });
+        console.log(`Removed ${name}`);
+      } catch (err) {
+        console.error('Error removing account:', err);
+        process.exitCode = 1;
+      }
+    });
+
   program
     .command('profile-list')
     .description('List saved profiles')
@@ -2,7 +2,7 @@ import { listen } from '@tauri-apps/api/event';
 import { invoke } from '@tauri-apps/api/core';
 import type { GenerateParams } from '../schema';
 import { translateSrt } from '../utils/translate';
-import type { Profile } from '../schema';
+import type { CaptionTrack, Profile, VideoMetadata } from '../schema';
 import { getProfile } from '../features/profiles';
 
 export async function callWithProgress<T>(fn: () => Promise<T>, onProgress?: (p: number) => void): Promise<T> {
@@ -66,7 +66,7 @@ export async function generateVideo(params: GenerateParams, onProgress?: (p: num
   );
 }
 
-interface UploadParams {
+interface UploadParams extends VideoMetadata {
   file: string;
   title?: string;
   description?: string;
@@ -75,6 +75,8 @@ interface UploadParams {
   thumbnail?: string;
   privacy?: string;
   playlistId?: string;
+  captionTracks?: CaptionTrack[];
+  account?: string;
 }
 
 interface UploadBatchParams extends Omit<UploadParams, 'file'> {
@@ -134,12 +136,22 @@ export async function generateAndUpload(params: GenerateParams, onProgress?: (p:
   }
 }
 
-export async function signIn(): Promise<void> {
-  await invoke('youtube_sign_in');
+export async function signIn(device?: boolean): Promise<void> {
+  let unlisten: (() => void) | undefined;
+  if (device) {
+    unlisten = await listen<{ userCode: string; verificationUrl: string }>('youtube_device_code', (e) => {
+      console.log(`Open ${e.payload.verificationUrl} and enter the code ${e.payload.userCode}`);
+    });
+  }
+  try {
+    await invoke('youtube_sign_in', { device });
+  } finally {
+    if (unlisten) unlisten();
+  }
 }
 
-export async function signOut(): Promise<void> {
-  await invoke('youtube_sign_out');
+export async function signOut(account?: string, force?: boolean): Promise<void> {
+  await invoke('youtube_sign_out', { account, force });
 }
 
 export async function isSignedIn(): Promise<boolean> {
@@ -0,0 +1,30 @@
+import React, { useEffect, useState } from 'react';
+import { useTranslation } from 'react-i18next';
+import { listAccounts, Account } from '../features/youtube';
+
+interface AccountSelectorProps {
+    value: string;
+    onChange: (name: string) => void;
+}
+
+const AccountSelector: React.FC<AccountSelectorProps> = ({ value, onChange }) => {
+    const { t } = useTranslation();
+    const [accounts, setAccounts] = useState<Account[]>([]);
+
+    useEffect(() => {
+        listAccounts().then(setAccounts).catch(() => setAccounts([]));
+    }, []);
+
+    return (
+        <select value={value} onChange={e => onChange(e.target.value)} aria-label={t('account')}>
+            <option value="">{t('default')}</option>
+            {accounts.map(a => (
+                <option key={a.name} value={a.name}>
+                    {a.channelTitle ? `${a.name} (${a.channelTitle})` : a.name}
+                </option>
+            ))}
+        </select>
+    );
+};
+
+export default AccountSelector;
@@ -106,8 +106,14 @@ const BatchProcessor: React.FC = () => {
         setAnnouncement(`Upload... ${pct}%`);
       }
     } else {
-      await generateBatchUpload({ files, ...options });
+      const batch = await generateBatchUpload({ files, ...options });
       setProgress(100);
+      if (batch.held.length) {
+        setUploading(false);
+        setAnnouncement('');
+        notify('Batch upload paused', `${batch.results.length} uploaded, ${batch.held.length} queued until the quota resets`);
+        return;
+      }
     }
     setUploading(false);
     setAnnouncement('');
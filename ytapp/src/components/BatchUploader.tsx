@@ -50,12 +50,13 @@ const BatchUploader: React.FC = () => {
         setRunning(true);
         setAnnouncement('Upload... 0%');
         const prog: Record<string, number> = {};
+        let partial = 0;
         setProgressMap({});
         for (const file of files) {
             prog[file] = 0;
             setProgressMap({ ...prog });
             const meta = csvMap[file] || {};
-            await uploadVideo(
+            const result = await uploadVideo(
                 {
                     file,
                     title: meta.title || title || undefined,
@@ -71,10 +72,14 @@ const BatchUploader: React.FC = () => {
                     setAnnouncement(`Upload... ${prog[file]}%`);
                 },
             );
+            if (result.partial) partial++;
         }
         setRunning(false);
         setAnnouncement('');
-        notify('Batch upload complete', `${files.length} video(s) uploaded`);
+        notify(
+            'Batch upload complete',
+            `${files.length} video(s) uploaded` + (partial ? `, ${partial} with failed post-upload steps` : ''),
+        );
     };
 
     return (
@@ -5,15 +5,17 @@ import { fetchPlaylists, Playlist } from '../features/youtube';
 interface PlaylistSelectorProps {
     value: string;
     onChange: (id: string) => void;
+    /** Account whose playlists are listed. */
+    account?: string;
 }
 
-const PlaylistSelector: React.FC<PlaylistSelectorProps> = ({ value, onChange }) => {
+const PlaylistSelector: React.FC<PlaylistSelectorProps> = ({ value, onChange, account }) => {
     const { t } = useTranslation();
     const [playlists, setPlaylists] = useState<Playlist[]>([]);
 
     useEffect(() => {
-        fetchPlaylists().then(setPlaylists).catch(() => setPlaylists([]));
-    }, []);
+        fetchPlaylists(account).then(setPlaylists).catch(() => setPlaylists([]));
+    }, [account]);
 
     return (
         <select value={value} onChange={e => onChange(e.target.value)} aria-label={t('playlist')}>
@@ -14,7 +14,9 @@ import {
   QueueProgress,
   listenNotify,
   QueueNotify,
+  formatBytes,
 } from '../features/queue';
+import { getQuotaStatus, QuotaStatus } from '../features/youtube';
 import { notify } from '../utils/notify';
 
 const QueuePage: React.FC = () => {
@@ -22,9 +24,11 @@ const QueuePage: React.FC = () => {
   const [jobs, setJobs] = useState<any[]>([]);
   const [progressMap, setProgressMap] = useState<Record<number, number>>({});
   const [dragIndex, setDragIndex] = useState<number | null>(null);
+  const [quota, setQuota] = useState<QuotaStatus | null>(null);
 
   const refresh = () => {
     listJobs().then(setJobs);
+    getQuotaStatus().then(setQuota).catch(() => setQuota(null));
   };
 
   useEffect(() => {
@@ -39,7 +43,8 @@ const QueuePage: React.FC = () => {
       setProgressMap(m => ({ ...m, [p.index]: p.progress }));
     }).then(u => { progUn = u; });
     listenNotify((n: QueueNotify) => {
-      notify('Queue', n.success ? 'Job completed' : 'Job failed');
+      const status = !n.success ? 'Job failed' : n.partial ? 'Job completed with failed post-upload steps' : 'Job completed';
+      notify('Queue', n.render ? `${status}: ${n.render.outputs.join(', ')}` : status);
       refresh();
     }).then(u => { notifyUn = u; });
     return () => {
@@ -52,6 +57,15 @@ const QueuePage: React.FC = () => {
   return (
     <div>
       <h2>{t('queue')}</h2>
+      {quota && (
+        <p title={Object.entries(quota.methods).map(([m, u]) => `${m}: ${u}`).join('\n') || undefined}>
+          {t('quota_headroom', {
+            remaining: quota.remaining,
+            limit: quota.limit,
+            reset: new Date(quota.resetsAt).toLocaleTimeString(),
+          })}
+        </p>
+      )}
       <button onClick={() => runQueue().then(refresh)}>{t('process_queue')}</button>
       <button onClick={() => pauseQueue().then(refresh)} aria-label={t('pause')}>
         {t('pause')}
@@ -78,6 +92,27 @@ const QueuePage: React.FC = () => {
           <span>{JSON.stringify(j.job)}</span>
           <span>{j.status}</span>
           <span>{j.retries}</span>
+          {j.estimate && (
+            <span title={`${t('scratch')}: ${formatBytes(j.estimate.intermediateBytes)}`}>
+              ~{formatBytes(j.estimate.outputBytes)}
+            </span>
+          )}
+          {j.uploadSession && (
+            <span title={j.uploadSession.file}>
+              {t('upload_resumable', {
+                sent: formatBytes(j.uploadSession.offset),
+                total: formatBytes(j.uploadSession.size),
+              })}
+            </span>
+          )}
+          {j.error && (
+            <span
+              className="error"
+              title={[j.error.hint, ...(j.error.ffmpeg?.lines ?? [])].filter(Boolean).join('\n') || undefined}
+            >
+              {j.error.message}
+            </span>
+          )}
           {j.status === 'running' && (
             <progress value={progressMap[i] || 0} max={100} />
           )}
@@ -31,6 +31,7 @@ const SettingsPage: React.FC = () => {
     const [modelSize, setModelSize] = useState('base');
     const [output, setOutput] = useState('');
     const [maxRetries, setMaxRetries] = useState(3);
+    const [quotaDailyLimit, setQuotaDailyLimit] = useState(10000);
     const [width, setWidth] = useState(1920);
     const [height, setHeight] = useState(1080);
     const [fps, setFps] = useState(25);
@@ -60,6 +61,7 @@ const SettingsPage: React.FC = () => {
             setOutput(s.output || '');
             if (s.modelSize) setModelSize(s.modelSize);
             if (typeof s.maxRetries === 'number') setMaxRetries(s.maxRetries);
+            if (typeof s.quotaDailyLimit === 'number') setQuotaDailyLimit(s.quotaDailyLimit);
             if (typeof s.defaultWidth === 'number') setWidth(s.defaultWidth);
             if (typeof s.defaultHeight === 'number') setHeight(s.defaultHeight);
             if (typeof s.defaultFps === 'number') setFps(s.defaultFps);
@@ -89,6 +91,7 @@ const SettingsPage: React.FC = () => {
             output: output || undefined,
             modelSize,
             maxRetries,
+            quotaDailyLimit,
             defaultWidth: width,
             defaultHeight: height,
             defaultFps: fps,
@@ -257,6 +260,10 @@ const SettingsPage: React.FC = () => {
                 <label>{t('max_retries')}</label>
                 <input type="number" min="1" value={maxRetries} onChange={e => setMaxRetries(parseInt(e.target.value, 10) || 1)} />
             </div>
+            <div>
+                <label>{t('quota_daily_limit')}</label>
+                <input type="number" min="1" value={quotaDailyLimit} onChange={e => setQuotaDailyLimit(parseInt(e.target.value, 10) || 1)} />
+            </div>
             <div>
                 <label>{t('privacy')}</label>
                 <select value={defaultPrivacy} onChange={e => setDefaultPrivacy(e.target.value as any)}>
@@ -1,5 +1,6 @@
 import React, { useEffect, useState, useRef } from 'react';
 import { loadSrt, saveSrt } from '../features/transcription';
+import { errorMessage } from '../utils/errors';
 
 interface SubtitleEditorProps {
     file: string;
@@ -17,7 +18,7 @@ const SubtitleEditor: React.FC<SubtitleEditorProps> = ({ file, onClose, onSaved
     useEffect(() => {
         loadSrt(file)
             .then(setContent)
-            .catch(e => setError(String(e)));
+            .catch(e => setError(errorMessage(e)));
     }, [file]);
 
     useEffect(() => {
@@ -42,7 +43,7 @@ const SubtitleEditor: React.FC<SubtitleEditorProps> = ({ file, onClose, onSaved
             await saveSrt(file, content);
             onSaved(file);
         } catch (err: any) {
-            setError(String(err));
+            setError(errorMessage(err));
         }
     };
 
@@ -4,6 +4,7 @@ import { useTranslation } from 'react-i18next';
 import { transcribeAudio, cancelTranscription } from '../features/transcription';
 import { Language } from '../features/language';
 import { loadSettings } from '../features/settings';
+import { errorMessage } from '../utils/errors';
 
 interface TranscribeButtonProps {
     file: string;
@@ -35,7 +36,7 @@ const TranscribeButton: React.FC<TranscribeButtonProps> = ({ file, language, tar
             const result = await transcribeAudio({ file, language, translate: targets, modelSize });
             onComplete(result);
         } catch (err: any) {
-            setError(String(err));
+            setError(errorMessage(err));
         }
         setRunning(false);
     };
@@ -2,7 +2,7 @@
 // These functions wrap `generateVideo` from the processing module and optionally
 // report progress back to the caller.
 
-import { GenerateParams, generateVideo } from '../processing';
+import { GenerateParams, GenerateResult, generateVideo } from '../processing';
 export type ProgressCallback = (current: number, total: number, file: string) => void;
 
 export interface BatchOptions extends Omit<GenerateParams, 'file' | 'output'> {
@@ -22,7 +22,7 @@ function outputPath(file: string, dir?: string): string | undefined {
     return base ? `${dir}/${base}` : undefined;
 }
 
-async function generateOne(file: string, options: BatchOptions): Promise<string> {
+async function generateOne(file: string, options: BatchOptions): Promise<GenerateResult> {
     return generateVideo({
         file,
         output: outputPath(file, options.outputDir),
@@ -43,8 +43,8 @@ async function generateOne(file: string, options: BatchOptions): Promise<string>
 /**
  * Generate videos sequentially without progress callbacks.
  */
-export async function generateBatch(files: string[], options: BatchOptions): Promise<string[]> {
-    const results: string[] = [];
+export async function generateBatch(files: string[], options: BatchOptions): Promise<GenerateResult[]> {
+    const results: GenerateResult[] = [];
     for (const f of files) {
         results.push(await generateOne(f, options));
     }
@@ -58,8 +58,8 @@ export async function generateBatchWithProgress(
     files: string[],
     options: BatchOptions,
     onProgress?: ProgressCallback,
-): Promise<string[]> {
-    const results: string[] = [];
+): Promise<GenerateResult[]> {
+    const results: GenerateResult[] = [];
     for (let i = 0; i < files.length; i++) {
         const file = files[i];
         onProgress?.(i, files.length, file);
@@ -0,0 +1,28 @@
+// Wrappers around the render cache commands.
+import { invoke } from '@tauri-apps/api/core';
+
+export interface CacheEntry {
+  name: string;
+  /** `segment` for intro/outro conversions, `output` for finished videos. */
+  kind: string;
+  bytes: number;
+  /** RFC 3339 timestamp of the last hit or store. */
+  lastUsed: string;
+}
+
+export interface CacheInfo {
+  dir: string;
+  totalBytes: number;
+  maxBytes: number;
+  entries: CacheEntry[];
+}
+
+/** List cached renders, most recently used first. */
+export async function getCacheInfo(): Promise<CacheInfo> {
+  return await invoke('cache_info');
+}
+
+/** Delete every cached render. Resolves to the number of bytes freed. */
+export async function clearCache(): Promise<number> {
+  return await invoke('cache_clear');
+}
@@ -9,6 +9,37 @@ export async function verifyDependencies(): Promise<void> {
   await invoke('verify_dependencies');
 }
 
+export type FfmpegFeature = 'render' | 'captions' | 'bookends' | 'image_watermark' | 'text_watermark';
+
+export interface FeatureStatus {
+  feature: FfmpegFeature;
+  available: boolean;
+  /** Missing components such as `filter subtitles`. */
+  missing: string[];
+}
+
+/** Version and feature support of the installed ffmpeg. */
+export interface FfmpegCapabilities {
+  version?: string;
+  /** Unknown for builds without a release number, such as git snapshots. */
+  versionSupported?: boolean;
+  minVersion: string;
+  features: FeatureStatus[];
+}
+
+/**
+ * Probe the installed ffmpeg. The result is cached by the backend unless
+ * `refresh` is set.
+ */
+export async function getFfmpegCapabilities(refresh = false): Promise<FfmpegCapabilities> {
+  return await invoke('ffmpeg_capabilities', { refresh });
+}
+
+/** Whether `feature` is usable according to `caps`. Unknown means usable. */
+export function featureAvailable(caps: FfmpegCapabilities | null, feature: FfmpegFeature): boolean {
+  return caps?.features.find(f => f.feature === feature)?.available ?? true;
+}
+
 /**
  * Ensure native dependencies like FFmpeg are installed. Prompts the user to
  * run the provided installation script when verification fails.
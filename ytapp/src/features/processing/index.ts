@@ -1,14 +1,15 @@
 // Wrapper around Tauri commands related to video generation.
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
-import type { CaptionOptions, GenerateParams } from '../../schema';
-export type { CaptionOptions, GenerateParams } from '../../schema';
+import type { CaptionOptions, Chapter, GenerateParams } from '../../schema';
+export type { CaptionOptions, Chapter, GenerateParams, Rendition } from '../../schema';
 
 export type ProgressCallback = (progress: number) => void;
 export type CancelCallback = () => void;
 
 /**
- * Generate a video from an audio file using the backend.
+ * Generate a video from an audio file using the backend. Resolves with the
+ * primary output and every rendition produced.
  *
  * @param params     Options that control video generation.
  * @param onProgress Optional callback receiving progress percentage.
@@ -17,7 +18,7 @@ export async function generateVideo(
   params: GenerateParams,
   onProgress?: ProgressCallback,
   onCancel?: CancelCallback,
-): Promise<string> {
+): Promise<GenerateResult> {
     let unlisten: (() => void) | undefined;
     let cancelListen: (() => void) | undefined;
     const cleanup = () => {
@@ -31,7 +32,7 @@ export async function generateVideo(
         }
     };
 
-    return new Promise<string>(async (resolve, reject) => {
+    return new Promise<GenerateResult>(async (resolve, reject) => {
         if (onProgress) {
             unlisten = await listen<number>('generate_progress', e => {
                 if (typeof e.payload === 'number') onProgress(e.payload);
@@ -43,7 +44,7 @@ export async function generateVideo(
             reject(new Error('canceled'));
         });
         try {
-            const result: string = await invoke('generate_video', params as any);
+            const result: GenerateResult = await invoke('generate_video', params as any);
             cleanup();
             resolve(result);
         } catch (err) {
@@ -52,3 +53,65 @@ export async function generateVideo(
         }
     });
 }
+
+export interface GenerateResult {
+  /** Path of the primary output. */
+  output: string;
+  /** Every file produced by the job, primary output first. */
+  outputs: string[];
+  /** Whether the still-image fast path was used for the main section. */
+  fastPath: boolean;
+  /** Whether the outputs were restored from the render cache. */
+  cached: boolean;
+  /** Wall-clock seconds spent rendering. */
+  renderSeconds: number;
+  /** Seconds of media rendered per wall-clock second. */
+  speed: number;
+  /** Chapters embedded in the outputs. */
+  chapters: Chapter[];
+  /** Description for YouTube with chapter timestamp lines appended. */
+  description?: string;
+}
+
+export interface PreviewOptions {
+  /** Offset in seconds where the excerpt starts. */
+  start?: number;
+  /** Length of the excerpt in seconds. Defaults to 10. */
+  length?: number;
+  /** Return a PNG still instead of an MP4 clip. */
+  still?: boolean;
+}
+
+/**
+ * Render a short low resolution excerpt of the main section.
+ * Resolves with the path of the cached MP4 or PNG.
+ */
+export async function generatePreview(params: GenerateParams, opts: PreviewOptions = {}): Promise<string> {
+    return await invoke('generate_preview', { params, ...opts });
+}
+
+export interface PlanStep {
+  program: string;
+  args: string[];
+  /** POSIX shell command line, ready to paste into a terminal. */
+  line: string;
+}
+
+export interface RenderPlan {
+  steps: PlanStep[];
+  /** Intermediate files in the scratch workspace, in creation order. */
+  tempFiles: string[];
+  /** Concat lists and ffmetadata documents the render writes. */
+  files: { path: string; contents: string }[];
+  /** Intermediate files moved into place, usually as a final output. */
+  moves: { from: string; to: string }[];
+  outputs: string[];
+}
+
+/**
+ * Resolve `params` and list the ffmpeg/ffprobe commands a render would run,
+ * without rendering anything.
+ */
+export async function generatePlan(params: GenerateParams): Promise<RenderPlan> {
+    return await invoke('generate_plan', { params });
+}
@@ -1,16 +1,59 @@
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
-import { GenerateParams } from './processing';
+import { GenerateParams, GenerateResult } from './processing';
+import type { AppError } from '../utils/errors';
 
 export type QueueJob =
   | { Generate: { params: GenerateParams; dest: string } }
   | { GenerateUpload: { params: GenerateParams; dest: string; thumbnail?: string } };
 
+export interface SizeEstimate {
+  /** Peak size of intermediate files in the scratch directory. */
+  intermediateBytes: number;
+  /** Combined size of all rendered outputs. */
+  outputBytes: number;
+}
+
+/** Categorized ffmpeg failure with the last meaningful stderr lines. */
+export interface FfmpegFailure {
+  cause: 'missing_codec' | 'unknown_filter' | 'invalid_font' | 'file_not_found' | 'libass_missing' | 'unknown';
+  summary: string;
+  lines: string[];
+  exitCode?: number;
+}
+
 export interface QueueItem {
+  /** Stable id; the position changes as jobs are removed or moved. */
+  id: number;
   job: QueueJob;
   status: 'pending' | 'running' | 'failed' | 'completed';
   retries: number;
-  error?: string;
+  error?: AppError;
+  estimate?: SizeEstimate;
+  /** Interrupted resumable upload, continued when the job is retried. */
+  uploadSession?: UploadSession;
+}
+
+export interface UploadSession {
+  uri: string;
+  file: string;
+  size: number;
+  modified: number;
+  /** Bytes the server confirmed so far. */
+  offset: number;
+  created: string;
+}
+
+/** Format a byte count like `1.2 GB`. */
+export function formatBytes(bytes: number): string {
+  const units = ['B', 'KB', 'MB', 'GB', 'TB'];
+  let value = bytes;
+  let unit = 0;
+  while (value >= 1024 && unit < units.length - 1) {
+    value /= 1024;
+    unit++;
+  }
+  return unit === 0 ? `${bytes} B` : `${value.toFixed(1)} ${units[unit]}`;
 }
 
 export interface QueueProgress {
@@ -96,7 +139,11 @@ export async function listenQueue(onChange: () => void): Promise<() => void> {
 export interface QueueNotify {
   index: number;
   success: boolean;
+  /** Uploaded, but some post-upload steps failed. */
+  partial: boolean;
   error?: string;
+  /** What the render produced, when the job rendered. */
+  render?: GenerateResult;
 }
 
 export async function listenNotify(onNotify: (n: QueueNotify) => void): Promise<() => void> {
@@ -1,5 +1,6 @@
 // Load and save persistent application settings via Tauri.
 import { invoke } from '@tauri-apps/api/core';
+import type { EncodingOptions } from '../../schema';
 
 export interface Settings {
     intro?: string;
@@ -27,6 +28,17 @@ export interface Settings {
     defaultFps?: number;
     defaultPrivacy?: string;
     defaultPlaylistId?: string;
+    encoding?: EncodingOptions;
+    /** Directory for per-job intermediate files. Defaults to the system temp dir. */
+    scratchDir?: string;
+    /** Render cache location. Defaults to the app cache dir. */
+    cacheDir?: string;
+    /** Render cache size cap in bytes; 0 disables caching. */
+    cacheMaxBytes?: number;
+    /** Log every ffmpeg/ffprobe command line of a render before it runs. */
+    logPlans?: boolean;
+    /** Daily YouTube API quota of the OAuth project. Defaults to 10,000 units. */
+    quotaDailyLimit?: number;
     theme?: string;
 }
 
@@ -6,6 +6,10 @@ export interface WatchParams extends Omit<GenerateParams, 'file' | 'output'> {
   autoUpload?: boolean;
   thumbnail?: string;
   recursive?: boolean;
+  /** Use ID3 tags and cover art of new files: fill unset fields, prefer them, or ignore them. */
+  sourceMetadata?: 'fill' | 'prefer' | 'off';
+  /** File extensions that start a job. Defaults to mp3, wav, m4a, flac and aac. */
+  extensions?: string[];
 }
 
 export async function watchDirectory(dir: string, options: WatchParams): Promise<void> {
@@ -2,9 +2,11 @@
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 import { GenerateParams } from '../processing';
+import type { CaptionTrack, VideoMetadata } from '../../schema';
+import type { AppError } from '../../utils/errors';
 export type { GenerateParams } from '../processing';
 
-export interface UploadOptions {
+export interface UploadOptions extends VideoMetadata {
     file: string;
     title?: string;
     description?: string;
@@ -13,6 +15,43 @@ export interface UploadOptions {
     thumbnail?: string;
     privacy?: string;
     playlistId?: string;
+    /** Caption tracks uploaded after the video. */
+    captionTracks?: CaptionTrack[];
+    /** Account to upload with. Defaults to the default account. */
+    account?: string;
+}
+
+/** Work done on a video after it was inserted. */
+export type PostStep =
+    | { kind: 'thumbnail'; file: string }
+    | { kind: 'playlist'; playlistId: string }
+    | { kind: 'caption'; track: CaptionTrack }
+    | { kind: 'synthetic_media'; value: boolean; status: Record<string, unknown> };
+
+export interface PostTask {
+    step: PostStep;
+    status: 'pending' | 'done' | 'failed';
+    attempts: number;
+    error?: AppError;
+}
+
+export interface UploadResult {
+    videoId: string;
+    /** Account the video was uploaded with; the default account when unset. */
+    account?: string;
+    steps: PostTask[];
+    /** The video was uploaded but at least one step failed. */
+    partial: boolean;
+}
+
+/** Outcome of a batch upload that may have stopped at the daily quota. */
+export interface BatchUploadResult {
+    results: UploadResult[];
+    /**
+     * Files left when the quota ran out. `generateBatchUpload` queues them,
+     * and the queue starts them after the quota resets.
+     */
+    held: string[];
 }
 
 export interface UploadBatchOptions extends Omit<UploadOptions, 'file'> {
@@ -26,7 +65,7 @@ export async function uploadVideoWithProgress(
     opts: UploadOptions,
     onProgress: ProgressCallback,
     onCancel?: CancelCallback,
-): Promise<string> {
+): Promise<UploadResult> {
     const unlisten = await listen<number>('upload_progress', e => {
         if (typeof e.payload === 'number') onProgress(e.payload);
     });
@@ -46,7 +85,7 @@ export async function uploadVideo(
     opts: UploadOptions,
     onProgress?: ProgressCallback,
     onCancel?: CancelCallback,
-): Promise<string> {
+): Promise<UploadResult> {
     if (onProgress) {
         return uploadVideoWithProgress(opts, onProgress, onCancel);
     }
@@ -60,7 +99,7 @@ export async function uploadVideos(
     opts: UploadBatchOptions,
     onProgress?: ProgressCallback,
     onCancel?: CancelCallback,
-): Promise<string[]> {
+): Promise<BatchUploadResult> {
     if (onProgress) {
         const unlisten = await listen<number>('upload_progress', e => {
             if (typeof e.payload === 'number') onProgress(e.payload);
@@ -79,7 +118,7 @@ export async function uploadVideos(
 /**
  * Generate a video and upload it directly.
  */
-export async function generateUpload(params: GenerateParams, onCancel?: CancelCallback): Promise<string> {
+export async function generateUpload(params: GenerateParams, onCancel?: CancelCallback): Promise<UploadResult> {
     const cancelListen = onCancel ? await listen('upload_canceled', () => onCancel()) : undefined;
     try {
         return await invoke('generate_upload', params as any);
@@ -96,7 +135,7 @@ export interface BatchGenerateParams extends Omit<GenerateParams, 'file' | 'outp
 /**
  * Generate and upload multiple videos in sequence.
  */
-export async function generateBatchUpload(params: BatchGenerateParams, onCancel?: CancelCallback): Promise<string[]> {
+export async function generateBatchUpload(params: BatchGenerateParams, onCancel?: CancelCallback): Promise<BatchUploadResult> {
     const cancelListen = onCancel ? await listen('upload_canceled', () => onCancel()) : undefined;
     try {
         return await invoke('generate_batch_upload', params as any);
@@ -106,17 +145,108 @@ export async function generateBatchUpload(params: BatchGenerateParams, onCancel?
 }
 
 /**
- * Trigger OAuth sign-in for YouTube.
+ * Uploaded videos whose post-upload steps have not all succeeded.
+ */
+export async function listIncompleteUploads(): Promise<UploadResult[]> {
+    return await invoke('upload_incomplete');
+}
+
+/**
+ * Re-run only the failed post-upload steps of a video, as the account that
+ * uploaded it.
+ */
+export async function retryUploadSteps(videoId: string): Promise<UploadResult> {
+    return await invoke('upload_retry_steps', { videoId });
+}
+
+export interface QuotaStatus {
+    /** Quota day in Pacific time, e.g. `2024-03-04`. */
+    day: string;
+    limit: number;
+    used: number;
+    remaining: number;
+    resetsAt: string;
+    /** The API refused a call for quota today. */
+    exhausted: boolean;
+    /** Uploads wait for the reset because the channel hit its upload limit. */
+    uploadLimited: boolean;
+    /** Units spent today per API method. */
+    methods: Record<string, number>;
+}
+
+/**
+ * YouTube API quota used today and the headroom left.
+ */
+export async function getQuotaStatus(): Promise<QuotaStatus> {
+    return await invoke('quota_status');
+}
+
+/**
+ * Trigger OAuth sign-in for YouTube. With `device`, the device flow is used
+ * and its code is delivered through `onDeviceCode`.
+ */
+export async function signIn(device?: boolean): Promise<void> {
+    await invoke('youtube_sign_in', { device });
+}
+
+/** Code to enter at the verification URL during a device sign-in. */
+export interface DeviceCode {
+    userCode: string;
+    verificationUrl: string;
+}
+
+export async function onDeviceCode(cb: (code: DeviceCode) => void): Promise<() => void> {
+    return await listen<DeviceCode>('youtube_device_code', e => cb(e.payload));
+}
+
+/**
+ * Import a token bundle exported on another machine.
  */
-export async function signIn(): Promise<void> {
-    await invoke('youtube_sign_in');
+export async function importTokens(file: string, account?: string): Promise<void> {
+    await invoke('youtube_import_tokens', { file, account });
 }
 
 /**
- * Sign out and remove stored YouTube tokens.
+ * Export an account's tokens unencrypted to `file`, which must not exist yet.
  */
-export async function signOut(): Promise<void> {
-    await invoke('youtube_sign_out');
+export async function exportTokens(file: string, account?: string): Promise<void> {
+    await invoke('youtube_export_tokens', { file, account });
+}
+
+/**
+ * Revoke stored YouTube tokens at Google and delete them. Without an
+ * account, every account is signed out. `force` deletes the tokens even
+ * when they cannot be revoked.
+ */
+export async function signOut(account?: string, force?: boolean): Promise<void> {
+    await invoke('youtube_sign_out', { account, force });
+}
+
+/** Stored tokens for one set of scopes. */
+export interface TokenStatus {
+    scopes: string[];
+    expiresAt?: string;
+    expired: boolean;
+    hasRefreshToken: boolean;
+    /** Set when the tokens were checked with Google. */
+    valid?: boolean;
+}
+
+export interface AccountStatus {
+    account: string;
+    tokens: TokenStatus[];
+}
+
+export interface SignInStatus {
+    signedIn: boolean;
+    accounts: AccountStatus[];
+}
+
+/**
+ * Describe the stored tokens, optionally checking them with Google.
+ */
+export async function getSignInStatus(account?: string, validate?: boolean): Promise<SignInStatus> {
+    return await invoke('youtube_sign_in_status', { account, validate });
 }
 
 /**
@@ -126,11 +256,51 @@ export async function isSignedIn(): Promise<boolean> {
     return await invoke('youtube_is_signed_in');
 }
 
+/**
+ * Unlock the token store with a passphrase for the rest of the session.
+ */
+export async function unlockTokenStore(passphrase: string): Promise<void> {
+    await invoke('token_unlock', { passphrase });
+}
+
+/**
+ * Re-encrypt the token store under a new passphrase.
+ */
+export async function rotateTokenKey(newPassphrase: string): Promise<void> {
+    await invoke('token_rotate_key', { newPassphrase });
+}
+
 export interface Playlist {
     id: string;
     title: string;
 }
 
-export async function fetchPlaylists(): Promise<Playlist[]> {
-    return await invoke('list_playlists');
+export async function fetchPlaylists(account?: string): Promise<Playlist[]> {
+    return await invoke('list_playlists', { account });
+}
+
+/** A named YouTube sign-in and the channel it posts to. */
+export interface Account {
+    name: string;
+    channelId?: string;
+    channelTitle?: string;
+}
+
+export async function listAccounts(): Promise<Account[]> {
+    return await invoke('account_list');
+}
+
+/**
+ * Sign in a new named account and resolve its channel.
+ */
+export async function addAccount(name: string, device?: boolean): Promise<Account> {
+    return await invoke('account_add', { name, device });
+}
+
+/**
+ * Forget a named account and revoke and delete its tokens. `force` deletes
+ * the tokens even when they cannot be revoked.
+ */
+export async function removeAccount(name: string, force?: boolean): Promise<void> {
+    await invoke('account_remove', { name, force });
 }
@@ -8,7 +8,80 @@ export interface CaptionOptions {
   background?: string;
 }
 
-export interface Profile {
+/** A chapter marker, written into the MP4 and the YouTube description. */
+export interface Chapter {
+  /** Start time in seconds from the beginning of the main section. */
+  start: number;
+  title: string;
+}
+
+/** Text drawn over the video, e.g. a channel handle or episode number. */
+export interface TextWatermark {
+  text: string;
+  font?: string;
+  fontPath?: string;
+  size?: number;
+  color?: string;
+  boxColor?: string;
+  shadowColor?: string;
+  shadowOffset?: number;
+}
+
+/** YouTube video settings beyond title, description and tags. */
+export interface VideoMetadata {
+  /** Numeric YouTube category, e.g. `"22"` for People & Blogs. */
+  categoryId?: string;
+  /** BCP-47 language of the title and description. */
+  defaultLanguage?: string;
+  /** BCP-47 language spoken in the video. */
+  defaultAudioLanguage?: string;
+  /** Sent as `selfDeclaredMadeForKids`. */
+  madeForKids?: boolean;
+  license?: 'youtube' | 'creativeCommon';
+  embeddable?: boolean;
+  publicStatsViewable?: boolean;
+  /** Whether subscribers are notified of the upload. Defaults to true. */
+  notifySubscribers?: boolean;
+  /** RFC 3339 timestamp or `YYYY-MM-DD`. */
+  recordingDate?: string;
+  /** Discloses realistic altered or synthetic content. */
+  containsSyntheticMedia?: boolean;
+}
+
+/** A subtitle file uploaded as a YouTube caption track instead of being burned in. */
+export interface CaptionTrack {
+  /** SRT or WebVTT file. */
+  file: string;
+  /** BCP-47 language code such as `en` or `pt-BR`. */
+  language: string;
+  name?: string;
+  /** Upload as a draft that is not visible to viewers. */
+  draft?: boolean;
+}
+
+/** x264/AAC encoder settings shared by every segment of a render. */
+export interface EncodingOptions {
+  preset?: string;
+  crf?: number;
+  videoBitrate?: string;
+  keyframeInterval?: number;
+  tune?: string;
+  audioBitrate?: string;
+  sampleRate?: number;
+}
+
+/** An additional output rendered from the same audio as the main video. */
+export interface Rendition {
+  name?: string;
+  output?: string;
+  width?: number;
+  height?: number;
+  fps?: number;
+  bitrate?: string;
+  captionOptions?: CaptionOptions;
+}
+
+export interface Profile extends VideoMetadata {
   captions?: string;
   captionOptions?: CaptionOptions;
   background?: string;
@@ -18,6 +91,16 @@ export interface Profile {
   watermarkPosition?: string;
   watermarkOpacity?: number;
   watermarkScale?: number;
+  watermarkText?: TextWatermark;
+  /** Pixels from the left, negative pixels from the right, or a percentage like `50%`. */
+  watermarkX?: string;
+  /** Pixels from the top, negative pixels from the bottom, or a percentage like `50%`. */
+  watermarkY?: string;
+  watermarkMargin?: number;
+  /** Seconds into the main section when the watermark appears. */
+  watermarkStart?: number;
+  /** Seconds into the main section when the watermark disappears. */
+  watermarkEnd?: number;
   width?: number;
   height?: number;
   fps?: number;
@@ -28,9 +111,15 @@ export interface Profile {
   thumbnail?: string;
   privacy?: string;
   playlistId?: string;
+  /** Account to upload with. Defaults to the default account. */
+  account?: string;
+  renditions?: Rendition[];
+  encoding?: EncodingOptions;
+  artist?: string;
+  chapterSource?: 'manual' | 'audio' | 'transcript' | 'none';
 }
 
-export interface GenerateParams {
+export interface GenerateParams extends VideoMetadata {
   file: string;
   output?: string;
   captions?: string;
@@ -42,6 +131,16 @@ export interface GenerateParams {
   watermarkPosition?: string;
   watermarkOpacity?: number;
   watermarkScale?: number;
+  watermarkText?: TextWatermark;
+  /** Pixels from the left, negative pixels from the right, or a percentage like `50%`. */
+  watermarkX?: string;
+  /** Pixels from the top, negative pixels from the bottom, or a percentage like `50%`. */
+  watermarkY?: string;
+  watermarkMargin?: number;
+  /** Seconds into the main section when the watermark appears. */
+  watermarkStart?: number;
+  /** Seconds into the main section when the watermark disappears. */
+  watermarkEnd?: number;
   width?: number;
   height?: number;
   fps?: number;
@@ -52,4 +151,16 @@ export interface GenerateParams {
   thumbnail?: string;
   privacy?: string;
   playlistId?: string;
+  /** Account to upload with. Defaults to the default account. */
+  account?: string;
+  /** Caption tracks uploaded with the video. */
+  captionTracks?: CaptionTrack[];
+  renditions?: Rendition[];
+  encoding?: EncodingOptions;
+  artist?: string;
+  season?: number;
+  episode?: number;
+  chapters?: Chapter[];
+  /** Where chapters come from. Defaults to `chapters` when given, otherwise the source audio. */
+  chapterSource?: 'manual' | 'audio' | 'transcript' | 'none';
 }
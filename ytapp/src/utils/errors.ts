@@ -0,0 +1,41 @@
+import type { FfmpegFailure } from '../features/queue';
+
+/** Structured error returned by every backend command. */
+export interface AppError {
+  kind:
+    | 'not_signed_in'
+    | 'auth'
+    | 'token_store'
+    | 'ffmpeg_missing'
+    | 'ffmpeg'
+    | 'invalid_input'
+    | 'not_found'
+    | 'disk_full'
+    | 'quota'
+    | 'upload_limit'
+    | 'network'
+    | 'upload'
+    | 'rejected'
+    | 'config'
+    | 'canceled'
+    | 'io'
+    | 'internal';
+  message: string;
+  /** Underlying error messages, outermost first. */
+  sources?: string[];
+  /** What the user can do about it. */
+  hint?: string;
+  ffmpeg?: FfmpegFailure;
+}
+
+export function isAppError(err: unknown): err is AppError {
+  return typeof err === 'object' && err !== null && 'kind' in err && 'message' in err;
+}
+
+/** Message for display, with the hint appended when there is one. */
+export function errorMessage(err: unknown): string {
+  if (isAppError(err)) {
+    return err.hint ? `${err.message}. ${err.hint}` : err.message;
+  }
+  return String(err);
+}